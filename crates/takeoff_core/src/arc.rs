@@ -0,0 +1,358 @@
+use crate::Point;
+use std::f64::consts::PI;
+
+/// Largest angle covered by one straight piece when flattening an arc
+const FLATTEN_STEP: f64 = PI / 36.0;
+
+/// A segment between two points that is either straight or a circular arc
+///
+/// The curvature is stored as a DXF-style bulge: the tangent of a quarter of
+/// the included angle. A bulge of 0 is a straight segment, 1 is a half circle,
+/// and a positive bulge sweeps counterclockwise from `start` to `end`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArcSegment {
+  pub start: Point,
+  pub end: Point,
+  pub bulge: f64,
+}
+
+impl ArcSegment {
+  pub fn new(start: Point, end: Point, bulge: f64) -> Self {
+    Self { start, end, bulge }
+  }
+
+  /// Create the arc that starts at `start`, passes through `through` and ends at `end`
+  pub fn from_three_points(start: Point, through: Point, end: Point) -> Self {
+    Self::new(start, end, bulge_from_three_points(start, through, end))
+  }
+
+  pub fn is_straight(&self) -> bool {
+    self.bulge == 0.0 || !self.bulge.is_finite() || self.chord() == 0.0
+  }
+
+  /// Straight-line distance between the end points
+  pub fn chord(&self) -> f64 {
+    self.start.distance_to(&self.end)
+  }
+
+  /// Signed included angle in radians (positive is counterclockwise)
+  pub fn included_angle(&self) -> f64 {
+    4.0 * self.bulge.atan()
+  }
+
+  /// Radius of the arc, or `None` for a straight segment
+  pub fn radius(&self) -> Option<f64> {
+    if self.is_straight() {
+      return None;
+    }
+    let half_angle = self.included_angle().abs() / 2.0;
+    Some(self.chord() / (2.0 * half_angle.sin()))
+  }
+
+  /// Center of the arc, or `None` for a straight segment
+  pub fn center(&self) -> Option<Point> {
+    if self.is_straight() {
+      return None;
+    }
+    let chord = self.chord();
+    let (dx, dy) = (
+      (self.end.x - self.start.x) / chord,
+      (self.end.y - self.start.y) / chord,
+    );
+    let offset = (chord / 2.0) * (1.0 - self.bulge * self.bulge) / (2.0 * self.bulge);
+    Some(Point::new(
+      (self.start.x + self.end.x) / 2.0 - dy * offset,
+      (self.start.y + self.end.y) / 2.0 + dx * offset,
+    ))
+  }
+
+  /// Length along the arc
+  pub fn length(&self) -> f64 {
+    match self.radius() {
+      Some(radius) => radius * self.included_angle().abs(),
+      None => self.chord(),
+    }
+  }
+
+  /// Signed area between the chord and the arc
+  ///
+  /// Adding this to the shoelace area of a ring gives the exact area of a ring
+  /// with curved edges, whichever way the ring is wound.
+  pub fn segment_area(&self) -> f64 {
+    match self.radius() {
+      Some(radius) => {
+        let angle = self.included_angle().abs();
+        self.bulge.signum() * radius * radius / 2.0 * (angle - angle.sin())
+      }
+      None => 0.0,
+    }
+  }
+
+  /// Point on the segment at parameter `t` in `[0, 1]`
+  pub fn point_at(&self, t: f64) -> Point {
+    match (self.center(), self.radius()) {
+      (Some(center), Some(radius)) => {
+        let start_angle = (self.start.y - center.y).atan2(self.start.x - center.x);
+        let angle = start_angle + self.included_angle() * t;
+        Point::new(
+          center.x + radius * angle.cos(),
+          center.y + radius * angle.sin(),
+        )
+      }
+      _ => Point::new(
+        self.start.x + (self.end.x - self.start.x) * t,
+        self.start.y + (self.end.y - self.start.y) * t,
+      ),
+    }
+  }
+
+  /// Approximate the segment with straight pieces
+  ///
+  /// Returns the start point and any intermediate points, but not the end point,
+  /// so consecutive segments can be chained without duplicates.
+  pub fn flatten(&self) -> Vec<Point> {
+    if self.is_straight() {
+      return vec![self.start];
+    }
+    let steps = (self.included_angle().abs() / FLATTEN_STEP).ceil().max(1.0) as usize;
+    let mut points = vec![self.start];
+    for i in 1..steps {
+      points.push(self.point_at(i as f64 / steps as f64));
+    }
+    points
+  }
+
+  /// Closest point on the segment to a point
+  pub fn closest_point(&self, point: &Point) -> Point {
+    match (self.center(), self.radius()) {
+      (Some(center), Some(radius)) => {
+        let start_angle = (self.start.y - center.y).atan2(self.start.x - center.x);
+        let point_angle = (point.y - center.y).atan2(point.x - center.x);
+        let sweep = self.included_angle();
+        let delta = ((point_angle - start_angle) * sweep.signum()).rem_euclid(2.0 * PI);
+        if delta <= sweep.abs() && point.distance_to(&center) > 0.0 {
+          Point::new(
+            center.x + radius * point_angle.cos(),
+            center.y + radius * point_angle.sin(),
+          )
+        } else if point.distance_to(&self.start) <= point.distance_to(&self.end) {
+          self.start
+        } else {
+          self.end
+        }
+      }
+      _ => closest_point_on_line_segment(point, &self.start, &self.end),
+    }
+  }
+
+  /// Split the segment at the point on it closest to `point`
+  ///
+  /// Both halves of an arc keep its circle.
+  pub fn split_at(&self, point: &Point) -> (ArcSegment, ArcSegment) {
+    let split = self.closest_point(point);
+    let (first, second) = match self.center() {
+      Some(center) => {
+        let sweep = self.included_angle();
+        let start_angle = (self.start.y - center.y).atan2(self.start.x - center.x);
+        let split_angle = (split.y - center.y).atan2(split.x - center.x);
+        let delta = ((split_angle - start_angle) * sweep.signum()).rem_euclid(2.0 * PI);
+        let first = (delta * sweep.signum()).clamp(-sweep.abs(), sweep.abs());
+        ((first / 4.0).tan(), ((sweep - first) / 4.0).tan())
+      }
+      None => (0.0, 0.0),
+    };
+    (
+      ArcSegment::new(self.start, split, first),
+      ArcSegment::new(split, self.end, second),
+    )
+  }
+
+  /// Shortest distance from a point to the segment
+  pub fn distance_to_point(&self, point: &Point) -> f64 {
+    point.distance_to(&self.closest_point(point))
+  }
+
+  /// Points where this segment crosses another
+  ///
+  /// Arcs are flattened first, so crossings on arcs are approximate.
+  pub fn intersections(&self, other: &ArcSegment) -> Vec<Point> {
+    let pieces = |segment: &ArcSegment| {
+      let mut points = segment.flatten();
+      points.push(segment.end);
+      points
+    };
+    let (a, b) = (pieces(self), pieces(other));
+    let mut crossings = Vec::new();
+    for a in a.windows(2) {
+      for b in b.windows(2) {
+        if let Some(point) = line_segment_intersection(&a[0], &a[1], &b[0], &b[1]) {
+          crossings.push(point);
+        }
+      }
+    }
+    crossings
+  }
+}
+
+/// Bulge of the arc from `start` through `through` to `end`
+///
+/// Collinear points give a straight segment (bulge 0).
+pub fn bulge_from_three_points(start: Point, through: Point, end: Point) -> f64 {
+  let (ax, ay) = (start.x - through.x, start.y - through.y);
+  let (bx, by) = (end.x - through.x, end.y - through.y);
+  let cross_chord =
+    (end.x - start.x) * (through.y - start.y) - (end.y - start.y) * (through.x - start.x);
+  if cross_chord == 0.0 {
+    return 0.0;
+  }
+  // The inscribed angle at `through` is half of the angle outside the arc
+  let inscribed = (ax * by - ay * bx).abs().atan2(ax * bx + ay * by);
+  let magnitude = 1.0 / (inscribed / 2.0).tan();
+  if cross_chord < 0.0 {
+    magnitude
+  } else {
+    -magnitude
+  }
+}
+
+/// Closest point on the straight segment between `start` and `end`
+pub fn closest_point_on_line_segment(point: &Point, start: &Point, end: &Point) -> Point {
+  let (dx, dy) = (end.x - start.x, end.y - start.y);
+  let length_sq = dx * dx + dy * dy;
+  if length_sq == 0.0 {
+    return *start;
+  }
+  let t = (((point.x - start.x) * dx + (point.y - start.y) * dy) / length_sq).clamp(0.0, 1.0);
+  Point::new(start.x + t * dx, start.y + t * dy)
+}
+
+/// Shortest distance from a point to the straight segment between `start` and `end`
+pub fn distance_to_line_segment(point: &Point, start: &Point, end: &Point) -> f64 {
+  point.distance_to(&closest_point_on_line_segment(point, start, end))
+}
+
+/// Point where two straight segments cross, if they do
+///
+/// Parallel and collinear segments have no single crossing and return `None`.
+pub fn line_segment_intersection(a1: &Point, a2: &Point, b1: &Point, b2: &Point) -> Option<Point> {
+  let (rx, ry) = (a2.x - a1.x, a2.y - a1.y);
+  let (sx, sy) = (b2.x - b1.x, b2.y - b1.y);
+  let denominator = rx * sy - ry * sx;
+  if denominator == 0.0 {
+    return None;
+  }
+  let (qx, qy) = (b1.x - a1.x, b1.y - a1.y);
+  let t = (qx * sy - qy * sx) / denominator;
+  let u = (qx * ry - qy * rx) / denominator;
+  ((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u))
+    .then(|| Point::new(a1.x + t * rx, a1.y + t * ry))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{a} != {b}");
+  }
+
+  #[test]
+  fn test_half_circle() {
+    let arc = ArcSegment::new(Point::new(0.0, 0.0), Point::new(2.0, 0.0), 1.0);
+    assert_close(arc.radius().unwrap(), 1.0);
+    assert_close(arc.length(), PI);
+    assert_close(arc.segment_area(), PI / 2.0);
+    let center = arc.center().unwrap();
+    assert_close(center.x, 1.0);
+    assert_close(center.y, 0.0);
+    // Counterclockwise from (0,0) to (2,0) about (1,0) passes below the chord
+    let mid = arc.point_at(0.5);
+    assert_close(mid.x, 1.0);
+    assert_close(mid.y, -1.0);
+  }
+
+  #[test]
+  fn test_three_point_arc() {
+    let start = Point::new(0.0, 0.0);
+    let end = Point::new(2.0, 0.0);
+    let arc = ArcSegment::from_three_points(start, Point::new(1.0, -1.0), end);
+    assert_close(arc.bulge, 1.0);
+    let arc = ArcSegment::from_three_points(start, Point::new(1.0, 1.0), end);
+    assert_close(arc.bulge, -1.0);
+    assert_close(arc.point_at(0.5).y, 1.0);
+
+    // A quarter circle of radius 10 centred at the origin
+    let quarter = ArcSegment::from_three_points(
+      Point::new(10.0, 0.0),
+      Point::new(10.0 * (PI / 4.0).cos(), 10.0 * (PI / 4.0).sin()),
+      Point::new(0.0, 10.0),
+    );
+    assert_close(quarter.included_angle(), PI / 2.0);
+    assert_close(quarter.length(), 5.0 * PI);
+    let center = quarter.center().unwrap();
+    assert_close(center.x, 0.0);
+    assert_close(center.y, 0.0);
+  }
+
+  #[test]
+  fn test_collinear_is_straight() {
+    let arc = ArcSegment::from_three_points(
+      Point::new(0.0, 0.0),
+      Point::new(1.0, 0.0),
+      Point::new(3.0, 0.0),
+    );
+    assert!(arc.is_straight());
+    assert_close(arc.length(), 3.0);
+    assert_eq!(arc.flatten(), vec![Point::new(0.0, 0.0)]);
+  }
+
+  #[test]
+  fn test_distance_to_point() {
+    let arc = ArcSegment::new(Point::new(0.0, 0.0), Point::new(2.0, 0.0), 1.0);
+    assert_close(arc.distance_to_point(&Point::new(1.0, -3.0)), 2.0);
+    // Above the chord is outside the sweep, so the nearest point is an end
+    assert_close(arc.distance_to_point(&Point::new(1.0, 1.0)), 2.0_f64.sqrt());
+  }
+
+  #[test]
+  fn test_closest_point_and_intersections() {
+    let arc = ArcSegment::new(Point::new(0.0, 0.0), Point::new(2.0, 0.0), 1.0);
+    let closest = arc.closest_point(&Point::new(1.0, -3.0));
+    assert_close(closest.x, 1.0);
+    assert_close(closest.y, -1.0);
+
+    let horizontal = ArcSegment::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 0.0);
+    let vertical = ArcSegment::new(Point::new(4.0, -5.0), Point::new(4.0, 5.0), 0.0);
+    assert_eq!(
+      horizontal.intersections(&vertical),
+      vec![Point::new(4.0, 0.0)]
+    );
+    let parallel = ArcSegment::new(Point::new(0.0, 1.0), Point::new(10.0, 1.0), 0.0);
+    assert!(horizontal.intersections(&parallel).is_empty());
+
+    // A vertical line through the middle of the half circle crosses its lowest point
+    let through = ArcSegment::new(Point::new(1.0, 0.5), Point::new(1.0, -2.0), 0.0);
+    let crossings = arc.intersections(&through);
+    assert_eq!(crossings.len(), 1);
+    assert!((crossings[0].y + 1.0).abs() < 1e-2);
+  }
+
+  #[test]
+  fn test_split_at() {
+    let arc = ArcSegment::new(Point::new(0.0, 0.0), Point::new(2.0, 0.0), 1.0);
+    let (first, second) = arc.split_at(&Point::new(1.0, -3.0));
+    assert_close(first.end.x, 1.0);
+    assert_close(first.end.y, -1.0);
+    assert_close(first.length() + second.length(), arc.length());
+    assert_close(first.center().unwrap().x, 1.0);
+    assert_close(second.radius().unwrap(), 1.0);
+
+    let line = ArcSegment::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 0.0);
+    let (first, second) = line.split_at(&Point::new(4.0, 2.0));
+    assert_eq!(first.end, Point::new(4.0, 0.0));
+    assert_eq!(
+      second,
+      ArcSegment::new(Point::new(4.0, 0.0), Point::new(10.0, 0.0), 0.0)
+    );
+  }
+}
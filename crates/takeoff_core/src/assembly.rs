@@ -0,0 +1,264 @@
+use crate::condition::ConditionTotals;
+use crate::{Dimension, Formula, FormulaInputs, Measurement, Unit};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// Decimal places base quantities are rounded to, so that conversion noise like
+/// `31.999999999` can't push `ceil` up a whole piece
+const INPUT_PLACES: i32 = 6;
+
+fn settle(value: f64) -> f64 {
+  let factor = 10f64.powi(INPUT_PLACES);
+  (value * factor).round() / factor
+}
+
+/// One quantity derived from a base measurement, e.g. studs at 16" o.c.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssemblyItem {
+  pub id: String,
+  pub name: String,
+  /// Formula over the base measurement's quantities, e.g. `ceil(length * 12 / 16) + 1`
+  ///
+  /// It can use `length`, `area`, `perimeter`, `volume`, `count`, `height`,
+  /// `width` and `depth`, numbers, `+ - * / ^`, parentheses and `ceil`, `floor`,
+  /// `round`, `abs`, `min` and `max`.
+  pub formula: String,
+  /// Unit of the derived quantity for display, e.g. `LF`, `EA` or `gal`
+  pub unit: Option<String>,
+}
+
+/// Items that one measurement drives, e.g. track, studs, drywall and paint for a wall
+///
+/// Measurements use an assembly through the `assembly_id` of their metadata.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Assembly {
+  pub id: String,
+  pub name: String,
+  /// Unit the base quantities are given to formulas in; areas are in its square
+  pub display_unit: Unit,
+  /// Height of linear runs such as walls, so `area` is their length times it
+  pub height: Option<Dimension>,
+  /// Depth or thickness for measurements that have none of their own
+  pub depth: Option<Dimension>,
+  pub items: Vec<AssemblyItem>,
+}
+
+/// Quantity of one assembly item for one measurement
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DerivedItem {
+  pub measurement_id: String,
+  pub assembly_id: String,
+  pub item_id: String,
+  pub name: String,
+  /// `None` if the formula does not parse or gives no finite number for this
+  /// measurement, e.g. after dividing by a zero `count`
+  pub quantity: Option<f64>,
+  pub unit: Option<String>,
+}
+
+impl Assembly {
+  /// Parse every item's formula, in item order; `None` for one that does not parse
+  pub fn formulas(&self) -> Vec<Option<Formula>> {
+    self
+      .items
+      .iter()
+      .map(|item| Formula::parse(&item.formula))
+      .collect()
+  }
+
+  /// IDs of the items whose formula does not parse, given the parsed `formulas`
+  pub fn invalid_items(&self, formulas: &[Option<Formula>]) -> Vec<String> {
+    self
+      .items
+      .iter()
+      .zip(formulas)
+      .filter(|(_, formula)| formula.is_none())
+      .map(|(item, _)| item.id.clone())
+      .collect()
+  }
+
+  /// The base quantities of a measurement, as formulas see them
+  ///
+  /// `count` is 1 for a count marker. Volumes are in the cube of the display unit.
+  /// Values are rounded to a millionth of a unit.
+  pub fn inputs(&self, measurement: &Measurement) -> FormulaInputs {
+    let unit = self.display_unit;
+    let mut totals = ConditionTotals::new(self.id.clone(), unit);
+    totals.add(measurement, self.height, self.depth);
+    let length_of = |dimension: Option<Dimension>| dimension.map_or(0.0, |d| d.convert(unit));
+    let depth = measurement.depth().or(self.depth);
+    let width = measurement.width();
+    FormulaInputs {
+      length: settle(totals.length),
+      area: settle(totals.area),
+      perimeter: settle(totals.perimeter),
+      // The totals give volume in cubic yards or meters; formulas get the display unit
+      volume: settle(match measurement {
+        Measurement::Area { .. } => totals.area * length_of(depth),
+        Measurement::Linear { .. } => totals.length * length_of(width) * length_of(depth),
+        Measurement::Count { .. } => 0.0,
+      }),
+      count: totals.count as f64,
+      height: settle(length_of(self.height)),
+      width: settle(length_of(width)),
+      depth: settle(length_of(depth)),
+    }
+  }
+
+  /// Evaluate every item for a measurement, in item order
+  ///
+  /// This parses the formulas on every call; to evaluate many measurements,
+  /// parse them once with `formulas` and use `evaluate_parsed`.
+  pub fn evaluate(&self, measurement: &Measurement) -> Vec<DerivedItem> {
+    self.evaluate_parsed(measurement, &self.formulas())
+  }
+
+  /// Evaluate every item for a measurement with formulas from `formulas`
+  ///
+  /// An item whose formula is missing or gives no finite number has no
+  /// quantity; the other items are still evaluated.
+  pub fn evaluate_parsed(
+    &self,
+    measurement: &Measurement,
+    formulas: &[Option<Formula>],
+  ) -> Vec<DerivedItem> {
+    let inputs = self.inputs(measurement);
+    self
+      .items
+      .iter()
+      .enumerate()
+      .map(|(index, item)| DerivedItem {
+        measurement_id: measurement.id().to_string(),
+        assembly_id: self.id.clone(),
+        item_id: item.id.clone(),
+        name: item.name.clone(),
+        quantity: formulas
+          .get(index)
+          .and_then(Option::as_ref)
+          .and_then(|formula| formula.evaluate(&inputs)),
+        unit: item.unit.clone(),
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{MeasurementMetadata, Point, Polyline, Scale};
+
+  fn item(id: &str, formula: &str, unit: &str) -> AssemblyItem {
+    AssemblyItem {
+      id: id.to_string(),
+      name: id.to_string(),
+      formula: formula.to_string(),
+      unit: Some(unit.to_string()),
+    }
+  }
+
+  fn stud_wall() -> Assembly {
+    Assembly {
+      id: "stud_wall".to_string(),
+      name: "Interior stud wall".to_string(),
+      display_unit: Unit::Feet,
+      height: Some(Dimension::new(9.0, Unit::Feet)),
+      depth: None,
+      items: vec![
+        item("track", "length * 2", "LF"),
+        item("studs", "ceil(length * 12 / 16) + 1", "EA"),
+        item("drywall", "area * 2", "SF"),
+        item("paint", "area * 2 / 350", "gal"),
+      ],
+    }
+  }
+
+  #[test]
+  fn test_wall_assembly() {
+    // 20 ft then 10 ft at 10 pixels per foot
+    let mut wall = Measurement::Linear {
+      id: "w1".to_string(),
+      line: None,
+      polyline: Some(Polyline::new(vec![
+        Point::new(0.0, 0.0),
+        Point::new(200.0, 0.0),
+        Point::new(200.0, 100.0),
+      ])),
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: Some(MeasurementMetadata {
+        assembly_id: Some("stud_wall".to_string()),
+        ..MeasurementMetadata::default()
+      }),
+    };
+    let assembly = stud_wall();
+    let formulas = assembly.formulas();
+    assert!(assembly.invalid_items(&formulas).is_empty());
+    let assert_quantities = |wall: &Measurement, expected: [f64; 4]| {
+      let derived = assembly.evaluate_parsed(wall, &formulas);
+      for (item, expected) in derived.iter().zip(expected) {
+        let quantity = item.quantity.unwrap();
+        assert!(
+          (quantity - expected).abs() < 1e-9,
+          "{}: {quantity} != {expected}",
+          item.item_id,
+        );
+      }
+    };
+    assert_quantities(&wall, [60.0, 24.0, 540.0, 540.0 / 350.0]);
+
+    // Editing the wall changes what it drives
+    wall.set_vertex(2, Point::new(200.0, 40.0));
+    assert_quantities(&wall, [48.0, 19.0, 432.0, 432.0 / 350.0]);
+  }
+
+  #[test]
+  fn test_invalid_formulas() {
+    let mut assembly = stud_wall();
+    assembly
+      .items
+      .push(item("bad", "length * std::process::exit(1)", "EA"));
+    assembly
+      .items
+      .push(item("per_marker", "length / count", "EA"));
+    assert_eq!(
+      assembly.invalid_items(&assembly.formulas()),
+      vec!["bad".to_string()]
+    );
+
+    // A failing item has no quantity but leaves the others alone
+    let marker = Measurement::Count {
+      id: "c1".to_string(),
+      point: Point::new(0.0, 0.0),
+      metadata: None,
+    };
+    let derived = assembly.evaluate(&marker);
+    assert_eq!(derived.len(), 6);
+    assert_eq!(derived[0].quantity, Some(0.0));
+    assert_eq!(derived[4].quantity, None);
+    assert_eq!(derived[4].measurement_id, "c1");
+    // A count marker has no length to divide
+    assert_eq!(derived[5].quantity, Some(0.0));
+
+    let wall = Measurement::Linear {
+      id: "w1".to_string(),
+      line: Some(crate::Line::new(Point::new(0.0, 0.0), Point::new(0.0, 0.0))),
+      polyline: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    // Zero length over a zero count is not a number
+    let derived = assembly.evaluate(&wall);
+    assert_eq!(derived[5].quantity, None);
+    assert_eq!(derived[1].quantity, Some(1.0));
+  }
+}
@@ -0,0 +1,147 @@
+use crate::measurement::generate_id;
+use crate::{Measurement, Polygon};
+use geo::{BooleanOps, OpType};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// How two area measurements are combined
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BooleanOp {
+  /// Everything covered by either area
+  Union,
+  /// The first area minus the second
+  Difference,
+  /// Only what both areas cover
+  Intersection,
+  /// What exactly one of the areas covers
+  Xor,
+}
+
+impl From<BooleanOp> for OpType {
+  fn from(op: BooleanOp) -> Self {
+    match op {
+      BooleanOp::Union => OpType::Union,
+      BooleanOp::Difference => OpType::Difference,
+      BooleanOp::Intersection => OpType::Intersection,
+      BooleanOp::Xor => OpType::Xor,
+    }
+  }
+}
+
+/// Combine two area measurements into new ones, one per separate piece
+///
+/// Pieces keep any holes and take the scale, display unit, depth, pitch and
+/// metadata of `a`. Arcs are flattened. Returns `None` unless both are areas; an
+/// empty result means nothing is left (e.g. two areas that don't overlap, intersected).
+pub fn boolean_op(a: &Measurement, b: &Measurement, op: BooleanOp) -> Option<Vec<Measurement>> {
+  let Measurement::Area {
+    scale,
+    display_unit,
+    depth,
+    pitch,
+    metadata,
+    ..
+  } = a
+  else {
+    return None;
+  };
+  let result = a.to_polygon()?.boolean_op(&b.to_polygon()?, op.into());
+  Some(
+    result
+      .iter()
+      .map(|piece| Measurement::Area {
+        id: generate_id("area"),
+        rectangle: None,
+        polygon: Some(Polygon::from_geo(piece)),
+        circle: None,
+        ellipse: None,
+        scale: *scale,
+        display_unit: *display_unit,
+        depth: *depth,
+        pitch: *pitch,
+        metadata: metadata.clone(),
+      })
+      .collect(),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Point, Rectangle, Scale, Unit};
+
+  fn room(start: Point, end: Point) -> Measurement {
+    Measurement::Area {
+      id: "room".to_string(),
+      rectangle: Some(Rectangle::new(start, end)),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    }
+  }
+
+  fn total_area(pieces: &[Measurement]) -> f64 {
+    pieces.iter().map(|m| m.pixel_area().unwrap()).sum()
+  }
+
+  #[test]
+  fn test_overlapping_rectangles() {
+    let a = room(Point::new(0.0, 0.0), Point::new(100.0, 100.0));
+    let b = room(Point::new(50.0, 50.0), Point::new(150.0, 150.0));
+
+    let union = boolean_op(&a, &b, BooleanOp::Union).unwrap();
+    assert_eq!(union.len(), 1);
+    assert!((total_area(&union) - 17500.0).abs() < 1e-6);
+    assert!((union[0].real_area().unwrap() - 175.0).abs() < 1e-6);
+
+    let difference = boolean_op(&a, &b, BooleanOp::Difference).unwrap();
+    assert!((total_area(&difference) - 7500.0).abs() < 1e-6);
+
+    let intersection = boolean_op(&a, &b, BooleanOp::Intersection).unwrap();
+    assert!((total_area(&intersection) - 2500.0).abs() < 1e-6);
+
+    let xor = boolean_op(&a, &b, BooleanOp::Xor).unwrap();
+    assert!((total_area(&xor) - 15000.0).abs() < 1e-6);
+  }
+
+  #[test]
+  fn test_holes_and_pieces() {
+    let floor = room(Point::new(0.0, 0.0), Point::new(100.0, 100.0));
+    let carpet = room(Point::new(25.0, 25.0), Point::new(75.0, 75.0));
+    let remaining = boolean_op(&floor, &carpet, BooleanOp::Difference).unwrap();
+    assert_eq!(remaining.len(), 1);
+    let Measurement::Area {
+      polygon: Some(polygon),
+      ..
+    } = &remaining[0]
+    else {
+      panic!("Difference should be a polygon");
+    };
+    assert_eq!(polygon.holes().len(), 1);
+    assert!((remaining[0].pixel_area().unwrap() - 7500.0).abs() < 1e-6);
+
+    // A band across the middle splits the floor in two
+    let band = room(Point::new(-10.0, 40.0), Point::new(110.0, 60.0));
+    let halves = boolean_op(&floor, &band, BooleanOp::Difference).unwrap();
+    assert_eq!(halves.len(), 2);
+    assert_ne!(halves[0].id(), halves[1].id());
+
+    let apart = room(Point::new(500.0, 500.0), Point::new(600.0, 600.0));
+    assert!(boolean_op(&floor, &apart, BooleanOp::Intersection)
+      .unwrap()
+      .is_empty());
+
+    let marker = Measurement::Count {
+      id: "c".to_string(),
+      point: Point::new(1.0, 1.0),
+      metadata: None,
+    };
+    assert!(boolean_op(&floor, &marker, BooleanOp::Union).is_none());
+  }
+}
@@ -0,0 +1,135 @@
+use crate::{ArcSegment, Point, Polygon};
+use geo::Polygon as GeoPolygon;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{PI, SQRT_2};
+
+/// Bulge of a quarter circle, tan(22.5°)
+const QUARTER_BULGE: f64 = SQRT_2 - 1.0;
+
+/// Represents a circle by its center and radius
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Circle {
+  pub center: Point,
+  pub radius: f64,
+}
+
+impl Circle {
+  pub fn new(center: Point, radius: f64) -> Self {
+    Self { center, radius }
+  }
+
+  /// Create the circle through three points, or `None` if they are in a line
+  pub fn from_three_points(a: Point, b: Point, c: Point) -> Option<Self> {
+    let center = ArcSegment::from_three_points(a, b, c).center()?;
+    Some(Self::new(center, center.distance_to(&a)))
+  }
+
+  pub fn diameter(&self) -> f64 {
+    2.0 * self.radius
+  }
+
+  /// Calculate the area of the circle
+  pub fn area(&self) -> f64 {
+    PI * self.radius * self.radius
+  }
+
+  /// Calculate the length around the circle
+  pub fn circumference(&self) -> f64 {
+    2.0 * PI * self.radius
+  }
+
+  /// Points on the circle at 0°, 90°, 180° and 270°
+  pub fn quadrants(&self) -> Vec<Point> {
+    let Point { x, y } = self.center;
+    let r = self.radius;
+    vec![
+      Point::new(x + r, y),
+      Point::new(x, y + r),
+      Point::new(x - r, y),
+      Point::new(x, y - r),
+    ]
+  }
+
+  /// Editing handles: the center, then the four quadrant points
+  pub fn handles(&self) -> Vec<Point> {
+    let mut handles = vec![self.center];
+    handles.extend(self.quadrants());
+    handles
+  }
+
+  /// Drag a handle, numbered as in `handles`
+  ///
+  /// The center moves the circle; a quadrant point sets the radius to its
+  /// distance from the center.
+  pub fn set_handle(&mut self, index: usize, point: Point) -> bool {
+    match index {
+      0 => self.center = point,
+      1..=4 => self.radius = self.center.distance_to(&point),
+      _ => return false,
+    }
+    true
+  }
+
+  /// The four quarter arcs around the circle, counter-clockwise from 0°
+  pub fn segments(&self) -> Vec<ArcSegment> {
+    self.to_arc_polygon().segments()
+  }
+
+  /// The circle as a polygon of four quarter arcs through the quadrant points
+  pub fn to_arc_polygon(&self) -> Polygon {
+    Polygon::with_bulges(self.quadrants(), vec![QUARTER_BULGE; 4])
+  }
+
+  /// Convert to a `geo` polygon, flattening the arcs
+  pub fn to_polygon(&self) -> GeoPolygon<f64> {
+    self.to_arc_polygon().to_polygon()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{a} != {b}");
+  }
+
+  #[test]
+  fn test_circle() {
+    let circle = Circle::new(Point::new(10.0, 10.0), 5.0);
+    assert_close(circle.area(), 25.0 * PI);
+    assert_close(circle.circumference(), 10.0 * PI);
+    // The quarter arcs are exact
+    let polygon = circle.to_arc_polygon();
+    assert_close(polygon.area(), circle.area());
+    assert_close(polygon.perimeter(), circle.circumference());
+
+    let through = Circle::from_three_points(
+      Point::new(15.0, 10.0),
+      Point::new(10.0, 15.0),
+      Point::new(5.0, 10.0),
+    )
+    .unwrap();
+    assert!(through.center.distance_to(&circle.center) < 1e-9);
+    assert_close(through.radius, 5.0);
+    assert!(Circle::from_three_points(
+      Point::new(0.0, 0.0),
+      Point::new(1.0, 1.0),
+      Point::new(2.0, 2.0)
+    )
+    .is_none());
+  }
+
+  #[test]
+  fn test_handles() {
+    let mut circle = Circle::new(Point::new(0.0, 0.0), 5.0);
+    assert_eq!(circle.handles().len(), 5);
+    assert!(circle.set_handle(2, Point::new(6.0, 8.0)));
+    assert_close(circle.radius, 10.0);
+    assert!(circle.set_handle(0, Point::new(1.0, 1.0)));
+    assert_eq!(circle.handles()[1], Point::new(11.0, 1.0));
+    assert!(!circle.set_handle(5, Point::new(0.0, 0.0)));
+  }
+}
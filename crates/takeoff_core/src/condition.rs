@@ -0,0 +1,277 @@
+use crate::{CostRates, Dimension, Measurement, Unit, UnitUtils, VolumeUnit};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// Kind of measurement a condition is taken off with
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionKind {
+  /// Lines and polylines, e.g. walls or footings
+  Linear,
+  /// Rectangles, polygons, circles and ellipses, e.g. slabs or flooring
+  Area,
+  /// Count markers, e.g. fixtures or outlets
+  Count,
+}
+
+impl ConditionKind {
+  /// The kind a measurement belongs to
+  pub fn of(measurement: &Measurement) -> Self {
+    match measurement {
+      Measurement::Linear { .. } => ConditionKind::Linear,
+      Measurement::Area { .. } => ConditionKind::Area,
+      Measurement::Count { .. } => ConditionKind::Count,
+    }
+  }
+}
+
+/// An item of work that measurements are taken off for, e.g. `5/8" Type X drywall`
+///
+/// Measurements join a condition through the `condition_id` of their metadata.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+  pub id: String,
+  pub name: String,
+  /// Trade the work belongs to, e.g. `Drywall`
+  pub trade: Option<String>,
+  /// CSI MasterFormat section, e.g. `09 21 16`
+  pub code: Option<String>,
+  pub kind: ConditionKind,
+  /// Unit the totals are reported in
+  pub display_unit: Unit,
+  /// CSS color measurements of this condition are drawn with, e.g. `#ff8800`
+  pub color: Option<String>,
+  /// Height of linear runs such as walls, so their length also gives an area
+  pub height: Option<Dimension>,
+  /// Depth or thickness for measurements that have none of their own
+  pub depth: Option<Dimension>,
+  /// Prices for measurements of this condition
+  pub cost: Option<CostRates>,
+}
+
+impl Condition {
+  pub fn new(
+    id: impl Into<String>,
+    name: impl Into<String>,
+    kind: ConditionKind,
+    display_unit: Unit,
+  ) -> Self {
+    Self {
+      id: id.into(),
+      name: name.into(),
+      trade: None,
+      code: None,
+      kind,
+      display_unit,
+      color: None,
+      height: None,
+      depth: None,
+      cost: None,
+    }
+  }
+
+  /// Check if a measurement is of the kind this condition is taken off with
+  pub fn accepts(&self, measurement: &Measurement) -> bool {
+    ConditionKind::of(measurement) == self.kind
+  }
+}
+
+/// Quantities of all the measurements assigned to one condition
+///
+/// Lengths are in the condition's display unit, areas in its square and volumes
+/// in `volume_unit`.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionTotals {
+  pub condition_id: String,
+  /// Number of measurements assigned to the condition
+  pub measurements: u32,
+  pub unit: Unit,
+  /// Length of linear runs, along any pitch
+  pub length: f64,
+  /// Perimeter of areas, on plan
+  pub perimeter: f64,
+  /// Area of areas along any pitch, plus linear runs times the condition height
+  pub area: f64,
+  /// Volume of areas with a depth and linear runs with a width and depth
+  pub volume: f64,
+  pub volume_unit: VolumeUnit,
+  /// Number of count markers
+  pub count: u32,
+}
+
+impl ConditionTotals {
+  pub(crate) fn new(condition_id: String, unit: Unit) -> Self {
+    Self {
+      condition_id,
+      measurements: 0,
+      unit,
+      length: 0.0,
+      perimeter: 0.0,
+      area: 0.0,
+      volume: 0.0,
+      volume_unit: unit.volume_unit(),
+      count: 0,
+    }
+  }
+
+  /// Add the quantities of one measurement, using `height` for linear runs and
+  /// `depth` where the measurement has none of its own
+  pub(crate) fn add(
+    &mut self,
+    measurement: &Measurement,
+    height: Option<Dimension>,
+    depth: Option<Dimension>,
+  ) {
+    let unit = self.unit;
+    let depth = measurement.depth().or(depth);
+    self.measurements += 1;
+    match measurement {
+      Measurement::Linear { scale, .. } => {
+        let Some(length) = measurement.real_length_with_target(scale, &unit) else {
+          return;
+        };
+        self.length += length;
+        if let Some(height) = height {
+          self.area += length * height.convert(unit);
+        }
+        if let (Some(width), Some(depth)) = (measurement.width(), depth) {
+          self.volume += UnitUtils::length_to_volume(length, unit, width, depth, self.volume_unit);
+        }
+      }
+      Measurement::Area { scale, .. } => {
+        if let Some(perimeter) = measurement.pixel_perimeter() {
+          self.perimeter += scale.pixel_to_real_unit(perimeter, unit);
+        }
+        let Some(area) = measurement.real_area_with_target(scale, &unit) else {
+          return;
+        };
+        self.area += area;
+        if let Some(depth) = depth {
+          self.volume += UnitUtils::area_to_volume(area, unit, depth, self.volume_unit);
+        }
+      }
+      Measurement::Count { .. } => self.count += 1,
+    }
+  }
+}
+
+/// Total the measurements assigned to each condition, in the order of `conditions`
+///
+/// Measurements without a condition, or assigned to one not in the list, are
+/// left out. Conditions without measurements report zeros.
+pub fn condition_totals<'a>(
+  conditions: &[Condition],
+  measurements: impl IntoIterator<Item = &'a Measurement>,
+) -> Vec<ConditionTotals> {
+  let mut totals: Vec<ConditionTotals> = conditions
+    .iter()
+    .map(|c| ConditionTotals::new(c.id.clone(), c.display_unit))
+    .collect();
+  for measurement in measurements {
+    let Some(condition_id) = measurement.condition_id() else {
+      continue;
+    };
+    if let Some(index) = conditions.iter().position(|c| c.id == condition_id) {
+      let condition = &conditions[index];
+      totals[index].add(measurement, condition.height, condition.depth);
+    }
+  }
+  totals
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Line, MeasurementMetadata, Point, Rectangle, Scale};
+
+  fn assigned(condition_id: &str) -> Option<MeasurementMetadata> {
+    Some(MeasurementMetadata {
+      condition_id: Some(condition_id.to_string()),
+      ..MeasurementMetadata::default()
+    })
+  }
+
+  fn wall(length: f64, condition_id: &str) -> Measurement {
+    Measurement::Linear {
+      id: format!("wall_{length}"),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(length, 0.0))),
+      polyline: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: assigned(condition_id),
+    }
+  }
+
+  #[test]
+  fn test_totals_per_condition() {
+    let drywall = Condition {
+      height: Some(Dimension::new(9.0, Unit::Feet)),
+      ..Condition::new("drywall", "5/8\" Type X", ConditionKind::Linear, Unit::Feet)
+    };
+    let slab = Condition {
+      depth: Some(Dimension::new(4.0, Unit::Inches)),
+      ..Condition::new("slab", "4\" SOG", ConditionKind::Area, Unit::Feet)
+    };
+    let outlets = Condition::new("outlets", "Duplex outlet", ConditionKind::Count, Unit::Feet);
+    let unused = Condition::new("unused", "Unused", ConditionKind::Area, Unit::Meters);
+
+    let measurements = vec![
+      wall(100.0, "drywall"),
+      wall(50.0, "drywall"),
+      // 30 x 20 ft
+      Measurement::Area {
+        id: "slab".to_string(),
+        rectangle: Some(Rectangle::new(
+          Point::new(0.0, 0.0),
+          Point::new(300.0, 200.0),
+        )),
+        polygon: None,
+        circle: None,
+        ellipse: None,
+        scale: Scale::new(10.0, 1.0, Unit::Feet),
+        display_unit: Unit::Feet,
+        depth: None,
+        pitch: None,
+        metadata: assigned("slab"),
+      },
+      Measurement::Count {
+        id: "o1".to_string(),
+        point: Point::new(0.0, 0.0),
+        metadata: assigned("outlets"),
+      },
+      Measurement::Count {
+        id: "o2".to_string(),
+        point: Point::new(1.0, 0.0),
+        metadata: assigned("outlets"),
+      },
+      // Not in any known condition
+      wall(10.0, "missing"),
+      Measurement::Count {
+        id: "o3".to_string(),
+        point: Point::new(2.0, 0.0),
+        metadata: None,
+      },
+    ];
+    let totals = condition_totals(&[drywall, slab, outlets, unused], &measurements);
+    assert_eq!(totals.len(), 4);
+
+    assert_eq!(totals[0].measurements, 2);
+    assert!((totals[0].length - 15.0).abs() < 1e-9);
+    assert!((totals[0].area - 135.0).abs() < 1e-9);
+
+    assert!((totals[1].area - 600.0).abs() < 1e-9);
+    assert!((totals[1].perimeter - 100.0).abs() < 1e-9);
+    // 600 SF at 4" is 200 CF, 200/27 CY
+    assert_eq!(totals[1].volume_unit, VolumeUnit::CubicYards);
+    assert!((totals[1].volume - 200.0 / 27.0).abs() < 1e-5);
+
+    assert_eq!(totals[2].count, 2);
+    assert_eq!(totals[3].measurements, 0);
+    assert_eq!(totals[3].unit, Unit::Meters);
+  }
+}
@@ -21,6 +21,18 @@ impl Point {
     let dy = self.y - other.y;
     (dx * dx + dy * dy).sqrt()
   }
+
+  /// Min and max corners of the smallest axis-aligned box around some points
+  pub fn bounds<'a>(points: impl IntoIterator<Item = &'a Point>) -> Option<(Point, Point)> {
+    let mut points = points.into_iter();
+    let first = *points.next()?;
+    Some(points.fold((first, first), |(min, max), p| {
+      (
+        Point::new(min.x.min(p.x), min.y.min(p.y)),
+        Point::new(max.x.max(p.x), max.y.max(p.y)),
+      )
+    }))
+  }
 }
 
 impl From<Point> for Coord<f64> {
@@ -78,3 +90,15 @@ impl Transform {
     )
   }
 }
+
+/// Sine and cosine of an angle in degrees, exact for multiples of 90
+pub(crate) fn exact_sin_cos(degrees: f64) -> (f64, f64) {
+  let degrees = degrees.rem_euclid(360.0);
+  match degrees {
+    0.0 => (0.0, 1.0),
+    90.0 => (1.0, 0.0),
+    180.0 => (0.0, -1.0),
+    270.0 => (-1.0, 0.0),
+    _ => degrees.to_radians().sin_cos(),
+  }
+}
@@ -0,0 +1,529 @@
+use crate::condition::ConditionTotals;
+use crate::{Condition, ConditionKind, Measurement, Unit};
+use napi_derive::napi;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Decimal places quantities are rounded to before they are priced
+const QUANTITY_PLACES: u32 = 4;
+/// Decimal places money amounts are rounded to
+const MONEY_PLACES: u32 = 2;
+
+/// Which quantity of a measurement is priced
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CostBasis {
+  /// Length of linear runs, or the perimeter of areas
+  Length,
+  /// Area of areas, or linear runs times the condition height
+  Area,
+  /// Volume from the depth, and the width of linear runs
+  Volume,
+  /// Number of count markers
+  Count,
+}
+
+impl CostBasis {
+  /// The usual basis for a kind of measurement
+  pub fn for_kind(kind: ConditionKind) -> Self {
+    match kind {
+      ConditionKind::Linear => CostBasis::Length,
+      ConditionKind::Area => CostBasis::Area,
+      ConditionKind::Count => CostBasis::Count,
+    }
+  }
+}
+
+/// Prices for the work of a condition or a single measurement
+///
+/// Amounts are decimal strings such as `"12.50"` so they pass through JavaScript
+/// exactly. Factors are fractions: a `waste_factor` of `"0.10"` orders 10% extra
+/// material. Unset fields on a measurement fall back to its condition's rates.
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostRates {
+  /// Quantity priced; none means length, area or count by the measurement kind
+  pub basis: Option<CostBasis>,
+  /// Material price per unit of quantity
+  pub material_unit_price: Option<String>,
+  /// Labor cost per hour
+  pub labor_rate: Option<String>,
+  /// Units of quantity installed per labor hour
+  pub productivity: Option<String>,
+  /// Extra material as a fraction of the quantity, for offcuts and breakage
+  pub waste_factor: Option<String>,
+  /// Added to material and labor as a fraction, for overhead and profit
+  pub markup: Option<String>,
+}
+
+impl CostRates {
+  /// Fill unset fields from `fallback`
+  pub fn or(&self, fallback: &CostRates) -> CostRates {
+    let pick = |own: &Option<String>, other: &Option<String>| own.clone().or(other.clone());
+    CostRates {
+      basis: self.basis.or(fallback.basis),
+      material_unit_price: pick(&self.material_unit_price, &fallback.material_unit_price),
+      labor_rate: pick(&self.labor_rate, &fallback.labor_rate),
+      productivity: pick(&self.productivity, &fallback.productivity),
+      waste_factor: pick(&self.waste_factor, &fallback.waste_factor),
+      markup: pick(&self.markup, &fallback.markup),
+    }
+  }
+
+  /// Check that every amount set is a decimal number
+  pub fn is_valid(&self) -> bool {
+    [
+      &self.material_unit_price,
+      &self.labor_rate,
+      &self.productivity,
+      &self.waste_factor,
+      &self.markup,
+    ]
+    .into_iter()
+    .all(|value| parse(value).is_some())
+  }
+}
+
+/// Parse an optional decimal, treating unset as zero; `None` if it is not a number
+fn parse(value: &Option<String>) -> Option<Decimal> {
+  match value {
+    Some(text) => Decimal::from_str(text.trim()).ok(),
+    None => Some(Decimal::ZERO),
+  }
+}
+
+fn money(value: Decimal) -> Decimal {
+  let mut rounded =
+    value.round_dp_with_strategy(MONEY_PLACES, RoundingStrategy::MidpointAwayFromZero);
+  rounded.rescale(MONEY_PLACES);
+  rounded
+}
+
+/// Priced quantity of one measurement
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostLine {
+  pub measurement_id: String,
+  pub condition_id: Option<String>,
+  pub trade: Option<String>,
+  pub basis: CostBasis,
+  /// Quantity in `unit`, its square, its volume unit or a plain count by `basis`
+  pub quantity: String,
+  pub unit: Unit,
+  pub labor_hours: String,
+  pub material: String,
+  pub labor: String,
+  pub markup: String,
+  pub total: String,
+}
+
+/// Costs added up for a condition or a trade
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostGroup {
+  /// Condition ID or trade name; none for measurements without one
+  pub key: Option<String>,
+  /// Condition name, or the trade name again
+  pub name: Option<String>,
+  pub labor_hours: String,
+  pub material: String,
+  pub labor: String,
+  pub markup: String,
+  pub total: String,
+}
+
+/// Costs of a takeoff, line by line and grouped by condition and by trade
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CostSummary {
+  pub lines: Vec<CostLine>,
+  /// In the order conditions are listed, then measurements without a condition
+  pub by_condition: Vec<CostGroup>,
+  /// In the order trades first appear, then work without a trade
+  pub by_trade: Vec<CostGroup>,
+  pub total: String,
+}
+
+/// Exact amounts of a line or group
+#[derive(Debug, Clone, Copy)]
+struct Amounts {
+  labor_hours: Decimal,
+  material: Decimal,
+  labor: Decimal,
+  markup: Decimal,
+}
+
+impl Default for Amounts {
+  fn default() -> Self {
+    let zero = Decimal::new(0, MONEY_PLACES);
+    Self {
+      labor_hours: Decimal::ZERO,
+      material: zero,
+      labor: zero,
+      markup: zero,
+    }
+  }
+}
+
+impl Amounts {
+  /// Material, labor and markup; `price` and `add` make sure it fits
+  fn total(&self) -> Decimal {
+    self.material + self.labor + self.markup
+  }
+
+  fn checked_total(&self) -> Option<Decimal> {
+    self
+      .material
+      .checked_add(self.labor)?
+      .checked_add(self.markup)
+  }
+
+  /// Add another line's amounts; `None` if a sum or the total overflows
+  fn add(&mut self, other: &Amounts) -> Option<()> {
+    let sum = Amounts {
+      labor_hours: self.labor_hours.checked_add(other.labor_hours)?,
+      material: self.material.checked_add(other.material)?,
+      labor: self.labor.checked_add(other.labor)?,
+      markup: self.markup.checked_add(other.markup)?,
+    };
+    sum.checked_total()?;
+    *self = sum;
+    Some(())
+  }
+
+  fn group(&self, key: Option<String>, name: Option<String>) -> CostGroup {
+    CostGroup {
+      key,
+      name,
+      labor_hours: self.labor_hours.to_string(),
+      material: self.material.to_string(),
+      labor: self.labor.to_string(),
+      markup: self.markup.to_string(),
+      total: self.total().to_string(),
+    }
+  }
+}
+
+/// Price a quantity; `None` if a rate is not a number or an amount overflows
+///
+/// Waste adds to the material bought but not to the labor to install it. Each
+/// amount is rounded to cents, and the markup applies to the rounded amounts.
+fn price(quantity: Decimal, rates: &CostRates) -> Option<Amounts> {
+  let unit_price = parse(&rates.material_unit_price)?;
+  let labor_rate = parse(&rates.labor_rate)?;
+  let productivity = parse(&rates.productivity)?;
+  let waste = parse(&rates.waste_factor)?;
+  let markup = parse(&rates.markup)?;
+
+  let material = money(
+    quantity
+      .checked_mul(Decimal::ONE.checked_add(waste)?)?
+      .checked_mul(unit_price)?,
+  );
+  let labor_hours = match quantity.checked_div(productivity) {
+    Some(hours) if !productivity.is_zero() => hours.round_dp(QUANTITY_PLACES).normalize(),
+    _ => Decimal::ZERO,
+  };
+  let labor = money(labor_hours.checked_mul(labor_rate)?);
+  let amounts = Amounts {
+    labor_hours,
+    material,
+    labor,
+    markup: money(material.checked_add(labor)?.checked_mul(markup)?),
+  };
+  amounts.checked_total()?;
+  Some(amounts)
+}
+
+/// The quantity of a measurement on a basis, rounded for pricing
+fn quantity(
+  measurement: &Measurement,
+  basis: CostBasis,
+  unit: Unit,
+  condition: Option<&Condition>,
+) -> Decimal {
+  let mut totals = ConditionTotals::new(String::new(), unit);
+  totals.add(
+    measurement,
+    condition.and_then(|c| c.height),
+    condition.and_then(|c| c.depth),
+  );
+  let value = match basis {
+    CostBasis::Length => totals.length + totals.perimeter,
+    CostBasis::Area => totals.area,
+    CostBasis::Volume => totals.volume,
+    CostBasis::Count => totals.count as f64,
+  };
+  Decimal::from_f64(value)
+    .unwrap_or_default()
+    .round_dp(QUANTITY_PLACES)
+    .normalize()
+}
+
+fn display_unit(measurement: &Measurement) -> Unit {
+  match measurement {
+    Measurement::Linear { display_unit, .. } | Measurement::Area { display_unit, .. } => {
+      *display_unit
+    }
+    Measurement::Count { .. } => Unit::Feet,
+  }
+}
+
+/// Price every measurement that has rates of its own or through its condition
+///
+/// A measurement's own rates override its condition's field by field. Quantities
+/// are in the condition's display unit, or the measurement's without one.
+/// Returns `None` if any rate used is not a decimal number or an amount overflows.
+pub fn cost_summary<'a>(
+  conditions: &[Condition],
+  measurements: impl IntoIterator<Item = &'a Measurement>,
+) -> Option<CostSummary> {
+  let mut lines = Vec::new();
+  let mut by_condition: Vec<(Option<&Condition>, Amounts)> = conditions
+    .iter()
+    .map(|c| (Some(c), Amounts::default()))
+    .collect();
+  let mut unassigned = Amounts::default();
+  let mut by_trade: Vec<(Option<String>, Amounts)> = Vec::new();
+  let mut total = Amounts::default();
+
+  for measurement in measurements {
+    let condition_index = measurement
+      .condition_id()
+      .and_then(|id| conditions.iter().position(|c| c.id == id));
+    let condition = condition_index.map(|index| &conditions[index]);
+    let own = measurement.metadata().and_then(|m| m.cost.as_ref());
+    let rates = match (own, condition.and_then(|c| c.cost.as_ref())) {
+      (Some(own), Some(inherited)) => own.or(inherited),
+      (Some(rates), None) | (None, Some(rates)) => rates.clone(),
+      (None, None) => continue,
+    };
+
+    let kind = ConditionKind::of(measurement);
+    let basis = rates.basis.unwrap_or(CostBasis::for_kind(kind));
+    let unit = condition.map_or(display_unit(measurement), |c| c.display_unit);
+    let quantity = quantity(measurement, basis, unit, condition);
+    let amounts = price(quantity, &rates)?;
+    let trade = condition.and_then(|c| c.trade.clone());
+
+    match condition_index {
+      Some(index) => by_condition[index].1.add(&amounts)?,
+      None => unassigned.add(&amounts)?,
+    }
+    match by_trade.iter_mut().find(|(t, _)| *t == trade) {
+      Some((_, group)) => group.add(&amounts)?,
+      None => by_trade.push((trade.clone(), amounts)),
+    }
+    total.add(&amounts)?;
+    lines.push(CostLine {
+      measurement_id: measurement.id().to_string(),
+      condition_id: condition.map(|c| c.id.clone()),
+      trade,
+      basis,
+      quantity: quantity.to_string(),
+      unit,
+      labor_hours: amounts.labor_hours.to_string(),
+      material: amounts.material.to_string(),
+      labor: amounts.labor.to_string(),
+      markup: amounts.markup.to_string(),
+      total: amounts.total().to_string(),
+    });
+  }
+
+  let mut condition_groups: Vec<CostGroup> = by_condition
+    .into_iter()
+    .filter_map(|(c, amounts)| c.map(|c| amounts.group(Some(c.id.clone()), Some(c.name.clone()))))
+    .collect();
+  if lines.iter().any(|line| line.condition_id.is_none()) {
+    condition_groups.push(unassigned.group(None, None));
+  }
+  // Work without a trade goes last
+  by_trade.sort_by_key(|(trade, _)| trade.is_none());
+  Some(CostSummary {
+    lines,
+    by_condition: condition_groups,
+    by_trade: by_trade
+      .into_iter()
+      .map(|(trade, amounts)| amounts.group(trade.clone(), trade))
+      .collect(),
+    total: total.total().to_string(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Dimension, Line, MeasurementMetadata, Point, Rectangle, Scale};
+
+  fn rates(unit_price: &str) -> CostRates {
+    CostRates {
+      material_unit_price: Some(unit_price.to_string()),
+      ..CostRates::default()
+    }
+  }
+
+  fn metadata(condition_id: Option<&str>, cost: Option<CostRates>) -> Option<MeasurementMetadata> {
+    Some(MeasurementMetadata {
+      condition_id: condition_id.map(str::to_string),
+      cost,
+      ..MeasurementMetadata::default()
+    })
+  }
+
+  // 10 pixels per foot
+  fn wall(id: &str, feet: f64, metadata: Option<MeasurementMetadata>) -> Measurement {
+    Measurement::Linear {
+      id: id.to_string(),
+      line: Some(Line::new(
+        Point::new(0.0, 0.0),
+        Point::new(feet * 10.0, 0.0),
+      )),
+      polyline: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata,
+    }
+  }
+
+  #[test]
+  fn test_price_is_exact() {
+    // 0.1 + 0.2 style amounts stay exact
+    let paint = CostRates {
+      material_unit_price: Some("0.10".to_string()),
+      labor_rate: Some("45.50".to_string()),
+      productivity: Some("8".to_string()),
+      waste_factor: Some("0.10".to_string()),
+      markup: Some("0.15".to_string()),
+      ..CostRates::default()
+    };
+    let amounts = price(Decimal::from(30), &paint).unwrap();
+    assert_eq!(amounts.material.to_string(), "3.30");
+    assert_eq!(amounts.labor_hours.to_string(), "3.75");
+    assert_eq!(amounts.labor.to_string(), "170.63");
+    assert_eq!(amounts.markup.to_string(), "26.09");
+    assert_eq!(amounts.total().to_string(), "200.02");
+
+    assert!(price(Decimal::ONE, &rates("12,50")).is_none());
+
+    // Rates too large to multiply out give no price rather than a panic
+    let max = "79228162514264337593543950335";
+    assert!(price(Decimal::from(30), &rates(max)).is_none());
+    let overflowing = |rates: CostRates| price(Decimal::from(30), &rates).is_none();
+    assert!(overflowing(CostRates {
+      labor_rate: Some(max.to_string()),
+      productivity: Some("1".to_string()),
+      ..CostRates::default()
+    }));
+    assert!(overflowing(CostRates {
+      markup: Some(max.to_string()),
+      ..rates("1")
+    }));
+    assert!(overflowing(CostRates {
+      waste_factor: Some(max.to_string()),
+      ..rates("1")
+    }));
+    // Each line fits, but their sum does not
+    let half = rates("39614081257132168796771975168");
+    let walls = [
+      wall("a", 1.0, metadata(None, Some(half.clone()))),
+      wall("b", 1.0, metadata(None, Some(half))),
+    ];
+    assert!(cost_summary(&[], &walls[..1]).is_some());
+    assert!(cost_summary(&[], &walls).is_none());
+    assert!(!rates("abc").is_valid());
+    assert!(rates(" 12.50 ").is_valid());
+  }
+
+  #[test]
+  fn test_cost_summary() {
+    let drywall = Condition {
+      trade: Some("Drywall".to_string()),
+      height: Some(Dimension::new(10.0, Unit::Feet)),
+      cost: Some(CostRates {
+        basis: Some(CostBasis::Area),
+        ..rates("0.55")
+      }),
+      ..Condition::new("gwb", "5/8\" Type X", ConditionKind::Linear, Unit::Feet)
+    };
+    let slab = Condition {
+      trade: Some("Concrete".to_string()),
+      cost: Some(rates("6.25")),
+      ..Condition::new("sog", "4\" SOG", ConditionKind::Area, Unit::Feet)
+    };
+    let unpriced = Condition::new("misc", "Misc", ConditionKind::Count, Unit::Feet);
+
+    let measurements = vec![
+      // 100 ft x 10 ft high at 0.55 SF
+      wall("w1", 100.0, metadata(Some("gwb"), None)),
+      // Its own price overrides the condition's
+      wall("w2", 20.0, metadata(Some("gwb"), Some(rates("0.60")))),
+      // 20 x 10 ft at 6.25 SF
+      Measurement::Area {
+        id: "s1".to_string(),
+        rectangle: Some(Rectangle::new(
+          Point::new(0.0, 0.0),
+          Point::new(200.0, 100.0),
+        )),
+        polygon: None,
+        circle: None,
+        ellipse: None,
+        scale: Scale::new(10.0, 1.0, Unit::Feet),
+        display_unit: Unit::Feet,
+        depth: None,
+        pitch: None,
+        metadata: metadata(Some("sog"), None),
+      },
+      // Priced on its own, without a condition
+      Measurement::Count {
+        id: "c1".to_string(),
+        point: Point::new(0.0, 0.0),
+        metadata: metadata(None, Some(rates("125"))),
+      },
+      // Nothing to price
+      wall("w3", 5.0, None),
+    ];
+    let summary = cost_summary(&[drywall, slab, unpriced], &measurements).unwrap();
+    let totals: Vec<&str> = summary.lines.iter().map(|l| l.total.as_str()).collect();
+    assert_eq!(totals, vec!["550.00", "120.00", "1250.00", "125.00"]);
+    assert_eq!(summary.lines[1].basis, CostBasis::Area);
+    assert_eq!(summary.lines[1].quantity, "200");
+
+    let by_condition: Vec<(Option<&str>, &str)> = summary
+      .by_condition
+      .iter()
+      .map(|g| (g.key.as_deref(), g.total.as_str()))
+      .collect();
+    assert_eq!(
+      by_condition,
+      vec![
+        (Some("gwb"), "670.00"),
+        (Some("sog"), "1250.00"),
+        (Some("misc"), "0.00"),
+        (None, "125.00"),
+      ]
+    );
+    let by_trade: Vec<(Option<&str>, &str)> = summary
+      .by_trade
+      .iter()
+      .map(|g| (g.key.as_deref(), g.total.as_str()))
+      .collect();
+    assert_eq!(
+      by_trade,
+      vec![
+        (Some("Drywall"), "670.00"),
+        (Some("Concrete"), "1250.00"),
+        (None, "125.00"),
+      ]
+    );
+    assert_eq!(summary.total, "2045.00");
+
+    // A bad rate stops the summary rather than pricing at zero
+    let bad = wall("w4", 1.0, metadata(None, Some(rates("n/a"))));
+    assert!(cost_summary(&[], [&bad]).is_none());
+  }
+}
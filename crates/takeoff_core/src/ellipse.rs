@@ -0,0 +1,190 @@
+use crate::coords::exact_sin_cos;
+use crate::Point;
+use geo::{Coord, LineString, Polygon as GeoPolygon};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Number of straight pieces an ellipse is drawn with when flattened
+const FLATTEN_SEGMENTS: usize = 72;
+
+/// Represents an ellipse by its center and two radii
+///
+/// `radius_x` runs along the first axis, turned `rotation` degrees
+/// counter-clockwise from the x axis, and `radius_y` across it.
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Ellipse {
+  pub center: Point,
+  pub radius_x: f64,
+  pub radius_y: f64,
+  /// Angle of the first axis in degrees, counter-clockwise; none means 0
+  pub rotation: Option<f64>,
+}
+
+impl Ellipse {
+  pub fn new(center: Point, radius_x: f64, radius_y: f64) -> Self {
+    Self {
+      center,
+      radius_x,
+      radius_y,
+      rotation: None,
+    }
+  }
+
+  /// Create an ellipse whose first axis is turned `degrees`
+  pub fn rotated(center: Point, radius_x: f64, radius_y: f64, degrees: f64) -> Self {
+    Self {
+      rotation: (degrees != 0.0).then_some(degrees),
+      ..Self::new(center, radius_x, radius_y)
+    }
+  }
+
+  /// Create an ellipse from its center, the end of one axis and a point on the other
+  ///
+  /// The first axis runs from `center` to `axis_end`; the second radius is how
+  /// far `side` lies from that axis.
+  pub fn from_axes(center: Point, axis_end: Point, side: Point) -> Self {
+    let degrees = (axis_end.y - center.y)
+      .atan2(axis_end.x - center.x)
+      .to_degrees();
+    let mut ellipse = Self::rotated(center, center.distance_to(&axis_end), 0.0, degrees);
+    let (_, (vx, vy)) = ellipse.axes();
+    ellipse.radius_y = ((side.x - center.x) * vx + (side.y - center.y) * vy).abs();
+    ellipse
+  }
+
+  /// Rotation of the first axis in degrees
+  pub fn rotation(&self) -> f64 {
+    self.rotation.unwrap_or(0.0)
+  }
+
+  /// Unit vectors along the first and second axes
+  fn axes(&self) -> ((f64, f64), (f64, f64)) {
+    let (sin, cos) = exact_sin_cos(self.rotation());
+    ((cos, sin), (-sin, cos))
+  }
+
+  /// Calculate the area of the ellipse
+  pub fn area(&self) -> f64 {
+    PI * self.radius_x.abs() * self.radius_y.abs()
+  }
+
+  /// Calculate the length around the ellipse
+  ///
+  /// Uses the arithmetic-geometric mean, which converges to full precision in
+  /// a handful of steps rather than approximating.
+  pub fn circumference(&self) -> f64 {
+    let (rx, ry) = (self.radius_x.abs(), self.radius_y.abs());
+    let (major, minor) = (rx.max(ry), rx.min(ry));
+    if minor == 0.0 {
+      return 4.0 * major;
+    }
+    let (mut a, mut b) = (major, minor);
+    let mut sum = (a * a - b * b) / 2.0;
+    let mut weight = 1.0;
+    while a - b > f64::EPSILON * a {
+      let c = (a - b) / 2.0;
+      sum += weight * c * c;
+      weight *= 2.0;
+      (a, b) = ((a + b) / 2.0, (a * b).sqrt());
+    }
+    2.0 * PI * (major * major - sum) / a
+  }
+
+  /// Point on the ellipse at a parametric angle in radians
+  pub fn point_at(&self, angle: f64) -> Point {
+    let ((ux, uy), (vx, vy)) = self.axes();
+    let (x, y) = (self.radius_x * angle.cos(), self.radius_y * angle.sin());
+    Point::new(
+      self.center.x + ux * x + vx * y,
+      self.center.y + uy * x + vy * y,
+    )
+  }
+
+  /// Editing handles: the center, then the ends of the axes counter-clockwise
+  /// from the positive end of the first axis
+  pub fn handles(&self) -> Vec<Point> {
+    let mut handles = vec![self.center];
+    handles.extend((0..4).map(|i| self.point_at(i as f64 * PI / 2.0)));
+    handles
+  }
+
+  /// Drag a handle, numbered as in `handles`
+  ///
+  /// The center moves the ellipse; an axis end sets that radius to how far the
+  /// point lies from the center along the axis, keeping the rotation.
+  pub fn set_handle(&mut self, index: usize, point: Point) -> bool {
+    let ((ux, uy), (vx, vy)) = self.axes();
+    let (dx, dy) = (point.x - self.center.x, point.y - self.center.y);
+    match index {
+      0 => self.center = point,
+      1 | 3 => self.radius_x = (dx * ux + dy * uy).abs(),
+      2 | 4 => self.radius_y = (dx * vx + dy * vy).abs(),
+      _ => return false,
+    }
+    true
+  }
+
+  /// Approximate the ellipse with straight pieces, starting at the first handle
+  pub fn flattened_points(&self) -> Vec<Point> {
+    (0..FLATTEN_SEGMENTS)
+      .map(|i| self.point_at(2.0 * PI * i as f64 / FLATTEN_SEGMENTS as f64))
+      .collect()
+  }
+
+  /// Convert to a `geo` polygon, flattening the curve
+  pub fn to_polygon(&self) -> GeoPolygon<f64> {
+    let ring: Vec<Coord<f64>> = self
+      .flattened_points()
+      .into_iter()
+      .map(Coord::from)
+      .collect();
+    GeoPolygon::new(LineString::from(ring), vec![])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{a} != {b}");
+  }
+
+  #[test]
+  fn test_area_and_circumference() {
+    let ellipse = Ellipse::new(Point::new(0.0, 0.0), 2.0, 1.0);
+    assert_close(ellipse.area(), 2.0 * PI);
+    assert_close(ellipse.circumference(), 9.688448220547675);
+    // Equal radii give a circle; a zero radius gives a doubled line
+    assert_close(
+      Ellipse::new(Point::new(0.0, 0.0), 3.0, 3.0).circumference(),
+      6.0 * PI,
+    );
+    assert_close(
+      Ellipse::new(Point::new(0.0, 0.0), 0.0, 3.0).circumference(),
+      12.0,
+    );
+  }
+
+  #[test]
+  fn test_rotated_axes_and_handles() {
+    let mut ellipse = Ellipse::from_axes(
+      Point::new(0.0, 0.0),
+      Point::new(0.0, 10.0),
+      Point::new(-2.0, 3.0),
+    );
+    assert_close(ellipse.rotation(), 90.0);
+    assert_close(ellipse.radius_x, 10.0);
+    assert_close(ellipse.radius_y, 2.0);
+    let handles = ellipse.handles();
+    assert!(handles[1].distance_to(&Point::new(0.0, 10.0)) < 1e-9);
+    assert!(handles[2].distance_to(&Point::new(-2.0, 0.0)) < 1e-9);
+
+    assert!(ellipse.set_handle(4, Point::new(5.0, 1.0)));
+    assert_close(ellipse.radius_y, 5.0);
+    assert_close(ellipse.radius_x, 10.0);
+    assert!(!ellipse.set_handle(5, Point::new(0.0, 0.0)));
+  }
+}
@@ -0,0 +1,383 @@
+use serde::{Deserialize, Serialize};
+
+/// Deepest nesting of parentheses, calls, powers and unary minus a formula may have
+const MAX_DEPTH: usize = 64;
+/// Most numbers, names and symbols a formula may have, which also bounds how
+/// deep a long chain like `1 + 1 + ...` evaluates
+const MAX_TOKENS: usize = 512;
+
+/// Quantities of a measurement that formulas can refer to by name
+///
+/// Lengths are in one unit, areas in its square and volumes in its cube.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FormulaInputs {
+  pub length: f64,
+  pub area: f64,
+  pub perimeter: f64,
+  pub volume: f64,
+  pub count: f64,
+  pub height: f64,
+  pub width: f64,
+  pub depth: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variable {
+  Length,
+  Area,
+  Perimeter,
+  Volume,
+  Count,
+  Height,
+  Width,
+  Depth,
+}
+
+impl Variable {
+  fn parse(name: &str) -> Option<Self> {
+    Some(match name {
+      "length" => Variable::Length,
+      "area" => Variable::Area,
+      "perimeter" => Variable::Perimeter,
+      "volume" => Variable::Volume,
+      "count" => Variable::Count,
+      "height" => Variable::Height,
+      "width" => Variable::Width,
+      "depth" => Variable::Depth,
+      _ => return None,
+    })
+  }
+
+  fn value(&self, inputs: &FormulaInputs) -> f64 {
+    match self {
+      Variable::Length => inputs.length,
+      Variable::Area => inputs.area,
+      Variable::Perimeter => inputs.perimeter,
+      Variable::Volume => inputs.volume,
+      Variable::Count => inputs.count,
+      Variable::Height => inputs.height,
+      Variable::Width => inputs.width,
+      Variable::Depth => inputs.depth,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+  Ceil,
+  Floor,
+  Round,
+  Abs,
+  Min,
+  Max,
+}
+
+impl Function {
+  fn parse(name: &str) -> Option<Self> {
+    Some(match name {
+      "ceil" => Function::Ceil,
+      "floor" => Function::Floor,
+      "round" => Function::Round,
+      "abs" => Function::Abs,
+      "min" => Function::Min,
+      "max" => Function::Max,
+      _ => return None,
+    })
+  }
+
+  /// Check if the function takes this many arguments
+  fn accepts(&self, count: usize) -> bool {
+    match self {
+      Function::Min | Function::Max => count >= 1,
+      _ => count == 1,
+    }
+  }
+
+  fn apply(&self, args: &[f64]) -> Option<f64> {
+    match (self, args) {
+      (Function::Ceil, [x]) => Some(x.ceil()),
+      (Function::Floor, [x]) => Some(x.floor()),
+      (Function::Round, [x]) => Some(x.round()),
+      (Function::Abs, [x]) => Some(x.abs()),
+      (Function::Min, [first, rest @ ..]) => Some(rest.iter().fold(*first, |a, b| a.min(*b))),
+      (Function::Max, [first, rest @ ..]) => Some(rest.iter().fold(*first, |a, b| a.max(*b))),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+  Number(f64),
+  Variable(Variable),
+  Negate(Box<Expr>),
+  Binary(char, Box<Expr>, Box<Expr>),
+  Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+  fn evaluate(&self, inputs: &FormulaInputs) -> Option<f64> {
+    let value = match self {
+      Expr::Number(value) => *value,
+      Expr::Variable(variable) => variable.value(inputs),
+      Expr::Negate(expr) => -expr.evaluate(inputs)?,
+      Expr::Binary(op, left, right) => {
+        let (a, b) = (left.evaluate(inputs)?, right.evaluate(inputs)?);
+        match op {
+          '+' => a + b,
+          '-' => a - b,
+          '*' => a * b,
+          '/' => a / b,
+          _ => a.powf(b),
+        }
+      }
+      Expr::Call(function, args) => {
+        let args = args
+          .iter()
+          .map(|arg| arg.evaluate(inputs))
+          .collect::<Option<Vec<f64>>>()?;
+        function.apply(&args)?
+      }
+    };
+    value.is_finite().then_some(value)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+  Number(f64),
+  Name(String),
+  Symbol(char),
+}
+
+fn tokenize(text: &str) -> Option<Vec<Token>> {
+  let mut tokens = Vec::new();
+  let mut chars = text.chars().peekable();
+  while let Some(&c) = chars.peek() {
+    if c.is_whitespace() {
+      chars.next();
+    } else if c.is_ascii_digit() || c == '.' {
+      let mut number = String::new();
+      while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '.') {
+        number.push(d);
+        chars.next();
+      }
+      tokens.push(Token::Number(number.parse().ok()?));
+    } else if c.is_ascii_alphabetic() || c == '_' {
+      let mut name = String::new();
+      while let Some(&d) = chars
+        .peek()
+        .filter(|d| d.is_ascii_alphanumeric() || **d == '_')
+      {
+        name.push(d.to_ascii_lowercase());
+        chars.next();
+      }
+      tokens.push(Token::Name(name));
+    } else if "+-*/^(),".contains(c) {
+      tokens.push(Token::Symbol(c));
+      chars.next();
+    } else {
+      return None;
+    }
+    if tokens.len() > MAX_TOKENS {
+      return None;
+    }
+  }
+  Some(tokens)
+}
+
+/// Recursive descent over the tokens, with the usual precedence:
+/// `^` binds tightest and to the right, then unary minus, then `*` `/`, then `+` `-`
+struct Parser {
+  tokens: Vec<Token>,
+  position: usize,
+  depth: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.position)
+  }
+
+  fn eat(&mut self, symbol: char) -> bool {
+    if self.peek() == Some(&Token::Symbol(symbol)) {
+      self.position += 1;
+      true
+    } else {
+      false
+    }
+  }
+
+  fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+    self.depth += 1;
+    if self.depth > MAX_DEPTH {
+      return None;
+    }
+    let result = parse(self);
+    self.depth -= 1;
+    result
+  }
+
+  fn expression(&mut self) -> Option<Expr> {
+    let mut expr = self.term()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol(op @ ('+' | '-'))) => *op,
+        _ => return Some(expr),
+      };
+      self.position += 1;
+      expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+    }
+  }
+
+  fn term(&mut self) -> Option<Expr> {
+    let mut expr = self.unary()?;
+    loop {
+      let op = match self.peek() {
+        Some(Token::Symbol(op @ ('*' | '/'))) => *op,
+        _ => return Some(expr),
+      };
+      self.position += 1;
+      expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+    }
+  }
+
+  fn unary(&mut self) -> Option<Expr> {
+    if self.eat('-') {
+      return self.nested(|p| Some(Expr::Negate(Box::new(p.unary()?))));
+    }
+    let base = self.atom()?;
+    if self.eat('^') {
+      let exponent = self.nested(|p| p.unary())?;
+      return Some(Expr::Binary('^', Box::new(base), Box::new(exponent)));
+    }
+    Some(base)
+  }
+
+  fn atom(&mut self) -> Option<Expr> {
+    let token = self.peek()?.clone();
+    self.position += 1;
+    match token {
+      Token::Number(value) => Some(Expr::Number(value)),
+      Token::Symbol('(') => {
+        let expr = self.nested(|p| p.expression())?;
+        self.eat(')').then_some(expr)
+      }
+      Token::Name(name) if self.eat('(') => {
+        let function = Function::parse(&name)?;
+        let args = self.nested(|p| {
+          let mut args = vec![p.expression()?];
+          while p.eat(',') {
+            args.push(p.expression()?);
+          }
+          Some(args)
+        })?;
+        (function.accepts(args.len()) && self.eat(')')).then_some(Expr::Call(function, args))
+      }
+      Token::Name(name) => Variable::parse(&name).map(Expr::Variable),
+      Token::Symbol(_) => None,
+    }
+  }
+}
+
+/// An arithmetic formula over a measurement's quantities, e.g. `ceil(length * 12 / 16) + 1`
+///
+/// Formulas are plain data: numbers, the names in `FormulaInputs`, `+ - * / ^`,
+/// parentheses and `ceil`, `floor`, `round`, `abs`, `min` and `max`. Nothing else
+/// is accepted, so evaluating one can't run code or touch anything but its inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+  source: String,
+  expr: Expr,
+}
+
+impl Formula {
+  /// Parse a formula, or `None` if it uses anything outside the formula language
+  pub fn parse(source: &str) -> Option<Formula> {
+    let mut parser = Parser {
+      tokens: tokenize(source)?,
+      position: 0,
+      depth: 0,
+    };
+    let expr = parser.expression()?;
+    (parser.position == parser.tokens.len()).then(|| Formula {
+      source: source.to_string(),
+      expr,
+    })
+  }
+
+  /// The text the formula was parsed from
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Evaluate with the given quantities, or `None` if the result is not a finite number
+  pub fn evaluate(&self, inputs: &FormulaInputs) -> Option<f64> {
+    self.expr.evaluate(inputs)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn eval(source: &str, inputs: &FormulaInputs) -> Option<f64> {
+    Formula::parse(source)?.evaluate(inputs)
+  }
+
+  #[test]
+  fn test_arithmetic_and_precedence() {
+    let none = FormulaInputs::default();
+    assert_eq!(eval("1 + 2 * 3", &none), Some(7.0));
+    assert_eq!(eval("(1 + 2) * 3", &none), Some(9.0));
+    assert_eq!(eval("2 ^ 3 ^ 2", &none), Some(512.0));
+    assert_eq!(eval("-2 ^ 2", &none), Some(-4.0));
+    assert_eq!(eval("10 - 4 - 3", &none), Some(3.0));
+    assert_eq!(eval("max(1, .5, 3) + min(2, -1)", &none), Some(2.0));
+    assert_eq!(
+      eval("CEIL(2.1) + floor(2.9) + round(2.5)", &none),
+      Some(8.0)
+    );
+    // Dividing by zero has no quantity
+    assert_eq!(eval("1 / 0", &none), None);
+  }
+
+  #[test]
+  fn test_variables() {
+    let wall = FormulaInputs {
+      length: 20.0,
+      height: 9.0,
+      ..FormulaInputs::default()
+    };
+    // Studs at 16" o.c., plus one to close the run
+    assert_eq!(eval("ceil(length * 12 / 16) + 1", &wall), Some(16.0));
+    assert_eq!(eval("length * height * 2", &wall), Some(360.0));
+  }
+
+  #[test]
+  fn test_rejects_anything_else() {
+    for source in [
+      "",
+      "length +",
+      "(length",
+      "length)",
+      "1 2",
+      "system(\"rm\")",
+      "unknown * 2",
+      "ceil()",
+      "length; 1",
+      "1..2",
+      "ceil(1, 2)",
+    ] {
+      let parsed = Formula::parse(source);
+      let evaluated = parsed.and_then(|f| f.evaluate(&FormulaInputs::default()));
+      assert!(evaluated.is_none(), "{source:?} should not evaluate");
+    }
+    // Deep nesting is refused rather than overflowing the stack
+    let deep = format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000));
+    assert!(Formula::parse(&deep).is_none());
+    assert!(Formula::parse(&"-".repeat(10_000)).is_none());
+    assert!(Formula::parse(&"1 + ".repeat(10_000)).is_none());
+    assert!(Formula::parse("ceil(1, 2)").is_none());
+  }
+}
@@ -0,0 +1,780 @@
+use crate::{Assembly, Condition, Measurement, Scale, ScaleRegion, TakeoffState};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Default number of undo steps kept by a `History`
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// A reversible mutation of a `TakeoffState`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+  /// A measurement appended to the state
+  AddMeasurement { measurement: Measurement },
+  /// A measurement removed from the given position
+  RemoveMeasurement {
+    index: usize,
+    measurement: Measurement,
+  },
+  /// All measurements removed at once
+  ClearMeasurements { measurements: Vec<Measurement> },
+  /// A measurement replaced in place (vertex edits, property changes)
+  ///
+  /// Boxed because it holds two measurements, which would otherwise make every
+  /// command twice the size of one.
+  UpdateMeasurement {
+    before: Box<Measurement>,
+    after: Box<Measurement>,
+  },
+  /// The state scale changed
+  SetScale {
+    before: Option<Scale>,
+    after: Option<Scale>,
+  },
+  /// A scale region added, or put in place of the one with the same ID
+  AddScaleRegion {
+    before: Option<ScaleRegion>,
+    after: ScaleRegion,
+  },
+  /// A scale region removed from the given position
+  RemoveScaleRegion { index: usize, region: ScaleRegion },
+  /// A condition added, or put in place of the one with the same ID
+  AddCondition {
+    before: Option<Condition>,
+    after: Condition,
+  },
+  /// A condition removed from the given position
+  RemoveCondition { index: usize, condition: Condition },
+  /// An assembly added, or put in place of the one with the same ID
+  AddAssembly {
+    before: Option<Assembly>,
+    after: Assembly,
+  },
+  /// An assembly removed from the given position
+  RemoveAssembly { index: usize, assembly: Assembly },
+}
+
+impl Command {
+  /// Apply the command to the state
+  pub fn apply(&self, state: &mut TakeoffState) {
+    match self {
+      Command::AddMeasurement { measurement } => state.add_measurement(measurement.clone()),
+      Command::RemoveMeasurement { measurement, .. } => {
+        state.remove_measurement(measurement.id());
+      }
+      Command::ClearMeasurements { .. } => state.clear_measurements(),
+      Command::UpdateMeasurement { after, .. } => {
+        state.update_measurement(after.as_ref().clone());
+      }
+      Command::SetScale { after, .. } => state.scale = *after,
+      Command::AddScaleRegion { after, .. } => state.add_scale_region(after.clone()),
+      Command::RemoveScaleRegion { region, .. } => {
+        state.remove_scale_region(&region.id);
+      }
+      Command::AddCondition { after, .. } => state.add_condition(after.clone()),
+      Command::RemoveCondition { condition, .. } => {
+        state.remove_condition(&condition.id);
+      }
+      Command::AddAssembly { after, .. } => state.add_assembly(after.clone()),
+      Command::RemoveAssembly { assembly, .. } => {
+        state.remove_assembly(&assembly.id);
+      }
+    }
+  }
+
+  /// Undo the effect of the command on the state
+  pub fn revert(&self, state: &mut TakeoffState) {
+    match self {
+      Command::AddMeasurement { measurement } => {
+        state.remove_measurement(measurement.id());
+      }
+      Command::RemoveMeasurement { index, measurement } => {
+        state.insert_measurement(*index, measurement.clone())
+      }
+      Command::ClearMeasurements { measurements } => {
+        for measurement in measurements {
+          state.add_measurement(measurement.clone());
+        }
+      }
+      Command::UpdateMeasurement { before, .. } => {
+        state.update_measurement(before.as_ref().clone());
+      }
+      Command::SetScale { before, .. } => state.scale = *before,
+      Command::AddScaleRegion { before, after } => match before {
+        Some(before) => state.add_scale_region(before.clone()),
+        None => {
+          state.remove_scale_region(&after.id);
+        }
+      },
+      Command::RemoveScaleRegion { index, region } => {
+        state.insert_scale_region(*index, region.clone())
+      }
+      Command::AddCondition { before, after } => match before {
+        Some(before) => state.add_condition(before.clone()),
+        None => {
+          state.remove_condition(&after.id);
+        }
+      },
+      Command::RemoveCondition { index, condition } => {
+        state.insert_condition(*index, condition.clone())
+      }
+      Command::AddAssembly { before, after } => match before {
+        Some(before) => state.add_assembly(before.clone()),
+        None => {
+          state.remove_assembly(&after.id);
+        }
+      },
+      Command::RemoveAssembly { index, assembly } => {
+        state.insert_assembly(*index, assembly.clone())
+      }
+    }
+  }
+
+  /// Fold a later command into this one when both describe the same edit
+  fn merge(&mut self, next: &Command) -> bool {
+    match (self, next) {
+      (
+        Command::UpdateMeasurement { after, .. },
+        Command::UpdateMeasurement {
+          before: next_before,
+          after: next_after,
+        },
+      ) if after == next_before => {
+        *after = next_after.clone();
+        true
+      }
+      (
+        Command::SetScale { after, .. },
+        Command::SetScale {
+          after: next_after, ..
+        },
+      ) => {
+        *after = *next_after;
+        true
+      }
+      _ => false,
+    }
+  }
+}
+
+/// Bounded undo/redo history of state mutations
+///
+/// Every mutation goes through one of the recording methods, which applies the
+/// change to the state and remembers how to reverse it. Mutations made between
+/// `begin_transaction` and `commit_transaction` are undone as a single step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct History {
+  undo_stack: VecDeque<Vec<Command>>,
+  redo_stack: Vec<Vec<Command>>,
+  limit: usize,
+  transaction: Option<Vec<Command>>,
+  /// Length of the open transaction at each unmatched `begin_transaction`
+  #[serde(default)]
+  checkpoints: Vec<usize>,
+}
+
+impl History {
+  pub fn new() -> Self {
+    Self::with_limit(DEFAULT_HISTORY_LIMIT)
+  }
+
+  /// Create a history that keeps at most `limit` undo steps
+  pub fn with_limit(limit: usize) -> Self {
+    Self {
+      undo_stack: VecDeque::new(),
+      redo_stack: Vec::new(),
+      limit: limit.max(1),
+      transaction: None,
+      checkpoints: Vec::new(),
+    }
+  }
+
+  pub fn limit(&self) -> usize {
+    self.limit
+  }
+
+  pub fn set_limit(&mut self, limit: usize) {
+    self.limit = limit.max(1);
+    self.enforce_limit();
+  }
+
+  /// Apply a command to the state and record it
+  pub fn execute(&mut self, state: &mut TakeoffState, command: Command) {
+    command.apply(state);
+    self.record(command);
+  }
+
+  /// Add a measurement to the state
+  pub fn add_measurement(&mut self, state: &mut TakeoffState, measurement: Measurement) {
+    self.execute(state, Command::AddMeasurement { measurement });
+  }
+
+  /// Remove a measurement from the state by ID
+  pub fn remove_measurement(&mut self, state: &mut TakeoffState, id: &str) -> bool {
+    match state.measurement_index(id) {
+      Some(index) => {
+        let measurement = state.measurements[index].clone();
+        self.execute(state, Command::RemoveMeasurement { index, measurement });
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Remove every measurement from the state
+  pub fn clear_measurements(&mut self, state: &mut TakeoffState) {
+    if state.measurements.is_empty() {
+      return;
+    }
+    let measurements = state.measurements.clone();
+    self.execute(state, Command::ClearMeasurements { measurements });
+  }
+
+  /// Replace the measurement with the same ID
+  pub fn update_measurement(&mut self, state: &mut TakeoffState, measurement: Measurement) -> bool {
+    match state.measurement(measurement.id()) {
+      Some(before) if *before == measurement => true,
+      Some(before) => {
+        let before = Box::new(before.clone());
+        self.execute(
+          state,
+          Command::UpdateMeasurement {
+            before,
+            after: Box::new(measurement),
+          },
+        );
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Edit a measurement in place with `edit`, recording the change if it returns true
+  pub fn modify_measurement<F>(&mut self, state: &mut TakeoffState, id: &str, edit: F) -> bool
+  where
+    F: FnOnce(&mut Measurement) -> bool,
+  {
+    let Some(mut measurement) = state.measurement(id).cloned() else {
+      return false;
+    };
+    if !edit(&mut measurement) {
+      return false;
+    }
+    self.update_measurement(state, measurement)
+  }
+
+  /// Set or clear the state scale
+  pub fn set_scale(&mut self, state: &mut TakeoffState, scale: Option<Scale>) {
+    if state.scale == scale {
+      return;
+    }
+    let before = state.scale;
+    self.execute(
+      state,
+      Command::SetScale {
+        before,
+        after: scale,
+      },
+    );
+  }
+
+  /// Add a scale region, replacing any region with the same ID
+  pub fn add_scale_region(&mut self, state: &mut TakeoffState, region: ScaleRegion) {
+    let before = state
+      .scale_regions()
+      .iter()
+      .find(|r| r.id == region.id)
+      .cloned();
+    if before.as_ref() == Some(&region) {
+      return;
+    }
+    self.execute(
+      state,
+      Command::AddScaleRegion {
+        before,
+        after: region,
+      },
+    );
+  }
+
+  /// Remove a scale region from the state by ID
+  pub fn remove_scale_region(&mut self, state: &mut TakeoffState, id: &str) -> bool {
+    match state.scale_regions().iter().position(|r| r.id == id) {
+      Some(index) => {
+        let region = state.scale_regions()[index].clone();
+        self.execute(state, Command::RemoveScaleRegion { index, region });
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Add a condition, replacing any condition with the same ID
+  pub fn add_condition(&mut self, state: &mut TakeoffState, condition: Condition) {
+    let before = state.condition(&condition.id).cloned();
+    if before.as_ref() == Some(&condition) {
+      return;
+    }
+    self.execute(
+      state,
+      Command::AddCondition {
+        before,
+        after: condition,
+      },
+    );
+  }
+
+  /// Remove a condition from the state by ID
+  pub fn remove_condition(&mut self, state: &mut TakeoffState, id: &str) -> bool {
+    match state.conditions().iter().position(|c| c.id == id) {
+      Some(index) => {
+        let condition = state.conditions()[index].clone();
+        self.execute(state, Command::RemoveCondition { index, condition });
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Add an assembly, replacing any assembly with the same ID
+  pub fn add_assembly(&mut self, state: &mut TakeoffState, assembly: Assembly) {
+    let before = state.assembly(&assembly.id).cloned();
+    if before.as_ref() == Some(&assembly) {
+      return;
+    }
+    self.execute(
+      state,
+      Command::AddAssembly {
+        before,
+        after: assembly,
+      },
+    );
+  }
+
+  /// Remove an assembly from the state by ID
+  pub fn remove_assembly(&mut self, state: &mut TakeoffState, id: &str) -> bool {
+    match state.assemblies().iter().position(|a| a.id == id) {
+      Some(index) => {
+        let assembly = state.assemblies()[index].clone();
+        self.execute(state, Command::RemoveAssembly { index, assembly });
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Start grouping commands into a single undo step
+  ///
+  /// Transactions nest; only the outermost `commit_transaction` closes the group.
+  pub fn begin_transaction(&mut self) {
+    let commands = self.transaction.get_or_insert_with(Vec::new);
+    self.checkpoints.push(commands.len());
+  }
+
+  /// Close the current transaction, pushing it as one undo step
+  pub fn commit_transaction(&mut self) {
+    if self.checkpoints.pop().is_none() {
+      return;
+    }
+    if self.checkpoints.is_empty() {
+      if let Some(commands) = self.transaction.take() {
+        self.push_group(commands);
+      }
+    }
+  }
+
+  /// Abandon the innermost transaction, reverting what it recorded
+  ///
+  /// Commands recorded by enclosing transactions before the matching
+  /// `begin_transaction` are kept and still committed with them.
+  pub fn rollback_transaction(&mut self, state: &mut TakeoffState) {
+    let Some(checkpoint) = self.checkpoints.pop() else {
+      return;
+    };
+    if let Some(commands) = self.transaction.as_mut() {
+      for command in commands.split_off(checkpoint).iter().rev() {
+        command.revert(state);
+      }
+    }
+    if self.checkpoints.is_empty() {
+      self.transaction = None;
+    }
+  }
+
+  pub fn in_transaction(&self) -> bool {
+    self.transaction.is_some()
+  }
+
+  pub fn can_undo(&self) -> bool {
+    !self.undo_stack.is_empty() || self.transaction.as_ref().is_some_and(|t| !t.is_empty())
+  }
+
+  pub fn can_redo(&self) -> bool {
+    !self.redo_stack.is_empty()
+  }
+
+  /// Undo the most recent step. An open transaction is committed first.
+  pub fn undo(&mut self, state: &mut TakeoffState) -> bool {
+    self.close_transaction();
+    match self.undo_stack.pop_back() {
+      Some(commands) => {
+        for command in commands.iter().rev() {
+          command.revert(state);
+        }
+        self.redo_stack.push(commands);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Redo the most recently undone step
+  pub fn redo(&mut self, state: &mut TakeoffState) -> bool {
+    self.close_transaction();
+    match self.redo_stack.pop() {
+      Some(commands) => {
+        for command in &commands {
+          command.apply(state);
+        }
+        self.undo_stack.push_back(commands);
+        self.enforce_limit();
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Number of steps that can be undone
+  pub fn undo_len(&self) -> usize {
+    self.undo_stack.len()
+  }
+
+  /// Number of steps that can be redone
+  pub fn redo_len(&self) -> usize {
+    self.redo_stack.len()
+  }
+
+  /// Forget all recorded steps
+  pub fn clear(&mut self) {
+    self.undo_stack.clear();
+    self.redo_stack.clear();
+    self.transaction = None;
+    self.checkpoints.clear();
+  }
+
+  fn record(&mut self, command: Command) {
+    match self.transaction.as_mut() {
+      Some(commands) => {
+        // Never merge across a checkpoint, so a rollback can still revert it
+        let checkpoint = self.checkpoints.last().copied().unwrap_or(0);
+        if commands.len() > checkpoint {
+          if let Some(last) = commands.last_mut() {
+            if last.merge(&command) {
+              return;
+            }
+          }
+        }
+        commands.push(command);
+      }
+      None => self.push_group(vec![command]),
+    }
+  }
+
+  fn push_group(&mut self, commands: Vec<Command>) {
+    if commands.is_empty() {
+      return;
+    }
+    self.undo_stack.push_back(commands);
+    self.redo_stack.clear();
+    self.enforce_limit();
+  }
+
+  fn close_transaction(&mut self) {
+    if let Some(commands) = self.transaction.take() {
+      self.checkpoints.clear();
+      self.push_group(commands);
+    }
+  }
+
+  fn enforce_limit(&mut self) {
+    while self.undo_stack.len() > self.limit {
+      self.undo_stack.pop_front();
+    }
+  }
+}
+
+impl Default for History {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{ConditionKind, Line, Point, Unit};
+
+  fn line(id: &str, end_x: f64) -> Measurement {
+    Measurement::Linear {
+      id: id.to_string(),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(end_x, 0.0))),
+      polyline: None,
+      scale: Scale::new(1.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    }
+  }
+
+  fn count(id: &str) -> Measurement {
+    Measurement::Count {
+      id: id.to_string(),
+      point: Point::new(1.0, 1.0),
+      metadata: None,
+    }
+  }
+
+  #[test]
+  fn test_undo_redo_add_and_remove() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+
+    history.add_measurement(&mut state, line("a", 10.0));
+    history.add_measurement(&mut state, count("b"));
+    history.add_measurement(&mut state, line("c", 5.0));
+    assert!(history.remove_measurement(&mut state, "b"));
+    assert_eq!(state.count, 0);
+
+    assert!(history.undo(&mut state));
+    let ids: Vec<&str> = state.measurements.iter().map(|m| m.id()).collect();
+    assert_eq!(ids, vec!["a", "b", "c"]);
+    assert_eq!(state.count, 1);
+
+    assert!(history.redo(&mut state));
+    assert_eq!(state.measurements.len(), 2);
+    assert!(!history.can_redo());
+  }
+
+  #[test]
+  fn test_undo_clear_and_scale() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    history.add_measurement(&mut state, line("a", 10.0));
+    history.add_measurement(&mut state, count("b"));
+    history.set_scale(&mut state, Some(Scale::new(10.0, 1.0, Unit::Feet)));
+    history.clear_measurements(&mut state);
+    assert!(state.measurements.is_empty());
+
+    history.undo(&mut state);
+    assert_eq!(state.measurements.len(), 2);
+    assert_eq!(state.count, 1);
+
+    history.undo(&mut state);
+    assert_eq!(state.scale, None);
+  }
+
+  #[test]
+  fn test_transaction_is_one_step() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    history.add_measurement(&mut state, line("a", 10.0));
+
+    history.begin_transaction();
+    for x in [11.0, 12.0, 13.0] {
+      history.update_measurement(&mut state, line("a", x));
+    }
+    history.commit_transaction();
+    assert_eq!(history.undo_len(), 2);
+    assert_eq!(state.measurements[0], line("a", 13.0));
+
+    history.undo(&mut state);
+    assert_eq!(state.measurements[0], line("a", 10.0));
+    history.redo(&mut state);
+    assert_eq!(state.measurements[0], line("a", 13.0));
+  }
+
+  #[test]
+  fn test_rollback_transaction() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    history.add_measurement(&mut state, line("a", 10.0));
+
+    history.begin_transaction();
+    history.update_measurement(&mut state, line("a", 20.0));
+    history.add_measurement(&mut state, count("b"));
+    history.rollback_transaction(&mut state);
+
+    assert_eq!(state.measurements, vec![line("a", 10.0)]);
+    assert_eq!(history.undo_len(), 1);
+  }
+
+  #[test]
+  fn test_nested_rollback_keeps_outer_transaction() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    history.add_measurement(&mut state, line("a", 10.0));
+
+    history.begin_transaction();
+    history.update_measurement(&mut state, line("a", 20.0));
+    history.begin_transaction();
+    history.update_measurement(&mut state, line("a", 30.0));
+    history.add_measurement(&mut state, count("b"));
+    history.rollback_transaction(&mut state);
+    assert!(history.in_transaction());
+    assert_eq!(state.measurements, vec![line("a", 20.0)]);
+
+    history.commit_transaction();
+    assert!(!history.in_transaction());
+    assert_eq!(history.undo_len(), 2);
+    history.undo(&mut state);
+    assert_eq!(state.measurements, vec![line("a", 10.0)]);
+  }
+
+  #[test]
+  fn test_limit_drops_oldest() {
+    let mut state = TakeoffState::new();
+    let mut history = History::with_limit(2);
+    for id in ["a", "b", "c"] {
+      history.add_measurement(&mut state, count(id));
+    }
+    assert_eq!(history.undo_len(), 2);
+    assert!(history.undo(&mut state));
+    assert!(history.undo(&mut state));
+    assert!(!history.undo(&mut state));
+    assert_eq!(state.measurements.len(), 1);
+  }
+
+  #[test]
+  fn test_new_command_clears_redo() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    history.add_measurement(&mut state, count("a"));
+    history.undo(&mut state);
+    assert!(history.can_redo());
+    history.add_measurement(&mut state, count("b"));
+    assert!(!history.can_redo());
+  }
+
+  #[test]
+  fn test_undo_metadata_edit() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    history.add_measurement(&mut state, count("a"));
+    assert!(history.modify_measurement(&mut state, "a", |m| { m.metadata_mut().add_tag("demo") }));
+    assert_eq!(state.measurements_with_tag("DEMO").len(), 1);
+
+    history.undo(&mut state);
+    assert!(state.measurements_with_tag("demo").is_empty());
+    history.redo(&mut state);
+    assert_eq!(state.measurements_with_tag("demo").len(), 1);
+  }
+
+  #[test]
+  fn test_undo_scale_regions() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    let region = |id: &str, ratio: f64| ScaleRegion {
+      id: id.to_string(),
+      name: None,
+      rectangle: Some(crate::Rectangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(100.0, 100.0),
+      )),
+      polygon: None,
+      scale: Scale::new(ratio, 1.0, Unit::Feet),
+    };
+    let inside = Point::new(50.0, 50.0);
+    history.add_scale_region(&mut state, region("detail", 10.0));
+    history.add_scale_region(&mut state, region("detail", 20.0));
+    assert_eq!(
+      state.scale_at(&inside),
+      Some(Scale::new(20.0, 1.0, Unit::Feet))
+    );
+    assert!(history.remove_scale_region(&mut state, "detail"));
+    assert_eq!(state.scale_at(&inside), None);
+
+    history.undo(&mut state);
+    assert_eq!(
+      state.scale_at(&inside),
+      Some(Scale::new(20.0, 1.0, Unit::Feet))
+    );
+    history.undo(&mut state);
+    assert_eq!(
+      state.scale_at(&inside),
+      Some(Scale::new(10.0, 1.0, Unit::Feet))
+    );
+    history.undo(&mut state);
+    assert!(state.scale_regions().is_empty());
+  }
+
+  #[test]
+  fn test_undo_conditions() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    let walls = Condition::new("walls", "Walls", ConditionKind::Linear, Unit::Feet);
+    let slab = Condition::new("slab", "Slab", ConditionKind::Area, Unit::Feet);
+    history.add_condition(&mut state, walls.clone());
+    history.add_condition(&mut state, slab.clone());
+    history.add_condition(
+      &mut state,
+      Condition {
+        name: "Partitions".to_string(),
+        ..walls.clone()
+      },
+    );
+    assert!(history.remove_condition(&mut state, "walls"));
+    assert!(!history.remove_condition(&mut state, "walls"));
+    assert_eq!(state.conditions().len(), 1);
+    assert_eq!(state.conditions()[0].id, "slab");
+
+    // Removing puts the condition back where it was
+    history.undo(&mut state);
+    assert_eq!(state.conditions()[0].name, "Partitions");
+    assert_eq!(state.conditions()[1], slab);
+
+    // Replacing restores the condition it replaced
+    history.undo(&mut state);
+    assert_eq!(state.conditions(), [walls, slab]);
+
+    history.undo(&mut state);
+    history.undo(&mut state);
+    assert!(state.conditions().is_empty());
+    history.redo(&mut state);
+    assert_eq!(state.conditions().len(), 1);
+  }
+
+  #[test]
+  fn test_undo_assemblies() {
+    let mut state = TakeoffState::new();
+    let mut history = History::new();
+    let assembly = |id: &str, name: &str| Assembly {
+      id: id.to_string(),
+      name: name.to_string(),
+      display_unit: Unit::Feet,
+      height: None,
+      depth: None,
+      items: Vec::new(),
+    };
+    history.add_assembly(&mut state, assembly("wall", "Wall"));
+    history.add_assembly(&mut state, assembly("slab", "Slab"));
+    history.add_assembly(&mut state, assembly("wall", "Stud wall"));
+    assert!(history.remove_assembly(&mut state, "wall"));
+    assert!(!history.remove_assembly(&mut state, "wall"));
+    assert_eq!(state.assemblies().len(), 1);
+
+    // Removing puts the assembly back where it was
+    history.undo(&mut state);
+    assert_eq!(state.assemblies()[0].name, "Stud wall");
+    // Replacing restores the assembly it replaced
+    history.undo(&mut state);
+    assert_eq!(state.assemblies()[0].name, "Wall");
+    assert_eq!(state.assemblies()[1].id, "slab");
+
+    history.undo(&mut state);
+    history.undo(&mut state);
+    assert!(state.assemblies().is_empty());
+    history.redo(&mut state);
+    assert_eq!(state.assembly("wall").unwrap().name, "Wall");
+  }
+}
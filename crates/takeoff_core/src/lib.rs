@@ -1,16 +1,53 @@
+pub mod arc;
+pub mod assembly;
+pub mod boolean;
+pub mod circle;
+pub mod condition;
 pub mod coords;
+pub mod cost;
+pub mod ellipse;
+pub mod formula;
+pub mod history;
 pub mod measurement;
+pub mod metadata;
+pub mod offset;
+pub mod pitch;
 pub mod polygon;
 pub mod polyline;
+pub mod project;
 pub mod rectangle;
 pub mod scale;
+pub mod scale_region;
+pub mod snap;
+pub mod spatial;
 pub mod state;
 pub mod units;
+pub mod validation;
+pub use arc::ArcSegment;
+pub use assembly::{Assembly, AssemblyItem, DerivedItem};
+pub use boolean::{boolean_op, BooleanOp};
+pub use circle::Circle;
+pub use condition::{condition_totals, Condition, ConditionKind, ConditionTotals};
 pub use coords::{Point, Transform};
-pub use measurement::Measurement;
+pub use cost::{cost_summary, CostBasis, CostGroup, CostLine, CostRates, CostSummary};
+pub use ellipse::Ellipse;
+pub use formula::{Formula, FormulaInputs};
+pub use history::{Command, History};
+pub use measurement::{GeometryConversion, Measurement};
+pub use metadata::{MeasurementMetadata, StrokeStyle};
+pub use offset::{offset_area, offset_linear, CapStyle, JoinStyle, OffsetOptions};
+pub use pitch::Pitch;
 pub use polygon::Polygon;
 pub use polyline::{Line, Polyline};
+pub use project::{PageKey, TakeoffProject};
 pub use rectangle::Rectangle;
-pub use scale::Scale;
+pub use scale::{Scale, ScaleCategory, ScalePreset};
+pub use scale_region::{ScaleRegion, ScaleRegionWarning, ScaleResolution};
+pub use snap::{SnapResult, SnapSettings, SnapType, Snapper};
+pub use spatial::{IndexedVertex, SpatialIndex};
 pub use state::TakeoffState;
-pub use units::{Unit, UnitUtils};
+pub use units::{AreaSuffix, Dimension, DisplayFormat, LengthStyle, Unit, UnitUtils, VolumeUnit};
+pub use validation::{
+  is_valid_polygon, repair_area, repair_polygon, validate_area, validate_polygon, ValidationIssue,
+  ValidationIssueKind,
+};
@@ -1,6 +1,12 @@
-use crate::{Line, Point, Polygon, Polyline, Rectangle, Scale, Unit, UnitUtils};
+use crate::coords::exact_sin_cos;
+use crate::{
+  ArcSegment, Circle, Dimension, DisplayFormat, Ellipse, Line, MeasurementMetadata, Pitch, Point,
+  Polygon, Polyline, Rectangle, Scale, Unit, UnitUtils, VolumeUnit,
+};
+use geo::{Contains, Point as GeoPoint, Polygon as GeoPolygon};
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_2;
 
 /// Types of measurements that can be created
 #[napi(discriminant = "type")]
@@ -13,17 +19,52 @@ pub enum Measurement {
     polyline: Option<Polyline>, // Multiple connected line segments
     scale: Scale,
     display_unit: Unit,
+    /// Width of the run, for volumes such as footings
+    width: Option<Dimension>,
+    /// Depth of the run, for volumes such as footings
+    depth: Option<Dimension>,
+    /// Slope of the run; real lengths are measured along it
+    pitch: Option<Pitch>,
+    /// Name, color, notes, tags and layer
+    metadata: Option<MeasurementMetadata>,
   },
-  /// Area measurement (rectangle or polygon)
+  /// Area measurement (rectangle, polygon, circle or ellipse)
   Area {
     id: String,
     rectangle: Option<Rectangle>,
     polygon: Option<Polygon>,
+    circle: Option<Circle>,
+    ellipse: Option<Ellipse>,
     scale: Scale,
     display_unit: Unit,
+    /// Depth or thickness, for volumes such as slabs and excavations
+    depth: Option<Dimension>,
+    /// Slope of the surface; real areas are measured along it
+    pitch: Option<Pitch>,
+    /// Name, color, notes, tags and layer
+    metadata: Option<MeasurementMetadata>,
   },
   /// Count marker
-  Count { id: String, point: Point },
+  Count {
+    id: String,
+    point: Point,
+    /// Name, color, notes, tags and layer
+    metadata: Option<MeasurementMetadata>,
+  },
+}
+
+/// Ways to turn a measurement into another geometry kind
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GeometryConversion {
+  /// Rectangle area to an editable polygon area
+  RectangleToPolygon,
+  /// Polyline to the polygon area it encloses, closing it back to the start
+  PolylineToPolygon,
+  /// Polygon area to a polyline around its outer ring, ending where it starts
+  PolygonToPolyline,
+  /// Single line to an editable polyline
+  LineToPolyline,
 }
 
 impl Measurement {
@@ -36,25 +77,699 @@ impl Measurement {
     }
   }
 
+  /// Name, color, notes, tags and layer of the measurement
+  pub fn metadata(&self) -> Option<&MeasurementMetadata> {
+    match self {
+      Measurement::Linear { metadata, .. }
+      | Measurement::Area { metadata, .. }
+      | Measurement::Count { metadata, .. } => metadata.as_ref(),
+    }
+  }
+
+  /// Get the metadata to edit it, creating empty metadata if there is none
+  pub fn metadata_mut(&mut self) -> &mut MeasurementMetadata {
+    match self {
+      Measurement::Linear { metadata, .. }
+      | Measurement::Area { metadata, .. }
+      | Measurement::Count { metadata, .. } => metadata.get_or_insert_with(Default::default),
+    }
+  }
+
+  /// ID of the condition the measurement is assigned to
+  pub fn condition_id(&self) -> Option<&str> {
+    self.metadata()?.condition_id.as_deref()
+  }
+
+  /// Replace the metadata of the measurement
+  pub fn set_metadata(&mut self, value: Option<MeasurementMetadata>) {
+    match self {
+      Measurement::Linear { metadata, .. }
+      | Measurement::Area { metadata, .. }
+      | Measurement::Count { metadata, .. } => *metadata = value,
+    }
+  }
+
+  /// Get every edge of the measurement, including closing edges and hole edges
+  ///
+  /// Counts have no edges.
+  pub fn segments(&self) -> Vec<ArcSegment> {
+    match self {
+      Measurement::Linear { line, polyline, .. } => {
+        if let Some(l) = line {
+          vec![ArcSegment::new(l.start, l.end, 0.0)]
+        } else if let Some(polyline) = polyline {
+          polyline.segments()
+        } else {
+          Vec::new()
+        }
+      }
+      Measurement::Area {
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
+      } => {
+        if let Some(rect) = rectangle {
+          ring_segments(&rect.corners())
+        } else if let Some(poly) = polygon {
+          let mut segments = poly.segments();
+          for hole in poly.holes() {
+            segments.extend(ring_segments(hole));
+          }
+          segments
+        } else if let Some(circle) = circle {
+          circle.segments()
+        } else if let Some(ellipse) = ellipse {
+          ring_segments(&ellipse.flattened_points())
+        } else {
+          Vec::new()
+        }
+      }
+      Measurement::Count { .. } => Vec::new(),
+    }
+  }
+
+  /// Bounding box of the measurement as `(min, max)` corners, including arc bulges
+  pub fn bounds(&self) -> Option<(Point, Point)> {
+    let mut points = self.vertices();
+    for segment in self.segments() {
+      if !segment.is_straight() {
+        points.extend(segment.flatten());
+      }
+    }
+    Point::bounds(&points)
+  }
+
+  /// Combined bounding box of several measurements
+  pub fn combined_bounds<'a>(
+    measurements: impl IntoIterator<Item = &'a Measurement>,
+  ) -> Option<(Point, Point)> {
+    let corners: Vec<Point> = measurements
+      .into_iter()
+      .filter_map(|m| m.bounds())
+      .flat_map(|(min, max)| [min, max])
+      .collect();
+    Point::bounds(&corners)
+  }
+
+  /// Center of the combined bounding box of several measurements
+  pub fn center_of<'a>(measurements: impl IntoIterator<Item = &'a Measurement>) -> Option<Point> {
+    let (min, max) = Self::combined_bounds(measurements)?;
+    Some(Point::new((min.x + max.x) / 2.0, (min.y + max.y) / 2.0))
+  }
+
+  /// Convert an area measurement to a `geo` polygon, flattening any arcs
+  pub fn to_polygon(&self) -> Option<GeoPolygon<f64>> {
+    match self {
+      Measurement::Area {
+        rectangle: Some(rect),
+        ..
+      } => Some(rect.to_polygon()),
+      Measurement::Area {
+        polygon: Some(poly),
+        ..
+      } => Some(poly.to_polygon()),
+      Measurement::Area {
+        circle: Some(circle),
+        ..
+      } => Some(circle.to_polygon()),
+      Measurement::Area {
+        ellipse: Some(ellipse),
+        ..
+      } => Some(ellipse.to_polygon()),
+      _ => None,
+    }
+  }
+
+  /// Check if a point lies inside an area measurement, outside any holes
+  pub fn contains_point(&self, point: &Point) -> bool {
+    self
+      .to_polygon()
+      .is_some_and(|shape| shape.contains(&GeoPoint::new(point.x, point.y)))
+  }
+
+  /// Get the editable vertices of the measurement
+  ///
+  /// Polygon holes follow the outer ring. Rectangles expose their two defining
+  /// corners, or all four corners in order once rotated off the axes.
+  pub fn vertices(&self) -> Vec<Point> {
+    match self {
+      Measurement::Linear { line, polyline, .. } => {
+        if let Some(l) = line {
+          vec![l.start, l.end]
+        } else if let Some(points) = polyline {
+          points.points.clone()
+        } else {
+          Vec::new()
+        }
+      }
+      Measurement::Area {
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
+      } => {
+        if let Some(rect) = rectangle {
+          if rect.is_rotated() {
+            rect.corners()
+          } else {
+            vec![rect.start, rect.end]
+          }
+        } else if let Some(poly) = polygon {
+          poly.vertices()
+        } else if let Some(circle) = circle {
+          circle.handles()
+        } else if let Some(ellipse) = ellipse {
+          ellipse.handles()
+        } else {
+          Vec::new()
+        }
+      }
+      Measurement::Count { point, .. } => {
+        vec![*point]
+      }
+    }
+  }
+
+  /// Move a vertex, using the same indexing as `vertices`
+  pub fn set_vertex(&mut self, vertex_index: usize, new_point: Point) -> bool {
+    match self {
+      Measurement::Linear { line, polyline, .. } => {
+        if let Some(l) = line {
+          match vertex_index {
+            0 => l.start = new_point,
+            1 => l.end = new_point,
+            _ => return false,
+          }
+          true
+        } else if let Some(poly) = polyline {
+          if let Some(point) = poly.points.get_mut(vertex_index) {
+            *point = new_point;
+            true
+          } else {
+            false
+          }
+        } else {
+          false
+        }
+      }
+      Measurement::Area {
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
+      } => {
+        if let Some(rect) = rectangle {
+          if rect.is_rotated() {
+            return rect.set_corner(vertex_index, new_point);
+          }
+          match vertex_index {
+            0 => rect.start = new_point,
+            1 => rect.end = new_point,
+            _ => return false,
+          }
+          true
+        } else if let Some(poly) = polygon {
+          if let Some(point) = poly.vertex_mut(vertex_index) {
+            *point = new_point;
+            true
+          } else {
+            false
+          }
+        } else if let Some(circle) = circle {
+          circle.set_handle(vertex_index, new_point)
+        } else if let Some(ellipse) = ellipse {
+          ellipse.set_handle(vertex_index, new_point)
+        } else {
+          false
+        }
+      }
+      Measurement::Count { point, .. } => {
+        if vertex_index == 0 {
+          *point = new_point;
+          true
+        } else {
+          false
+        }
+      }
+    }
+  }
+
+  /// Add a vertex on an edge, using the same edge indexing as `segments`
+  ///
+  /// The point is moved onto the edge. Single lines become polylines and
+  /// rectangles become polygons; circles and ellipses take no vertices. Returns
+  /// the index of the new vertex, as in `vertices`.
+  pub fn insert_vertex(&mut self, edge_index: usize, point: Point) -> Option<usize> {
+    match self {
+      Measurement::Linear { line, polyline, .. } => {
+        if let Some(l) = line {
+          if edge_index != 0 {
+            return None;
+          }
+          *polyline = Some(Polyline::new(vec![l.start, l.end]));
+          *line = None;
+        }
+        polyline.as_mut()?.insert_point(edge_index, point)
+      }
+      Measurement::Area {
+        circle: Some(_), ..
+      }
+      | Measurement::Area {
+        ellipse: Some(_), ..
+      } => None,
+      Measurement::Area {
+        rectangle, polygon, ..
+      } => {
+        if let Some(rect) = rectangle {
+          if edge_index >= 4 {
+            return None;
+          }
+          *polygon = Some(Polygon::new(rect.corners()));
+          *rectangle = None;
+        }
+        polygon.as_mut()?.insert_point(edge_index, point)
+      }
+      Measurement::Count { .. } => None,
+    }
+  }
+
+  /// Remove a vertex, using the same indexing as `vertices`
+  ///
+  /// Polylines keep at least 2 vertices and polygon rings at least 3. Lines,
+  /// rectangles and counts have no vertices to spare.
+  pub fn remove_vertex(&mut self, vertex_index: usize) -> bool {
+    match self {
+      Measurement::Linear {
+        polyline: Some(polyline),
+        ..
+      } => polyline.remove_point(vertex_index),
+      Measurement::Area {
+        polygon: Some(polygon),
+        ..
+      } => polygon.remove_point(vertex_index),
+      _ => false,
+    }
+  }
+
+  /// Cut an interior ring out of an area measurement
+  ///
+  /// Rectangles, circles and ellipses are converted to polygons so they can hold the hole.
+  pub fn add_cutout(&mut self, points: Vec<Point>) -> bool {
+    if points.len() < 3 {
+      return false;
+    }
+    let outline = self.area_polygon();
+    match self {
+      Measurement::Area {
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
+      } => {
+        if polygon.is_none() {
+          *polygon = outline;
+          *rectangle = None;
+          *circle = None;
+          *ellipse = None;
+        }
+        match polygon {
+          Some(poly) => poly.add_hole(points),
+          None => false,
+        }
+      }
+      _ => false,
+    }
+  }
+
+  /// Convert to another geometry kind, keeping the ID, scale and display unit
+  ///
+  /// Depth, pitch and metadata carry over; the width of a run is dropped when it becomes
+  /// an area, and polygon holes are dropped when it becomes a polyline. Returns
+  /// `None` if the measurement is not of the kind the conversion starts from.
+  pub fn convert(&self, conversion: GeometryConversion) -> Option<Measurement> {
+    let mut converted = match (conversion, self) {
+      (
+        GeometryConversion::RectangleToPolygon,
+        Measurement::Area {
+          id,
+          rectangle: Some(rect),
+          scale,
+          display_unit,
+          depth,
+          pitch,
+          ..
+        },
+      ) => Some(Measurement::Area {
+        id: id.clone(),
+        rectangle: None,
+        polygon: Some(Polygon::new(rect.corners())),
+        circle: None,
+        ellipse: None,
+        scale: *scale,
+        display_unit: *display_unit,
+        depth: *depth,
+        pitch: *pitch,
+        metadata: None,
+      }),
+      (
+        GeometryConversion::PolylineToPolygon,
+        Measurement::Linear {
+          id,
+          polyline: Some(polyline),
+          scale,
+          display_unit,
+          depth,
+          pitch,
+          ..
+        },
+      ) => {
+        let mut points = polyline.points.clone();
+        let mut bulges: Vec<f64> = (0..points.len().saturating_sub(1))
+          .map(|i| polyline.bulge(i))
+          .collect();
+        if points.len() > 1 && points.first() == points.last() {
+          // Already closed: the last segment becomes the closing one
+          points.pop();
+        } else {
+          bulges.push(0.0);
+        }
+        if points.len() < 3 {
+          return None;
+        }
+        Some(Measurement::Area {
+          id: id.clone(),
+          rectangle: None,
+          polygon: Some(Polygon::with_bulges(points, bulges)),
+          circle: None,
+          ellipse: None,
+          scale: *scale,
+          display_unit: *display_unit,
+          depth: *depth,
+          pitch: *pitch,
+          metadata: None,
+        })
+      }
+      (
+        GeometryConversion::PolygonToPolyline,
+        Measurement::Area {
+          id,
+          polygon: Some(polygon),
+          scale,
+          display_unit,
+          depth,
+          pitch,
+          ..
+        },
+      ) => Some(Measurement::Linear {
+        id: id.clone(),
+        line: None,
+        polyline: Some(closed_polyline(polygon)),
+        scale: *scale,
+        display_unit: *display_unit,
+        width: None,
+        depth: *depth,
+        pitch: *pitch,
+        metadata: None,
+      }),
+      (
+        GeometryConversion::LineToPolyline,
+        Measurement::Linear {
+          id,
+          line: Some(l),
+          scale,
+          display_unit,
+          width,
+          depth,
+          pitch,
+          ..
+        },
+      ) => Some(Measurement::Linear {
+        id: id.clone(),
+        line: None,
+        polyline: Some(Polyline::new(vec![l.start, l.end])),
+        scale: *scale,
+        display_unit: *display_unit,
+        width: *width,
+        depth: *depth,
+        pitch: *pitch,
+        metadata: None,
+      }),
+      _ => None,
+    }?;
+    converted.set_metadata(self.metadata().cloned());
+    Some(converted)
+  }
+
+  /// The shape of an area measurement as a polygon
+  ///
+  /// Rectangles give their corners and circles four quarter arcs; ellipses are
+  /// flattened. Returns `None` for other kinds or an area without a shape.
+  pub fn area_polygon(&self) -> Option<Polygon> {
+    match self {
+      Measurement::Area {
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
+      } => {
+        if let Some(rect) = rectangle {
+          Some(Polygon::new(rect.corners()))
+        } else if let Some(poly) = polygon {
+          Some(poly.clone())
+        } else if let Some(circle) = circle {
+          Some(circle.to_arc_polygon())
+        } else {
+          ellipse.map(|ellipse| Polygon::new(ellipse.flattened_points()))
+        }
+      }
+      _ => None,
+    }
+  }
+
+  /// New linear measurements around the edges of an area, one per ring
+  ///
+  /// The outer ring comes first, then each hole. Each is a polyline ending where
+  /// it starts, with the area's scale, display unit and metadata. Returns `None`
+  /// for other kinds.
+  pub fn perimeter_measurements(&self) -> Option<Vec<Measurement>> {
+    let Measurement::Area {
+      scale,
+      display_unit,
+      metadata,
+      ..
+    } = self
+    else {
+      return None;
+    };
+    let Some(polygon) = self.area_polygon() else {
+      return Some(Vec::new());
+    };
+    let rings = std::iter::once(closed_polyline(&polygon)).chain(
+      polygon
+        .holes()
+        .iter()
+        .map(|hole| closed_polyline(&Polygon::new(hole.clone()))),
+    );
+    Some(
+      rings
+        .map(|polyline| Measurement::Linear {
+          id: generate_id("polyline"),
+          line: None,
+          polyline: Some(polyline),
+          scale: *scale,
+          display_unit: *display_unit,
+          width: None,
+          depth: None,
+          pitch: None,
+          metadata: metadata.clone(),
+        })
+        .collect(),
+    )
+  }
+
+  /// Set the scale of an area or linear measurement. Counts have no scale.
+  pub fn set_scale(&mut self, value: Scale) -> bool {
+    match self {
+      Measurement::Linear { scale, .. } | Measurement::Area { scale, .. } => {
+        *scale = value;
+        true
+      }
+      Measurement::Count { .. } => false,
+    }
+  }
+
+  /// Set the display unit of an area or linear measurement. Counts have no unit.
+  pub fn set_display_unit(&mut self, value: Unit) -> bool {
+    match self {
+      Measurement::Linear { display_unit, .. } | Measurement::Area { display_unit, .. } => {
+        *display_unit = value;
+        true
+      }
+      Measurement::Count { .. } => false,
+    }
+  }
+
+  /// Move the whole measurement by an offset
+  pub fn translate(&mut self, dx: f64, dy: f64) {
+    self.map_points(|p| Point::new(p.x + dx, p.y + dy));
+  }
+
+  /// Rotate the whole measurement about a pivot, counter-clockwise in degrees
+  ///
+  /// Rectangles stay rectangles, rotated unless they end up square to the axes.
+  pub fn rotate(&mut self, pivot: Point, degrees: f64) {
+    let (sin, cos) = exact_sin_cos(degrees);
+    self.map_points(|p| {
+      let (dx, dy) = (p.x - pivot.x, p.y - pivot.y);
+      Point::new(pivot.x + dx * cos - dy * sin, pivot.y + dx * sin + dy * cos)
+    });
+  }
+
+  /// Mirror the whole measurement across the line through `a` and `b`
+  ///
+  /// Arcs keep their shape and rectangles stay rectangles.
+  pub fn mirror(&mut self, a: Point, b: Point) -> bool {
+    let (ux, uy) = (b.x - a.x, b.y - a.y);
+    let length_squared = ux * ux + uy * uy;
+    if length_squared == 0.0 {
+      return false;
+    }
+    self.map_points(|p| {
+      let t = ((p.x - a.x) * ux + (p.y - a.y) * uy) / length_squared;
+      let (fx, fy) = (a.x + t * ux, a.y + t * uy);
+      Point::new(2.0 * fx - p.x, 2.0 * fy - p.y)
+    });
+    // Mirroring reverses the direction every arc turns
+    let bulges = match self {
+      Measurement::Linear {
+        polyline: Some(polyline),
+        ..
+      } => polyline.bulges.as_mut(),
+      Measurement::Area {
+        polygon: Some(polygon),
+        ..
+      } => polygon.bulges.as_mut(),
+      _ => None,
+    };
+    for bulge in bulges.into_iter().flatten() {
+      *bulge = -*bulge;
+    }
+    true
+  }
+
+  /// Scale the whole measurement uniformly about an origin
+  ///
+  /// The factor must be positive; use `mirror` to flip.
+  pub fn scale_about(&mut self, origin: Point, factor: f64) -> bool {
+    if !(factor > 0.0 && factor.is_finite()) {
+      return false;
+    }
+    self.map_points(|p| {
+      Point::new(
+        origin.x + (p.x - origin.x) * factor,
+        origin.y + (p.y - origin.y) * factor,
+      )
+    });
+    true
+  }
+
+  /// Apply a rigid or similarity transform to every point of the geometry
+  fn map_points(&mut self, f: impl Fn(Point) -> Point) {
+    match self {
+      Measurement::Linear { line, polyline, .. } => {
+        if let Some(l) = line {
+          l.start = f(l.start);
+          l.end = f(l.end);
+        }
+        if let Some(polyline) = polyline {
+          polyline.points.iter_mut().for_each(|p| *p = f(*p));
+        }
+      }
+      Measurement::Area {
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
+      } => {
+        if let Some(rect) = rectangle.as_mut() {
+          let corners: Vec<Point> = rect.corners().into_iter().map(&f).collect();
+          *rect = if is_axis_aligned(&corners) {
+            Rectangle::new(corners[0], corners[2])
+          } else {
+            // Follow where the first edge's direction ends up
+            let (sin, cos) = exact_sin_cos(rect.rotation());
+            let along = f(Point::new(rect.start.x + cos, rect.start.y + sin));
+            let degrees = (along.y - corners[0].y)
+              .atan2(along.x - corners[0].x)
+              .to_degrees();
+            Rectangle::rotated(corners[0], corners[2], degrees)
+          };
+        } else if let Some(poly) = polygon {
+          poly.points.iter_mut().for_each(|p| *p = f(*p));
+          for hole in poly.holes.iter_mut().flatten() {
+            hole.iter_mut().for_each(|p| *p = f(*p));
+          }
+        } else if let Some(circle) = circle {
+          let edge = f(circle.quadrants()[0]);
+          circle.center = f(circle.center);
+          circle.radius = circle.center.distance_to(&edge);
+        } else if let Some(ellipse) = ellipse {
+          let center = f(ellipse.center);
+          let (along, across) = (f(ellipse.point_at(0.0)), f(ellipse.point_at(FRAC_PI_2)));
+          let degrees = (along.y - center.y).atan2(along.x - center.x).to_degrees();
+          *ellipse = Ellipse::rotated(
+            center,
+            center.distance_to(&along),
+            center.distance_to(&across),
+            degrees,
+          );
+        }
+      }
+      Measurement::Count { point, .. } => *point = f(*point),
+    }
+  }
+
   /// Calculate pixel area for area measurements
   pub fn pixel_area(&self) -> Option<f64> {
     match self {
       Measurement::Area {
-        rectangle, polygon, ..
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
       } => {
         if let Some(rect) = rectangle {
           Some(rect.area())
         } else if let Some(poly) = polygon {
           Some(poly.area())
+        } else if let Some(circle) = circle {
+          Some(circle.area())
         } else {
-          None
+          ellipse.map(|ellipse| ellipse.area())
         }
       }
       _ => None,
     }
   }
 
+  /// Calculate the area along the slope in a specific unit
+  ///
+  /// Equal to the plan area unless the measurement has a pitch.
   pub fn real_area_with_target(&self, scale: &Scale, target_unit: &Unit) -> Option<f64> {
+    let plan_area = self.plan_area_with_target(scale, target_unit)?;
+    Some(plan_area * self.slope_factor())
+  }
+
+  /// Calculate the area projected onto the plan in a specific unit, ignoring any pitch
+  pub fn plan_area_with_target(&self, scale: &Scale, target_unit: &Unit) -> Option<f64> {
     match self {
       Measurement::Area { .. } => {
         let pixel_area = self.pixel_area().unwrap_or(0.0);
@@ -88,11 +803,149 @@ impl Measurement {
     }
   }
 
+  /// Area projected onto the plan, ignoring any pitch
+  pub fn plan_area(&self) -> Option<f64> {
+    match self {
+      Measurement::Area {
+        scale,
+        display_unit,
+        ..
+      } => self.plan_area_with_target(scale, display_unit),
+      _ => None,
+    }
+  }
+
+  /// Pitch of an area or linear measurement
+  pub fn pitch(&self) -> Option<Pitch> {
+    match self {
+      Measurement::Linear { pitch, .. } | Measurement::Area { pitch, .. } => *pitch,
+      Measurement::Count { .. } => None,
+    }
+  }
+
+  /// Set the pitch of an area or linear measurement. Counts have no pitch.
+  pub fn set_pitch(&mut self, value: Option<Pitch>) -> bool {
+    match self {
+      Measurement::Linear { pitch, .. } | Measurement::Area { pitch, .. } => {
+        *pitch = value;
+        true
+      }
+      Measurement::Count { .. } => false,
+    }
+  }
+
+  /// Ratio of real to plan values, 1 without a pitch
+  pub fn slope_factor(&self) -> f64 {
+    self.pitch().map_or(1.0, |pitch| pitch.factor())
+  }
+
+  /// Calculate the length along the slope in a specific unit
+  pub fn real_length_with_target(&self, scale: &Scale, target_unit: &Unit) -> Option<f64> {
+    let plan_length = scale.pixel_to_real_unit(self.pixel_length()?, *target_unit);
+    Some(plan_length * self.slope_factor())
+  }
+
+  /// Calculate the length along the slope in the display unit
+  pub fn real_length(&self) -> Option<f64> {
+    match self {
+      Measurement::Linear {
+        scale,
+        display_unit,
+        ..
+      } => self.real_length_with_target(scale, display_unit),
+      _ => None,
+    }
+  }
+
+  /// Depth of an area or linear measurement
+  pub fn depth(&self) -> Option<Dimension> {
+    match self {
+      Measurement::Linear { depth, .. } | Measurement::Area { depth, .. } => *depth,
+      Measurement::Count { .. } => None,
+    }
+  }
+
+  /// Width of a linear measurement
+  pub fn width(&self) -> Option<Dimension> {
+    match self {
+      Measurement::Linear { width, .. } => *width,
+      _ => None,
+    }
+  }
+
+  /// Set the depth of an area or linear measurement. Counts have no depth.
+  pub fn set_depth(&mut self, value: Option<Dimension>) -> bool {
+    match self {
+      Measurement::Linear { depth, .. } | Measurement::Area { depth, .. } => {
+        *depth = value;
+        true
+      }
+      Measurement::Count { .. } => false,
+    }
+  }
+
+  /// Set the width of a linear measurement
+  pub fn set_width(&mut self, value: Option<Dimension>) -> bool {
+    match self {
+      Measurement::Linear { width, .. } => {
+        *width = value;
+        true
+      }
+      _ => false,
+    }
+  }
+
+  /// Calculate the volume in a specific unit
+  ///
+  /// Areas need a depth; linear measurements need both a width and a depth.
+  pub fn real_volume_with_target(&self, target_unit: VolumeUnit) -> Option<f64> {
+    match self {
+      Measurement::Area {
+        display_unit,
+        depth,
+        ..
+      } => Some(UnitUtils::area_to_volume(
+        self.real_area()?,
+        *display_unit,
+        (*depth)?,
+        target_unit,
+      )),
+      Measurement::Linear {
+        scale,
+        display_unit,
+        width,
+        depth,
+        ..
+      } => Some(UnitUtils::length_to_volume(
+        self.real_length_with_target(scale, display_unit)?,
+        *display_unit,
+        (*width)?,
+        (*depth)?,
+        target_unit,
+      )),
+      Measurement::Count { .. } => None,
+    }
+  }
+
+  /// Calculate the volume in the usual volume unit for the display unit
+  pub fn real_volume(&self) -> Option<f64> {
+    match self {
+      Measurement::Linear { display_unit, .. } | Measurement::Area { display_unit, .. } => {
+        self.real_volume_with_target(display_unit.volume_unit())
+      }
+      Measurement::Count { .. } => None,
+    }
+  }
+
   /// Calculate pixel perimeter for area measurements
   pub fn pixel_perimeter(&self) -> Option<f64> {
     match self {
       Measurement::Area {
-        rectangle, polygon, ..
+        rectangle,
+        polygon,
+        circle,
+        ellipse,
+        ..
       } => {
         if let Some(rect) = rectangle {
           Some(rect.perimeter())
@@ -102,8 +955,10 @@ impl Measurement {
           } else {
             Some(poly.perimeter())
           }
+        } else if let Some(circle) = circle {
+          Some(circle.circumference())
         } else {
-          None
+          ellipse.map(|ellipse| ellipse.circumference())
         }
       }
       _ => None,
@@ -116,10 +971,8 @@ impl Measurement {
       Measurement::Linear { line, polyline, .. } => {
         if let Some(l) = line {
           Some(l.length())
-        } else if let Some(poly) = polyline {
-          Some(poly.length())
         } else {
-          None
+          polyline.as_ref().map(|poly| poly.length())
         }
       }
       _ => None,
@@ -128,32 +981,97 @@ impl Measurement {
 
   /// Get the display value as a string
   pub fn display_value(&self) -> String {
+    self.display_value_with(&DisplayFormat::default())
+  }
+
+  /// Get the display value as a string using the given format
+  ///
+  /// Pitched measurements show the real value followed by the plan value.
+  pub fn display_value_with(&self, format: &DisplayFormat) -> String {
     match self {
       Measurement::Linear {
         scale,
         display_unit,
+        pitch,
         ..
       } => {
-        if let Some(pixel_length) = self.pixel_length() {
-          let real_length = scale.pixel_to_real_unit(pixel_length, *display_unit);
-          format!("{:.2} {}", real_length, display_unit.display())
-        } else {
-          "0.00".to_string()
+        let plan_length = self
+          .pixel_length()
+          .map(|pixel_length| scale.pixel_to_real_unit(pixel_length, *display_unit))
+          .unwrap_or(0.0);
+        let real = format.format_length(plan_length * self.slope_factor(), *display_unit);
+        match pitch {
+          Some(_) => format!(
+            "{real} (plan {})",
+            format.format_length(plan_length, *display_unit)
+          ),
+          None => real,
         }
       }
-      Measurement::Area { display_unit, .. } => {
-        let real_area = self.real_area().unwrap_or(0.0);
-        format!("{:.2} {}²", real_area, display_unit.display())
+      Measurement::Area {
+        display_unit,
+        pitch,
+        ..
+      } => {
+        let real = format.format_area(self.real_area().unwrap_or(0.0), *display_unit);
+        match pitch {
+          Some(_) => format!(
+            "{real} (plan {})",
+            format.format_area(self.plan_area().unwrap_or(0.0), *display_unit)
+          ),
+          None => real,
+        }
       }
       Measurement::Count { .. } => "1".to_string(),
     }
   }
+
+  /// Get the volume as a display string, e.g. `1.23 CY`
+  pub fn display_volume(&self, unit: VolumeUnit, format: &DisplayFormat) -> Option<String> {
+    let volume = self.real_volume_with_target(unit)?;
+    Some(format.format_volume(volume, unit))
+  }
+}
+
+/// New measurement ID such as `area_` followed by a UUID without dashes
+pub(crate) fn generate_id(prefix: &str) -> String {
+  format!(
+    "{prefix}_{}",
+    uuid::Uuid::new_v4().to_string().replace('-', "")
+  )
+}
+
+/// Straight segments around a closed ring of points
+fn ring_segments(points: &[Point]) -> Vec<ArcSegment> {
+  (0..points.len())
+    .map(|i| ArcSegment::new(points[i], points[(i + 1) % points.len()], 0.0))
+    .collect()
+}
+
+/// Polyline around the outer ring of a polygon, repeating the first point at the end
+fn closed_polyline(polygon: &Polygon) -> Polyline {
+  let mut points = polygon.points.clone();
+  let bulges = (0..points.len()).map(|i| polygon.bulge(i)).collect();
+  if let Some(first) = points.first() {
+    points.push(*first);
+  }
+  Polyline::with_bulges(points, bulges)
+}
+
+/// Whether a ring of four corners has only horizontal and vertical edges
+fn is_axis_aligned(corners: &[Point]) -> bool {
+  const EPSILON: f64 = 1e-9;
+  corners
+    .iter()
+    .zip(corners.iter().cycle().skip(1))
+    .all(|(a, b)| (a.x - b.x).abs() < EPSILON || (a.y - b.y).abs() < EPSILON)
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
-  use crate::{Point, Polygon, Rectangle, Scale, Unit};
+  use crate::{Point, Polygon, Polyline, Rectangle, Scale, Unit};
+  use std::f64::consts::PI;
 
   #[test]
   fn test_negative_area() {
@@ -170,8 +1088,13 @@ mod tests {
       id: "poly1".to_string(),
       rectangle: None,
       polygon: Some(polygon),
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
 
     let area = measurement.real_area().unwrap();
@@ -184,6 +1107,7 @@ mod tests {
     let rect = Rectangle {
       start: Point { x: 0.0, y: 0.0 },
       end: Point { x: 100.0, y: 50.0 },
+      rotation: None,
     };
 
     // Scale: 100 pixels == 2 meters
@@ -193,8 +1117,13 @@ mod tests {
       id: "rect1".to_string(),
       rectangle: Some(rect),
       polygon: None,
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
 
     // Area in pixels: 100 * 50 = 5000
@@ -236,8 +1165,13 @@ mod tests {
       id: "poly1".to_string(),
       rectangle: None,
       polygon: Some(polygon),
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
 
     // 20 pixels = 1 foot -> 1 pixel = 0.05 foot
@@ -258,6 +1192,7 @@ mod tests {
     let rect = Rectangle {
       start: Point { x: 0.0, y: 0.0 },
       end: Point { x: 50.0, y: 40.0 },
+      rotation: None,
     };
     // Scale: 10 pixels = 1 meter
     let scale = Scale::new(10.0, 1.0, Unit::Meters);
@@ -267,8 +1202,13 @@ mod tests {
       id: "rectCm".to_string(),
       rectangle: Some(rect),
       polygon: None,
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Centimeters,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
 
     // Each 10 pixels = 1 meter, so 1 px = 0.1 m = 10 cm
@@ -295,14 +1235,19 @@ mod tests {
       y: start.y + (140.0 * ratio),
     };
 
-    let rectangle = Rectangle { start, end };
+    let rectangle = Rectangle::new(start, end);
 
     let rect_measurement = Measurement::Area {
       id: "rect".to_string(),
       rectangle: Some(rectangle),
       polygon: None,
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
 
     let polygon = Polygon::new(vec![
@@ -322,8 +1267,13 @@ mod tests {
       id: "poly".to_string(),
       rectangle: None,
       polygon: Some(polygon),
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
 
     let rect_pixel_area = rect_measurement.pixel_area().unwrap();
@@ -343,6 +1293,7 @@ mod tests {
     let rect = Rectangle {
       start: Point { x: 0.0, y: 0.0 },
       end: Point { x: 100.0, y: 50.0 },
+      rotation: None,
     };
     let polygon = Polygon::new(vec![
       Point { x: 0.0, y: 0.0 },
@@ -356,15 +1307,25 @@ mod tests {
       rectangle: Some(rect),
       // polygon: Some(polygon),
       polygon: None,
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
     let poly_measurement = Measurement::Area {
       id: "poly".to_string(),
       rectangle: None,
       polygon: Some(polygon),
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
     println!(
       "rect_measurement: {:?}",
@@ -378,6 +1339,384 @@ mod tests {
     assert!(rect_measurement.real_area().unwrap() == poly_measurement.real_area().unwrap());
   }
 
+  #[test]
+  fn test_real_area_polygon_with_holes() {
+    // 100 x 100 px slab with a 20 x 10 px shaft and a 10 x 10 px stairwell
+    let polygon = Polygon::with_holes(
+      vec![
+        Point::new(0.0, 0.0),
+        Point::new(100.0, 0.0),
+        Point::new(100.0, 100.0),
+        Point::new(0.0, 100.0),
+      ],
+      vec![
+        vec![
+          Point::new(10.0, 10.0),
+          Point::new(30.0, 10.0),
+          Point::new(30.0, 20.0),
+          Point::new(10.0, 20.0),
+        ],
+        vec![
+          Point::new(50.0, 50.0),
+          Point::new(50.0, 60.0),
+          Point::new(60.0, 60.0),
+          Point::new(60.0, 50.0),
+        ],
+      ],
+    );
+    let measurement = Measurement::Area {
+      id: "slab".to_string(),
+      rectangle: None,
+      polygon: Some(polygon),
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+
+    assert_eq!(measurement.pixel_area().unwrap(), 10000.0 - 200.0 - 100.0);
+    assert_eq!(measurement.pixel_perimeter().unwrap(), 400.0 + 60.0 + 40.0);
+    assert!((measurement.real_area().unwrap() - 97.0).abs() < 1e-10);
+  }
+
+  #[test]
+  fn test_add_cutout_to_rectangle() {
+    let mut measurement = Measurement::Area {
+      id: "rect".to_string(),
+      rectangle: Some(Rectangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(100.0, 50.0),
+      )),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+
+    assert!(measurement.add_cutout(vec![
+      Point::new(10.0, 10.0),
+      Point::new(20.0, 10.0),
+      Point::new(20.0, 20.0),
+    ]));
+    assert!(!measurement.add_cutout(vec![Point::new(0.0, 0.0)]));
+    // Not inside the outline
+    assert!(!measurement.add_cutout(vec![
+      Point::new(90.0, 40.0),
+      Point::new(110.0, 40.0),
+      Point::new(110.0, 45.0),
+    ]));
+    // Overlapping the first hole
+    assert!(!measurement.add_cutout(vec![
+      Point::new(15.0, 12.0),
+      Point::new(30.0, 12.0),
+      Point::new(30.0, 30.0),
+    ]));
+    assert_eq!(measurement.pixel_area().unwrap(), 5000.0 - 50.0);
+
+    if let Measurement::Area {
+      rectangle, polygon, ..
+    } = &measurement
+    {
+      assert!(rectangle.is_none());
+      assert_eq!(polygon.as_ref().unwrap().holes().len(), 1);
+    } else {
+      panic!("Measurement should be Area");
+    }
+  }
+
+  #[test]
+  fn test_translate_moves_holes() {
+    let mut measurement = Measurement::Area {
+      id: "slab".to_string(),
+      rectangle: None,
+      polygon: Some(Polygon::with_holes(
+        vec![
+          Point::new(0.0, 0.0),
+          Point::new(100.0, 0.0),
+          Point::new(100.0, 100.0),
+          Point::new(0.0, 100.0),
+        ],
+        vec![vec![
+          Point::new(10.0, 10.0),
+          Point::new(20.0, 10.0),
+          Point::new(20.0, 20.0),
+        ]],
+      )),
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    let area = measurement.pixel_area().unwrap();
+
+    measurement.translate(5.0, -5.0);
+    assert_eq!(measurement.vertices()[0], Point::new(5.0, -5.0));
+    assert_eq!(measurement.vertices()[4], Point::new(15.0, 5.0));
+    assert_eq!(measurement.pixel_area().unwrap(), area);
+
+    assert!(measurement.set_display_unit(Unit::Feet));
+    let mut count = Measurement::Count {
+      id: "c".to_string(),
+      point: Point::new(1.0, 1.0),
+      metadata: None,
+    };
+    assert!(!count.set_display_unit(Unit::Feet));
+    count.translate(1.0, 2.0);
+    assert_eq!(count.vertices(), vec![Point::new(2.0, 3.0)]);
+  }
+
+  #[test]
+  fn test_rotate_mirror_and_scale() {
+    let rect = Measurement::Area {
+      id: "rect".to_string(),
+      rectangle: Some(Rectangle::new(Point::new(0.0, 0.0), Point::new(40.0, 20.0))),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+
+    // A quarter turn keeps the rectangle
+    let mut turned = rect.clone();
+    turned.rotate(Point::new(0.0, 0.0), 90.0);
+    assert!(matches!(
+      &turned,
+      Measurement::Area { rectangle: Some(r), .. } if r.end == Point::new(-20.0, 40.0)
+    ));
+
+    // Other angles keep it as a rotated rectangle with the same area
+    let mut tilted = rect.clone();
+    tilted.rotate(Point::new(20.0, 10.0), 30.0);
+    assert!(matches!(
+      &tilted,
+      Measurement::Area { rectangle: Some(r), polygon: None, .. }
+        if (r.rotation() - 30.0).abs() < 1e-9
+    ));
+    assert!((tilted.pixel_area().unwrap() - 800.0).abs() < 1e-9);
+    assert_eq!(tilted.vertices().len(), 4);
+
+    // Mirrored across a slanted axis, the first edge turns the other way
+    let mut flipped = tilted.clone();
+    assert!(flipped.mirror(Point::new(0.0, 0.0), Point::new(1.0, 0.0)));
+    assert!(matches!(
+      &flipped,
+      Measurement::Area { rectangle: Some(r), .. } if (r.rotation() + 30.0).abs() < 1e-9
+    ));
+    assert!((flipped.pixel_area().unwrap() - 800.0).abs() < 1e-9);
+    let expected: Vec<Point> = tilted
+      .vertices()
+      .iter()
+      .map(|p| Point::new(p.x, -p.y))
+      .collect();
+    for (a, b) in flipped.vertices().iter().zip(&expected) {
+      assert!(a.distance_to(b) < 1e-9);
+    }
+
+    // Turning it back lines it up with the axes again
+    tilted.rotate(Point::new(20.0, 10.0), -30.0);
+    assert!(matches!(
+      &tilted,
+      Measurement::Area { rectangle: Some(r), .. } if r.rotation.is_none()
+    ));
+
+    let mut scaled = rect.clone();
+    assert!(scaled.scale_about(Point::new(0.0, 0.0), 2.0));
+    assert_eq!(scaled.pixel_area().unwrap(), 3200.0);
+    assert!(!scaled.scale_about(Point::new(0.0, 0.0), 0.0));
+
+    let mut arc = Measurement::Linear {
+      id: "arc".to_string(),
+      line: None,
+      polyline: Some(Polyline::with_bulges(
+        vec![Point::new(0.0, 0.0), Point::new(10.0, 0.0)],
+        vec![1.0],
+      )),
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    let length = arc.pixel_length().unwrap();
+    assert!(arc.mirror(Point::new(0.0, 5.0), Point::new(10.0, 5.0)));
+    assert_eq!(arc.vertices()[1], Point::new(10.0, 10.0));
+    assert!(matches!(
+      &arc,
+      Measurement::Linear { polyline: Some(p), .. } if p.bulge(0) == -1.0
+    ));
+    assert!((arc.pixel_length().unwrap() - length).abs() < 1e-9);
+    assert!(!arc.mirror(Point::new(1.0, 1.0), Point::new(1.0, 1.0)));
+  }
+
+  #[test]
+  fn test_insert_and_remove_vertex() {
+    let mut wall = Measurement::Linear {
+      id: "wall".to_string(),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0))),
+      polyline: None,
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(!wall.remove_vertex(0));
+    assert_eq!(wall.insert_vertex(0, Point::new(4.0, 1.0)), Some(1));
+    assert_eq!(wall.vertices()[1], Point::new(4.0, 0.0));
+    assert_eq!(wall.pixel_length().unwrap(), 10.0);
+    assert!(wall.set_vertex(1, Point::new(4.0, 3.0)));
+    assert!(wall.remove_vertex(1));
+    assert_eq!(wall.pixel_length().unwrap(), 10.0);
+    assert!(!wall.remove_vertex(0));
+
+    let mut room = Measurement::Area {
+      id: "room".to_string(),
+      rectangle: Some(Rectangle::new(Point::new(0.0, 0.0), Point::new(10.0, 10.0))),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(!room.remove_vertex(0));
+    // The top edge runs from (10,10) back to (0,10)
+    assert_eq!(room.insert_vertex(2, Point::new(5.0, 12.0)), Some(3));
+    assert!(room.set_vertex(3, Point::new(5.0, 20.0)));
+    assert_eq!(room.pixel_area().unwrap(), 150.0);
+    assert!(room.remove_vertex(3));
+    assert_eq!(room.pixel_area().unwrap(), 100.0);
+    assert!(room.remove_vertex(0));
+    assert_eq!(room.pixel_area().unwrap(), 50.0);
+    assert!(!room.remove_vertex(0));
+    assert_eq!(room.insert_vertex(9, Point::new(0.0, 0.0)), None);
+  }
+
+  #[test]
+  fn test_insert_vertex_keeps_arcs() {
+    let mut arc = Measurement::Linear {
+      id: "arc".to_string(),
+      line: None,
+      polyline: Some(Polyline::with_bulges(
+        vec![
+          Point::new(0.0, 0.0),
+          Point::new(2.0, 0.0),
+          Point::new(4.0, 0.0),
+        ],
+        vec![1.0, 0.0],
+      )),
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    let length = arc.pixel_length().unwrap();
+    assert_eq!(arc.insert_vertex(0, Point::new(1.0, -3.0)), Some(1));
+    assert!((arc.pixel_length().unwrap() - length).abs() < 1e-9);
+    let point = arc.vertices()[1];
+    assert!((point.x - 1.0).abs() < 1e-9 && (point.y + 1.0).abs() < 1e-9);
+
+    // Removing the split point joins its neighbours with a straight segment
+    assert!(arc.remove_vertex(1));
+    assert!((arc.pixel_length().unwrap() - 4.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_arc_polygon_area_and_perimeter() {
+    use std::f64::consts::PI;
+    // 2 x 2 square whose bottom edge is replaced by an outward half circle
+    let counterclockwise = Polygon::with_bulges(
+      vec![
+        Point::new(0.0, 0.0),
+        Point::new(2.0, 0.0),
+        Point::new(2.0, 2.0),
+        Point::new(0.0, 2.0),
+      ],
+      vec![1.0, 0.0, 0.0, 0.0],
+    );
+    let clockwise = Polygon::with_bulges(
+      vec![
+        Point::new(0.0, 0.0),
+        Point::new(0.0, 2.0),
+        Point::new(2.0, 2.0),
+        Point::new(2.0, 0.0),
+      ],
+      vec![0.0, 0.0, 0.0, -1.0],
+    );
+    for polygon in [counterclockwise, clockwise] {
+      assert!((polygon.area() - (4.0 + PI / 2.0)).abs() < 1e-10);
+      assert!((polygon.perimeter() - (6.0 + PI)).abs() < 1e-10);
+    }
+
+    // The same half circle bulging inward removes area instead
+    let inward = Polygon::with_bulges(
+      vec![
+        Point::new(0.0, 0.0),
+        Point::new(2.0, 0.0),
+        Point::new(2.0, 2.0),
+        Point::new(0.0, 2.0),
+      ],
+      vec![-1.0, 0.0, 0.0, 0.0],
+    );
+    let measurement = Measurement::Area {
+      id: "arc".to_string(),
+      rectangle: None,
+      polygon: Some(inward),
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(1.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!((measurement.pixel_area().unwrap() - (4.0 - PI / 2.0)).abs() < 1e-10);
+  }
+
+  #[test]
+  fn test_arc_polyline_length() {
+    use std::f64::consts::PI;
+    let measurement = Measurement::Linear {
+      id: "curb".to_string(),
+      line: None,
+      polyline: Some(Polyline::with_bulges(
+        vec![
+          Point::new(0.0, 0.0),
+          Point::new(10.0, 0.0),
+          Point::new(20.0, 0.0),
+        ],
+        vec![0.0, 1.0],
+      )),
+      scale: Scale::new(1.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!((measurement.pixel_length().unwrap() - (10.0 + 5.0 * PI)).abs() < 1e-10);
+  }
+
   #[test]
   fn test_real_area_returns_none_for_non_area() {
     let m = Measurement::Linear {
@@ -386,12 +1725,17 @@ mod tests {
       polyline: None,
       scale: Scale::new(100.0, 2.0, Unit::Meters),
       display_unit: Unit::Meters,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
     assert!(m.real_area().is_none());
 
     let m2 = Measurement::Count {
       id: "ct".to_string(),
       point: Point { x: 0.0, y: 0.0 },
+      metadata: None,
     };
     assert!(m2.real_area().is_none());
   }
@@ -403,9 +1747,405 @@ mod tests {
       id: "empty".to_string(),
       rectangle: None,
       polygon: None,
+      circle: None,
+      ellipse: None,
       scale,
       display_unit: Unit::Meters,
+      depth: None,
+      pitch: None,
+      metadata: None,
     };
     assert!(m.real_area().unwrap() == 0.0);
   }
+
+  #[test]
+  fn test_display_value_with_format() {
+    // 150 px at 12 px per foot is 12'-6"
+    let linear = Measurement::Linear {
+      id: "line".to_string(),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(150.5, 0.0))),
+      polyline: None,
+      scale: Scale::new(12.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert_eq!(linear.display_value(), "12.54 ft");
+    assert_eq!(
+      linear.display_value_with(&DisplayFormat::feet_inches_fraction(2)),
+      "12'-6 1/2\""
+    );
+
+    let area = Measurement::Area {
+      id: "rect".to_string(),
+      rectangle: Some(Rectangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(120.0, 120.0),
+      )),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(12.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    let format = DisplayFormat {
+      area_suffix: Some(crate::AreaSuffix::Estimator),
+      precision: Some(0),
+      ..DisplayFormat::feet_inches()
+    };
+    assert_eq!(area.display_value_with(&format), "100 SF");
+  }
+
+  #[test]
+  fn test_real_volume() {
+    // 10' x 10' slab at 12 px per foot, 4" thick
+    let mut slab = Measurement::Area {
+      id: "slab".to_string(),
+      rectangle: Some(Rectangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(120.0, 120.0),
+      )),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(12.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(slab.real_volume().is_none());
+    assert!(slab.set_depth(Some(Dimension::new(4.0, Unit::Inches))));
+    let cubic_feet = slab.real_volume_with_target(VolumeUnit::CubicFeet).unwrap();
+    assert!((cubic_feet - 100.0 / 3.0).abs() < 1e-4);
+    assert!((slab.real_volume().unwrap() - 100.0 / 81.0).abs() < 1e-5);
+    assert!(!slab.set_width(Some(Dimension::new(1.0, Unit::Feet))));
+
+    // 27' footing, 2' wide and 6" deep is one cubic yard
+    let mut footing = Measurement::Linear {
+      id: "footing".to_string(),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(27.0, 0.0))),
+      polyline: None,
+      scale: Scale::new(1.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: Some(Dimension::new(2.0, Unit::Feet)),
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(footing.real_volume().is_none());
+    footing.set_depth(Some(Dimension::new(6.0, Unit::Inches)));
+    assert!((footing.real_volume().unwrap() - 1.0).abs() < 1e-5);
+    assert_eq!(
+      footing
+        .display_volume(VolumeUnit::CubicYards, &DisplayFormat::default())
+        .as_deref(),
+      Some("1.00 CY")
+    );
+
+    // Metric display units default to cubic meters
+    let pit = Measurement::Area {
+      id: "pit".to_string(),
+      rectangle: Some(Rectangle::new(Point::new(0.0, 0.0), Point::new(2.0, 3.0))),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(1.0, 1.0, Unit::Meters),
+      display_unit: Unit::Meters,
+      depth: Some(Dimension::new(50.0, Unit::Centimeters)),
+      pitch: None,
+      metadata: None,
+    };
+    assert!((pit.real_volume().unwrap() - 3.0).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_pitch_applies_slope_factor() {
+    // 10' x 10' roof plan at 12 px per foot
+    let mut roof = Measurement::Area {
+      id: "roof".to_string(),
+      rectangle: Some(Rectangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(120.0, 120.0),
+      )),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(12.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(roof.set_pitch(Some(Pitch::new(12.0, 12.0))));
+    let sloped = 100.0 * 2.0_f64.sqrt();
+    assert!((roof.real_area().unwrap() - sloped).abs() < 1e-9);
+    assert!((roof.plan_area().unwrap() - 100.0).abs() < 1e-9);
+    let sloped_yards = roof
+      .real_area_with_target(&Scale::new(12.0, 1.0, Unit::Feet), &Unit::Yards)
+      .unwrap();
+    assert!((sloped_yards - sloped / 9.0).abs() < 1e-6);
+    assert_eq!(roof.display_value(), "141.42 ft² (plan 100.00 ft²)");
+
+    // 3/4 pitch ramp: 40' of plan is 50' along the slope
+    let ramp = Measurement::Linear {
+      id: "ramp".to_string(),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(40.0, 0.0))),
+      polyline: None,
+      scale: Scale::new(1.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: Some(Pitch::new(3.0, 4.0)),
+      metadata: None,
+    };
+    assert!((ramp.real_length().unwrap() - 50.0).abs() < 1e-9);
+    assert_eq!(ramp.pixel_length(), Some(40.0));
+    assert_eq!(ramp.display_value(), "50.00 ft (plan 40.00 ft)");
+  }
+
+  #[test]
+  fn test_geometry_conversions() {
+    let scale = Scale::new(10.0, 1.0, Unit::Feet);
+    let room = Measurement::Area {
+      id: "room".to_string(),
+      rectangle: Some(Rectangle::new(Point::new(0.0, 0.0), Point::new(40.0, 30.0))),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale,
+      display_unit: Unit::Inches,
+      depth: Some(Dimension::new(6.0, Unit::Inches)),
+      pitch: None,
+      metadata: None,
+    };
+    let polygon = room
+      .convert(GeometryConversion::RectangleToPolygon)
+      .unwrap();
+    assert_eq!(polygon.id(), "room");
+    assert_eq!(polygon.vertices().len(), 4);
+    assert_eq!(polygon.real_area(), room.real_area());
+    assert_eq!(polygon.depth(), room.depth());
+    assert!(room
+      .convert(GeometryConversion::PolygonToPolyline)
+      .is_none());
+
+    // Round trip through a closed polyline keeps the shape and its arcs
+    let mut arched = polygon.clone();
+    if let Measurement::Area {
+      polygon: Some(p),
+      circle: None,
+      ellipse: None,
+      ..
+    } = &mut arched
+    {
+      p.set_bulge(1, 0.5);
+    }
+    let outline = arched
+      .convert(GeometryConversion::PolygonToPolyline)
+      .unwrap();
+    assert!(matches!(outline, Measurement::Linear { .. }));
+    assert_eq!(outline.vertices().len(), 5);
+    assert!((outline.pixel_length().unwrap() - arched.pixel_perimeter().unwrap()).abs() < 1e-9);
+    let back = outline
+      .convert(GeometryConversion::PolylineToPolygon)
+      .unwrap();
+    assert_eq!(back.id(), "room");
+    assert_eq!(back.segments(), arched.segments());
+    assert_eq!(back.depth(), arched.depth());
+
+    // An open polyline is closed back to its start
+    let run = Measurement::Linear {
+      id: "run".to_string(),
+      line: None,
+      polyline: Some(Polyline::new(vec![
+        Point::new(0.0, 0.0),
+        Point::new(10.0, 0.0),
+        Point::new(10.0, 10.0),
+      ])),
+      scale,
+      display_unit: Unit::Feet,
+      width: Some(Dimension::new(1.0, Unit::Feet)),
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    let closed = run.convert(GeometryConversion::PolylineToPolygon).unwrap();
+    assert_eq!(closed.pixel_area(), Some(50.0));
+
+    let wall = Measurement::Linear {
+      id: "wall".to_string(),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0))),
+      polyline: None,
+      scale,
+      display_unit: Unit::Feet,
+      width: Some(Dimension::new(1.0, Unit::Feet)),
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(wall
+      .convert(GeometryConversion::PolylineToPolygon)
+      .is_none());
+    let editable = wall.convert(GeometryConversion::LineToPolyline).unwrap();
+    assert_eq!(editable.real_length(), wall.real_length());
+    assert_eq!(editable.width(), wall.width());
+  }
+
+  #[test]
+  fn test_perimeter_measurements() {
+    let mut slab = Measurement::Area {
+      id: "slab".to_string(),
+      rectangle: Some(Rectangle::new(Point::new(0.0, 0.0), Point::new(40.0, 30.0))),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    let outline = slab.perimeter_measurements().unwrap();
+    assert_eq!(outline.len(), 1);
+    assert_ne!(outline[0].id(), "slab");
+    assert!((outline[0].real_length().unwrap() - 14.0).abs() < 1e-9);
+
+    slab.add_cutout(vec![
+      Point::new(10.0, 10.0),
+      Point::new(20.0, 10.0),
+      Point::new(20.0, 20.0),
+    ]);
+    let outlines = slab.perimeter_measurements().unwrap();
+    assert_eq!(outlines.len(), 2);
+    let total: f64 = outlines.iter().filter_map(|m| m.pixel_length()).sum();
+    assert!((total - slab.pixel_perimeter().unwrap()).abs() < 1e-9);
+
+    let marker = Measurement::Count {
+      id: "c".to_string(),
+      point: Point::new(0.0, 0.0),
+      metadata: None,
+    };
+    assert!(marker.perimeter_measurements().is_none());
+  }
+
+  #[test]
+  fn test_circle_and_ellipse_areas() {
+    let area = |circle, ellipse| Measurement::Area {
+      id: "round".to_string(),
+      rectangle: None,
+      polygon: None,
+      circle,
+      ellipse,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    let mut column = area(Some(Circle::new(Point::new(50.0, 50.0), 10.0)), None);
+    assert!((column.real_area().unwrap() - PI).abs() < 1e-9);
+    assert!((column.pixel_perimeter().unwrap() - 20.0 * PI).abs() < 1e-9);
+    assert!(column.contains_point(&Point::new(57.0, 57.0)));
+    assert!(!column.contains_point(&Point::new(58.0, 58.0)));
+    let (min, max) = column.bounds().unwrap();
+    assert!(min.distance_to(&Point::new(40.0, 40.0)) < 1e-9);
+    assert!(max.distance_to(&Point::new(60.0, 60.0)) < 1e-9);
+    let marker = Measurement::Count {
+      id: "c".to_string(),
+      point: Point::new(100.0, 0.0),
+      metadata: None,
+    };
+    let (min, max) = Measurement::combined_bounds([&column, &marker]).unwrap();
+    assert!(min.distance_to(&Point::new(40.0, 0.0)) < 1e-9);
+    assert!(max.distance_to(&Point::new(100.0, 60.0)) < 1e-9);
+    let center = Measurement::center_of([&column, &marker]).unwrap();
+    assert!(center.distance_to(&Point::new(70.0, 30.0)) < 1e-9);
+    assert!(Measurement::combined_bounds([]).is_none());
+
+    // Dragging a quadrant handle sets the radius
+    assert_eq!(column.vertices().len(), 5);
+    assert!(column.set_vertex(2, Point::new(50.0, 70.0)));
+    assert!((column.pixel_area().unwrap() - 400.0 * PI).abs() < 1e-9);
+    assert_eq!(column.insert_vertex(0, Point::new(70.0, 50.0)), None);
+
+    column.scale_about(Point::new(0.0, 0.0), 0.5);
+    assert!((column.pixel_perimeter().unwrap() - 20.0 * PI).abs() < 1e-9);
+
+    let mut tank = area(None, Some(Ellipse::new(Point::new(0.0, 0.0), 20.0, 10.0)));
+    assert!((tank.pixel_area().unwrap() - 200.0 * PI).abs() < 1e-9);
+    tank.rotate(Point::new(0.0, 0.0), 90.0);
+    assert!(matches!(
+      &tank,
+      Measurement::Area { ellipse: Some(e), .. } if (e.rotation() - 90.0).abs() < 1e-9
+    ));
+    assert!((tank.pixel_area().unwrap() - 200.0 * PI).abs() < 1e-9);
+    assert!(tank.contains_point(&Point::new(0.0, 19.0)));
+    assert!(!tank.contains_point(&Point::new(19.0, 0.0)));
+
+    // A cutout turns the circle into arcs that keep its exact area
+    let mut planter = area(Some(Circle::new(Point::new(0.0, 0.0), 10.0)), None);
+    assert!(planter.add_cutout(vec![
+      Point::new(-1.0, -1.0),
+      Point::new(1.0, -1.0),
+      Point::new(1.0, 1.0),
+      Point::new(-1.0, 1.0),
+    ]));
+    assert!(matches!(
+      &planter,
+      Measurement::Area {
+        circle: None,
+        polygon: Some(_),
+        ..
+      }
+    ));
+    assert!((planter.pixel_area().unwrap() - (100.0 * PI - 4.0)).abs() < 1e-9);
+  }
+
+  #[test]
+  fn test_metadata() {
+    let mut wall = Measurement::Linear {
+      id: "wall".to_string(),
+      line: Some(Line::new(Point::new(0.0, 0.0), Point::new(100.0, 0.0))),
+      polyline: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(wall.metadata().is_none());
+    wall.metadata_mut().name = Some("North wall".to_string());
+    wall.metadata_mut().add_tag("exterior");
+    assert_eq!(
+      wall.metadata().and_then(|m| m.name.as_deref()),
+      Some("North wall")
+    );
+
+    // Converting keeps the metadata along with the ID
+    let converted = wall.convert(GeometryConversion::LineToPolyline).unwrap();
+    assert_eq!(converted.metadata(), wall.metadata());
+
+    let mut marker = Measurement::Count {
+      id: "outlet".to_string(),
+      point: Point::new(5.0, 5.0),
+      metadata: None,
+    };
+    marker.set_metadata(Some(MeasurementMetadata {
+      layer_id: Some("electrical".to_string()),
+      ..MeasurementMetadata::named("Outlet")
+    }));
+    assert_eq!(
+      marker.metadata().and_then(|m| m.layer_id.as_deref()),
+      Some("electrical")
+    );
+    marker.set_metadata(None);
+    assert!(marker.metadata().is_none());
+  }
 }
@@ -0,0 +1,90 @@
+use crate::CostRates;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// How the outline of a measurement is stroked
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrokeStyle {
+  #[default]
+  Solid,
+  Dashed,
+  Dotted,
+}
+
+/// Descriptive details carried with a measurement through undo, copy and saving
+///
+/// None of it affects the measurement's own quantities; it is what the front end
+/// shows and groups by.
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MeasurementMetadata {
+  /// Label shown in lists and on the drawing, e.g. `Level 2 slab`
+  pub name: Option<String>,
+  /// CSS color of the outline and fill, e.g. `#ff8800`
+  pub color: Option<String>,
+  /// Outline width in screen pixels
+  pub stroke_width: Option<f64>,
+  pub stroke_style: Option<StrokeStyle>,
+  /// Free-text notes
+  pub notes: Option<String>,
+  /// Arbitrary labels for filtering, e.g. `demo` or `phase 2`
+  #[serde(default)]
+  pub tags: Vec<String>,
+  /// ID of the layer the measurement is drawn on
+  pub layer_id: Option<String>,
+  /// ID of the condition the measurement is taken off for
+  pub condition_id: Option<String>,
+  /// Prices for this measurement, overriding its condition's
+  pub cost: Option<CostRates>,
+  /// ID of the assembly whose items this measurement drives
+  pub assembly_id: Option<String>,
+}
+
+impl MeasurementMetadata {
+  /// Metadata with just a name
+  pub fn named(name: impl Into<String>) -> Self {
+    Self {
+      name: Some(name.into()),
+      ..Self::default()
+    }
+  }
+
+  /// Check if a tag is set, ignoring case
+  pub fn has_tag(&self, tag: &str) -> bool {
+    self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+  }
+
+  /// Add a tag unless it is already set, returning whether it was added
+  pub fn add_tag(&mut self, tag: impl Into<String>) -> bool {
+    let tag = tag.into();
+    if self.has_tag(&tag) {
+      return false;
+    }
+    self.tags.push(tag);
+    true
+  }
+
+  /// Remove a tag, ignoring case, returning whether it was set
+  pub fn remove_tag(&mut self, tag: &str) -> bool {
+    let before = self.tags.len();
+    self.tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+    self.tags.len() != before
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_tags() {
+    let mut metadata = MeasurementMetadata::named("Slab");
+    assert!(metadata.add_tag("Phase 2"));
+    assert!(!metadata.add_tag("phase 2"));
+    assert!(metadata.has_tag("PHASE 2"));
+    assert!(metadata.remove_tag("phase 2"));
+    assert!(!metadata.remove_tag("phase 2"));
+    assert!(metadata.tags.is_empty());
+  }
+}
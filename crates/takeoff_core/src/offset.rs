@@ -0,0 +1,268 @@
+use crate::measurement::generate_id;
+use crate::{Dimension, Measurement, MeasurementMetadata, Pitch, Polygon, Scale, Unit};
+use geo::algorithm::buffer::{Buffer, BufferStyle, LineCap, LineJoin};
+use geo::{Coord, LineString, MultiPolygon};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// Sharpest corner, in radians, that a miter join keeps before beveling it
+const MITER_LIMIT: f64 = 0.1;
+
+/// Angle step, in radians, of round joins and caps
+const ROUND_STEP: f64 = 0.2;
+
+/// How the offset edges meet at a corner
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JoinStyle {
+  /// Extend the edges to a sharp corner
+  Miter,
+  /// Round the corner off
+  Round,
+  /// Cut the corner straight across
+  Bevel,
+}
+
+/// How the ends of an offset polyline are closed
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CapStyle {
+  /// Square, flush with the end point
+  Butt,
+  /// Square, extended past the end point by half the width
+  Square,
+  /// Half circle around the end point
+  Round,
+}
+
+/// Join and cap styles for offsets; defaults to mitered corners and butt ends
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OffsetOptions {
+  pub join: Option<JoinStyle>,
+  pub cap: Option<CapStyle>,
+}
+
+impl OffsetOptions {
+  fn style(&self, distance: f64) -> BufferStyle<f64> {
+    let join = match self.join.unwrap_or(JoinStyle::Miter) {
+      JoinStyle::Miter => LineJoin::Miter(MITER_LIMIT),
+      JoinStyle::Round => LineJoin::Round(ROUND_STEP),
+      JoinStyle::Bevel => LineJoin::Bevel,
+    };
+    let cap = match self.cap.unwrap_or(CapStyle::Butt) {
+      CapStyle::Butt => LineCap::Butt,
+      CapStyle::Square => LineCap::Square,
+      CapStyle::Round => LineCap::Round(ROUND_STEP),
+    };
+    BufferStyle::new(distance).line_join(join).line_cap(cap)
+  }
+}
+
+/// Turn a line or polyline into the area it covers at a given total width
+///
+/// The width is centered on the line, e.g. a wall thickness on its centerline.
+/// Arcs are flattened. The areas keep the scale, display unit, depth, pitch and
+/// metadata of the line. Returns `None` for anything but a linear measurement with a positive width.
+pub fn offset_linear(
+  measurement: &Measurement,
+  width: Dimension,
+  options: &OffsetOptions,
+) -> Option<Vec<Measurement>> {
+  let Measurement::Linear {
+    line,
+    polyline,
+    scale,
+    display_unit,
+    depth,
+    pitch,
+    metadata,
+    ..
+  } = measurement
+  else {
+    return None;
+  };
+  let points = match (line, polyline) {
+    (Some(line), _) => vec![line.start, line.end],
+    (None, Some(polyline)) => polyline.flatten(),
+    (None, None) => return None,
+  };
+  let half_width = to_pixels(scale, width) / 2.0;
+  if half_width.is_nan() || half_width <= 0.0 {
+    return None;
+  }
+  let path: LineString<f64> = points.into_iter().map(Coord::from).collect();
+  let shape = path.buffer_with_style(options.style(half_width));
+  Some(areas(shape, scale, *display_unit, *depth, *pitch, metadata))
+}
+
+/// Grow an area outward by a positive distance, or shrink it inward by a negative one
+///
+/// Caps don't apply to areas. Shrinking can split an area into pieces or remove
+/// it entirely. Returns `None` for anything but an area measurement.
+pub fn offset_area(
+  measurement: &Measurement,
+  distance: Dimension,
+  options: &OffsetOptions,
+) -> Option<Vec<Measurement>> {
+  let Measurement::Area {
+    scale,
+    display_unit,
+    depth,
+    pitch,
+    metadata,
+    ..
+  } = measurement
+  else {
+    return None;
+  };
+  let shape = measurement
+    .to_polygon()?
+    .buffer_with_style(options.style(to_pixels(scale, distance)));
+  Some(areas(shape, scale, *display_unit, *depth, *pitch, metadata))
+}
+
+/// Convert a real distance to pixels at a scale
+fn to_pixels(scale: &Scale, distance: Dimension) -> f64 {
+  scale.real_to_pixel(distance.convert(scale.unit))
+}
+
+fn areas(
+  shape: MultiPolygon<f64>,
+  scale: &Scale,
+  display_unit: Unit,
+  depth: Option<Dimension>,
+  pitch: Option<Pitch>,
+  metadata: &Option<MeasurementMetadata>,
+) -> Vec<Measurement> {
+  shape
+    .iter()
+    .map(|piece| Measurement::Area {
+      id: generate_id("area"),
+      rectangle: None,
+      polygon: Some(Polygon::from_geo(piece)),
+      circle: None,
+      ellipse: None,
+      scale: *scale,
+      display_unit,
+      depth,
+      pitch,
+      metadata: metadata.clone(),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Point, Polyline, Rectangle};
+
+  // 10 pixels per foot
+  fn scale() -> Scale {
+    Scale::new(10.0, 1.0, Unit::Feet)
+  }
+
+  fn wall(points: Vec<Point>) -> Measurement {
+    Measurement::Linear {
+      id: "wall".to_string(),
+      line: None,
+      polyline: Some(Polyline::new(points)),
+      scale: scale(),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    }
+  }
+
+  fn assert_close(a: f64, b: f64, tolerance: f64) {
+    assert!((a - b).abs() < tolerance, "{a} != {b}");
+  }
+
+  #[test]
+  fn test_wall_footprint() {
+    // A 20 ft by 10 ft L-shaped wall run, 6 inches thick
+    let wall = wall(vec![
+      Point::new(0.0, 0.0),
+      Point::new(200.0, 0.0),
+      Point::new(200.0, 100.0),
+    ]);
+    let thickness = Dimension::new(6.0, Unit::Inches);
+
+    let mitered = offset_linear(&wall, thickness, &OffsetOptions::default()).unwrap();
+    assert_eq!(mitered.len(), 1);
+    // 30 ft of centerline at 0.5 ft wide
+    assert_close(mitered[0].real_area().unwrap(), 15.0, 1e-6);
+
+    let square = OffsetOptions {
+      join: Some(JoinStyle::Miter),
+      cap: Some(CapStyle::Square),
+    };
+    let extended = offset_linear(&wall, thickness, &square).unwrap();
+    assert_close(extended[0].real_area().unwrap(), 15.25, 1e-6);
+
+    let bevel = OffsetOptions {
+      join: Some(JoinStyle::Bevel),
+      ..OffsetOptions::default()
+    };
+    let beveled = offset_linear(&wall, thickness, &bevel).unwrap();
+    // The outside corner loses a 0.25 ft right triangle
+    assert_close(beveled[0].real_area().unwrap(), 15.0 - 0.03125, 1e-6);
+
+    let round = OffsetOptions {
+      join: Some(JoinStyle::Round),
+      ..OffsetOptions::default()
+    };
+    let rounded = offset_linear(&wall, thickness, &round).unwrap()[0]
+      .real_area()
+      .unwrap();
+    assert!(rounded < 15.0 && rounded > 15.0 - 0.03125);
+
+    assert!(offset_linear(&wall, Dimension::new(0.0, Unit::Feet), &round).is_none());
+  }
+
+  #[test]
+  fn test_offset_area() {
+    let slab = Measurement::Area {
+      id: "slab".to_string(),
+      rectangle: Some(Rectangle::new(
+        Point::new(0.0, 0.0),
+        Point::new(200.0, 100.0),
+      )),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: scale(),
+      display_unit: Unit::Feet,
+      depth: Some(Dimension::new(4.0, Unit::Inches)),
+      pitch: None,
+      metadata: None,
+    };
+    let two_feet = Dimension::new(2.0, Unit::Feet);
+
+    let grown = offset_area(&slab, two_feet, &OffsetOptions::default()).unwrap();
+    assert_eq!(grown.len(), 1);
+    assert_close(grown[0].real_area().unwrap(), 24.0 * 14.0, 1e-6);
+    assert_eq!(grown[0].depth(), slab.depth());
+
+    let shrunk = offset_area(
+      &slab,
+      Dimension::new(-2.0, Unit::Feet),
+      &OffsetOptions::default(),
+    )
+    .unwrap();
+    assert_close(shrunk[0].real_area().unwrap(), 16.0 * 6.0, 1e-6);
+
+    // Shrinking past the middle leaves nothing
+    let gone = offset_area(
+      &slab,
+      Dimension::new(-6.0, Unit::Feet),
+      &OffsetOptions::default(),
+    )
+    .unwrap();
+    assert!(gone.is_empty());
+
+    assert!(offset_area(&wall(vec![]), two_feet, &OffsetOptions::default()).is_none());
+  }
+}
@@ -0,0 +1,106 @@
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// Slope of a roof or ramp as rise over run, e.g. 6/12
+///
+/// Measurements taken from a plan view are multiplied by `factor` to get the
+/// surface along the slope.
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pitch {
+  pub rise: f64,
+  pub run: f64,
+}
+
+impl Pitch {
+  pub fn new(rise: f64, run: f64) -> Self {
+    Self { rise, run }
+  }
+
+  /// Create a pitch from an angle above horizontal, in degrees
+  pub fn from_degrees(degrees: f64) -> Self {
+    Self::new(degrees.to_radians().tan() * 12.0, 12.0)
+  }
+
+  /// Parse `6/12`, `6:12`, `6 in 12`, `30°` or `30deg`
+  pub fn parse(text: &str) -> Option<Pitch> {
+    let text = text.trim().to_lowercase();
+    let degrees = text
+      .strip_suffix('°')
+      .or_else(|| text.strip_suffix("degrees"))
+      .or_else(|| text.strip_suffix("deg"));
+    if let Some(degrees) = degrees {
+      let degrees: f64 = degrees.trim().parse().ok()?;
+      return (0.0..90.0)
+        .contains(&degrees)
+        .then(|| Self::from_degrees(degrees));
+    }
+    let (rise, run) = text
+      .split_once('/')
+      .or_else(|| text.split_once(':'))
+      .or_else(|| text.split_once(" in "))?;
+    let pitch = Self::new(rise.trim().parse().ok()?, run.trim().parse().ok()?);
+    pitch.is_valid().then_some(pitch)
+  }
+
+  /// A pitch needs a positive run and a non-negative rise
+  pub fn is_valid(&self) -> bool {
+    self.run > 0.0 && self.rise >= 0.0 && self.rise.is_finite()
+  }
+
+  /// Angle above horizontal, in degrees
+  pub fn degrees(&self) -> f64 {
+    self.rise.atan2(self.run).to_degrees()
+  }
+
+  /// Ratio of the sloped surface to its plan projection
+  ///
+  /// Invalid pitches have no effect (factor 1).
+  pub fn factor(&self) -> f64 {
+    if !self.is_valid() {
+      return 1.0;
+    }
+    (1.0 + (self.rise / self.run).powi(2)).sqrt()
+  }
+
+  /// Format as rise over a run of 12, e.g. `6/12`
+  pub fn to_notation(&self) -> String {
+    let rise = self.rise / self.run * 12.0;
+    let text = format!("{rise:.2}");
+    format!("{}/12", text.trim_end_matches('0').trim_end_matches('.'))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{a} != {b}");
+  }
+
+  #[test]
+  fn test_factor() {
+    assert_close(Pitch::new(6.0, 12.0).factor(), 1.25_f64.sqrt());
+    assert_close(Pitch::new(12.0, 12.0).factor(), 2.0_f64.sqrt());
+    assert_close(Pitch::new(0.0, 12.0).factor(), 1.0);
+    assert_close(Pitch::from_degrees(60.0).factor(), 2.0);
+    assert_close(Pitch::new(1.0, 0.0).factor(), 1.0);
+  }
+
+  #[test]
+  fn test_parse_and_format() {
+    assert_eq!(Pitch::parse("6/12"), Some(Pitch::new(6.0, 12.0)));
+    assert_eq!(Pitch::parse("4:12"), Some(Pitch::new(4.0, 12.0)));
+    assert_eq!(Pitch::parse("3 in 12"), Some(Pitch::new(3.0, 12.0)));
+    assert_close(Pitch::parse("45°").unwrap().degrees(), 45.0);
+    assert_close(Pitch::parse("30 deg").unwrap().degrees(), 30.0);
+    assert!(Pitch::parse("90°").is_none());
+    assert!(Pitch::parse("6/0").is_none());
+    assert!(Pitch::parse("steep").is_none());
+
+    assert_eq!(Pitch::new(6.0, 12.0).to_notation(), "6/12");
+    assert_eq!(Pitch::new(1.0, 4.0).to_notation(), "3/12");
+    assert_eq!(Pitch::new(7.5, 12.0).to_notation(), "7.5/12");
+  }
+}
@@ -1,5 +1,7 @@
-use crate::Point;
-use geo::{Area, Coord, LineString, Polygon as GeoPolygon};
+use crate::arc::closest_point_on_line_segment;
+use crate::validation::{is_valid_polygon, repair_polygon};
+use crate::{ArcSegment, Point};
+use geo::{Area, Contains, Coord, LineString, Polygon as GeoPolygon, Relate};
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 
@@ -8,28 +10,314 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Polygon {
   pub points: Vec<Point>,
+  /// Interior rings (cutouts) subtracted from the area
+  pub holes: Option<Vec<Vec<Point>>>,
+  /// Bulge of each outer segment (index `i` joins point `i` to the next, wrapping to the start)
+  pub bulges: Option<Vec<f64>>,
 }
 
 impl Polygon {
   pub fn new(points: Vec<Point>) -> Self {
-    Self { points }
+    Self {
+      points,
+      holes: None,
+      bulges: None,
+    }
+  }
+
+  pub fn with_holes(points: Vec<Point>, holes: Vec<Vec<Point>>) -> Self {
+    let mut polygon = Self::new(points);
+    for hole in holes {
+      polygon.add_hole(hole);
+    }
+    polygon
+  }
+
+  /// Create a polygon whose outer segments may be arcs
+  pub fn with_bulges(points: Vec<Point>, bulges: Vec<f64>) -> Self {
+    let mut polygon = Self::new(points);
+    if bulges.iter().any(|b| *b != 0.0) {
+      polygon.bulges = Some(bulges);
+    }
+    polygon
+  }
+
+  /// Bulge of the outer segment starting at point `index`
+  pub fn bulge(&self, index: usize) -> f64 {
+    self
+      .bulges
+      .as_ref()
+      .and_then(|bulges| bulges.get(index).copied())
+      .unwrap_or(0.0)
+  }
+
+  /// Set the bulge of the outer segment starting at point `index`
+  pub fn set_bulge(&mut self, index: usize, bulge: f64) -> bool {
+    if index >= self.points.len() {
+      return false;
+    }
+    let bulges = self.bulges.get_or_insert_with(Vec::new);
+    if bulges.len() <= index {
+      bulges.resize(index + 1, 0.0);
+    }
+    bulges[index] = bulge;
+    true
+  }
+
+  /// Check if any outer segment is an arc
+  pub fn has_arcs(&self) -> bool {
+    (0..self.points.len()).any(|i| self.bulge(i) != 0.0)
+  }
+
+  /// Get every segment of the outer ring, including the closing one
+  pub fn segments(&self) -> Vec<ArcSegment> {
+    let n = self.points.len();
+    (0..n)
+      .map(|i| ArcSegment::new(self.points[i], self.points[(i + 1) % n], self.bulge(i)))
+      .collect()
+  }
+
+  /// Approximate the outer ring with straight segments only
+  pub fn flattened_points(&self) -> Vec<Point> {
+    if !self.has_arcs() {
+      return self.points.clone();
+    }
+    self
+      .segments()
+      .iter()
+      .flat_map(|segment| segment.flatten())
+      .collect()
+  }
+
+  /// Get the interior rings of the polygon
+  pub fn holes(&self) -> &[Vec<Point>] {
+    self.holes.as_deref().unwrap_or(&[])
+  }
+
+  /// Add an interior ring
+  ///
+  /// Rings with fewer than 3 points, rings that are not inside the outer ring and
+  /// rings that overlap an existing hole are ignored.
+  pub fn add_hole(&mut self, points: Vec<Point>) -> bool {
+    if points.len() < 3 {
+      return false;
+    }
+    let hole = Self::ring_polygon(&points);
+    if !Self::ring_polygon(&self.flattened_points()).contains(&hole)
+      || self
+        .holes()
+        .iter()
+        .any(|other| Self::rings_overlap(&hole, &Self::ring_polygon(other)))
+    {
+      return false;
+    }
+    self.holes.get_or_insert_with(Vec::new).push(points);
+    true
+  }
+
+  /// Remove an interior ring by index
+  pub fn remove_hole(&mut self, index: usize) -> Option<Vec<Point>> {
+    let holes = self.holes.as_mut()?;
+    if index >= holes.len() {
+      return None;
+    }
+    let hole = holes.remove(index);
+    if holes.is_empty() {
+      self.holes = None;
+    }
+    Some(hole)
+  }
+
+  /// Convert to a `geo` polygon, flattening any arcs
+  pub fn to_polygon(&self) -> GeoPolygon<f64> {
+    GeoPolygon::new(
+      Self::ring(&self.flattened_points()),
+      self.holes().iter().map(|hole| Self::ring(hole)).collect(),
+    )
+  }
+
+  /// Convert from a `geo` polygon, dropping the repeated closing point of each ring
+  pub fn from_geo(polygon: &GeoPolygon<f64>) -> Self {
+    let mut result = Self::new(Self::ring_points(polygon.exterior()));
+    for hole in polygon.interiors() {
+      result.add_hole(Self::ring_points(hole));
+    }
+    result
+  }
+
+  fn ring_points(ring: &LineString<f64>) -> Vec<Point> {
+    let mut points: Vec<Point> = ring.coords().map(|c| (*c).into()).collect();
+    if points.len() > 1 && points.first() == points.last() {
+      points.pop();
+    }
+    points
+  }
+
+  fn ring(points: &[Point]) -> LineString<f64> {
+    let points: Vec<Coord<f64>> = points.iter().map(|p| (*p).into()).collect();
+    LineString::from(points)
+  }
+
+  fn ring_polygon(points: &[Point]) -> GeoPolygon<f64> {
+    GeoPolygon::new(Self::ring(points), vec![])
   }
 
-  fn to_polygon(&self) -> GeoPolygon<f64> {
-    let points: Vec<Coord<f64>> = self.points.iter().map(|p| (*p).into()).collect();
-    GeoPolygon::new(LineString::from(points), vec![])
+  /// Check if two rings share any area; rings that only touch don't
+  fn rings_overlap(a: &GeoPolygon<f64>, b: &GeoPolygon<f64>) -> bool {
+    let relation = a.relate(b);
+    relation.is_intersects() && !relation.is_touches()
   }
 
+  /// Area inside the outer ring, less the holes
+  ///
+  /// A polygon that `validate_polygon` finds problems with is measured by the
+  /// parts `repair_polygon` splits it into, so both lobes of a bow-tie count.
   pub fn area(&self) -> f64 {
-    self.to_polygon().unsigned_area()
+    if !is_valid_polygon(self) {
+      return repair_polygon(self)
+        .iter()
+        .map(|part| part.ring_area())
+        .sum();
+    }
+    self.ring_area()
   }
 
+  /// Outer ring area less the hole areas, correct only for a valid polygon
+  fn ring_area(&self) -> f64 {
+    let exterior = if self.has_arcs() {
+      // Shoelace area of the vertices plus the exact area of each arc segment
+      let signed: f64 = self
+        .segments()
+        .iter()
+        .map(|s| (s.start.x * s.end.y - s.end.x * s.start.y) / 2.0 + s.segment_area())
+        .sum();
+      signed.abs()
+    } else {
+      Self::ring_polygon(&self.points).unsigned_area()
+    };
+    let holes: f64 = self
+      .holes()
+      .iter()
+      .map(|hole| Self::ring_polygon(hole).unsigned_area())
+      .sum();
+    (exterior - holes).max(0.0)
+  }
+
+  /// Perimeter of the outer ring plus the perimeter of every hole
   pub fn perimeter(&self) -> f64 {
+    self.segments().iter().map(|s| s.length()).sum::<f64>()
+      + self
+        .holes()
+        .iter()
+        .map(|hole| Self::ring_perimeter(hole))
+        .sum::<f64>()
+  }
+
+  fn ring_perimeter(points: &[Point]) -> f64 {
     let mut perimeter = 0.0;
-    for i in 0..self.points.len() {
-      let j = (i + 1) % self.points.len();
-      perimeter += self.points[i].distance_to(&self.points[j]);
+    for i in 0..points.len() {
+      let j = (i + 1) % points.len();
+      perimeter += points[i].distance_to(&points[j]);
     }
     perimeter
   }
+
+  /// All vertices of the polygon: the outer ring followed by each hole in order
+  pub fn vertices(&self) -> Vec<Point> {
+    let mut vertices = self.points.clone();
+    for hole in self.holes() {
+      vertices.extend_from_slice(hole);
+    }
+    vertices
+  }
+
+  /// Split an edge with a new point, keeping arcs on their circle
+  ///
+  /// Edges and the returned vertex index follow `segments` then each hole in
+  /// order, the same as `vertices`. The point is moved onto the edge.
+  pub fn insert_point(&mut self, edge_index: usize, point: Point) -> Option<usize> {
+    let n = self.points.len();
+    if edge_index < n {
+      let (first, second) = self.segments()[edge_index].split_at(&point);
+      self.points.insert(edge_index + 1, first.end);
+      if let Some(bulges) = self.bulges.as_mut() {
+        if bulges.len() <= edge_index {
+          bulges.resize(edge_index + 1, 0.0);
+        }
+        bulges[edge_index] = first.bulge;
+        bulges.insert(edge_index + 1, second.bulge);
+      }
+      return Some(edge_index + 1);
+    }
+    let (mut edge, mut offset) = (edge_index - n, n);
+    for hole in self.holes.as_mut()?.iter_mut() {
+      if edge < hole.len() {
+        let end = hole[(edge + 1) % hole.len()];
+        let split = closest_point_on_line_segment(&point, &hole[edge], &end);
+        hole.insert(edge + 1, split);
+        return Some(offset + edge + 1);
+      }
+      edge -= hole.len();
+      offset += hole.len();
+    }
+    None
+  }
+
+  /// Remove a vertex using the same indexing as `vertices`, keeping at least 3 per ring
+  ///
+  /// The edges on either side of a removed outer vertex are joined with a straight edge.
+  pub fn remove_point(&mut self, index: usize) -> bool {
+    let n = self.points.len();
+    if index < n {
+      if n <= 3 {
+        return false;
+      }
+      self.points.remove(index);
+      if let Some(bulges) = self.bulges.as_mut() {
+        if index < bulges.len() {
+          bulges.remove(index);
+        }
+        let previous = (index + n - 1) % n;
+        let previous = if previous > index {
+          previous - 1
+        } else {
+          previous
+        };
+        if let Some(bulge) = bulges.get_mut(previous) {
+          *bulge = 0.0;
+        }
+      }
+      return true;
+    }
+    let mut index = index - n;
+    let Some(holes) = self.holes.as_mut() else {
+      return false;
+    };
+    for hole in holes.iter_mut() {
+      if index < hole.len() {
+        if hole.len() <= 3 {
+          return false;
+        }
+        hole.remove(index);
+        return true;
+      }
+      index -= hole.len();
+    }
+    false
+  }
+
+  /// Get a mutable vertex using the same indexing as `vertices`
+  pub fn vertex_mut(&mut self, index: usize) -> Option<&mut Point> {
+    if index < self.points.len() {
+      return self.points.get_mut(index);
+    }
+    let mut index = index - self.points.len();
+    for hole in self.holes.as_mut()?.iter_mut() {
+      if index < hole.len() {
+        return hole.get_mut(index);
+      }
+      index -= hole.len();
+    }
+    None
+  }
 }
@@ -1,4 +1,4 @@
-use crate::Point;
+use crate::{ArcSegment, Point};
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 
@@ -25,24 +25,124 @@ impl Line {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Polyline {
   pub points: Vec<Point>,
+  /// Bulge of each segment (index `i` joins point `i` to `i + 1`); missing entries are straight
+  pub bulges: Option<Vec<f64>>,
 }
 
 impl Polyline {
   pub fn new(points: Vec<Point>) -> Self {
-    Self { points }
+    Self {
+      points,
+      bulges: None,
+    }
   }
-  pub fn length(&self) -> f64 {
-    let mut length = 0.0;
-    for i in 0..self.points.len() - 1 {
-      length += self.points[i].distance_to(&self.points[i + 1]);
+
+  /// Create a polyline whose segments may be arcs
+  pub fn with_bulges(points: Vec<Point>, bulges: Vec<f64>) -> Self {
+    let bulges = if bulges.iter().all(|b| *b == 0.0) {
+      None
+    } else {
+      Some(bulges)
+    };
+    Self { points, bulges }
+  }
+
+  /// Bulge of the segment starting at point `index`
+  pub fn bulge(&self, index: usize) -> f64 {
+    self
+      .bulges
+      .as_ref()
+      .and_then(|bulges| bulges.get(index).copied())
+      .unwrap_or(0.0)
+  }
+
+  /// Set the bulge of the segment starting at point `index`
+  pub fn set_bulge(&mut self, index: usize, bulge: f64) -> bool {
+    if index + 1 >= self.points.len() {
+      return false;
+    }
+    let bulges = self.bulges.get_or_insert_with(Vec::new);
+    if bulges.len() <= index {
+      bulges.resize(index + 1, 0.0);
+    }
+    bulges[index] = bulge;
+    true
+  }
+
+  /// Check if any segment is an arc
+  pub fn has_arcs(&self) -> bool {
+    (0..self.points.len().saturating_sub(1)).any(|i| self.bulge(i) != 0.0)
+  }
+
+  /// Get every segment of the polyline
+  pub fn segments(&self) -> Vec<ArcSegment> {
+    self
+      .points
+      .windows(2)
+      .enumerate()
+      .map(|(i, pair)| ArcSegment::new(pair[0], pair[1], self.bulge(i)))
+      .collect()
+  }
+
+  /// Split segment `segment_index` with a new point, keeping arcs on their circle
+  ///
+  /// The point is moved onto the segment. Returns the index of the new point.
+  pub fn insert_point(&mut self, segment_index: usize, point: Point) -> Option<usize> {
+    let segment = *self.segments().get(segment_index)?;
+    let (first, second) = segment.split_at(&point);
+    self.points.insert(segment_index + 1, first.end);
+    if let Some(bulges) = self.bulges.as_mut() {
+      if bulges.len() <= segment_index {
+        bulges.resize(segment_index + 1, 0.0);
+      }
+      bulges[segment_index] = first.bulge;
+      bulges.insert(segment_index + 1, second.bulge);
+    }
+    Some(segment_index + 1)
+  }
+
+  /// Remove a point, keeping at least 2
+  ///
+  /// Removing an interior point joins its neighbours with a straight segment.
+  pub fn remove_point(&mut self, index: usize) -> bool {
+    if index >= self.points.len() || self.points.len() <= 2 {
+      return false;
     }
-    length
+    self.points.remove(index);
+    if let Some(bulges) = self.bulges.as_mut() {
+      // The segment that started at the removed point goes; the one ending there becomes straight
+      let removed = index.min(self.points.len() - 1);
+      if removed < bulges.len() {
+        bulges.remove(removed);
+      }
+      if index > 0 && index < self.points.len() {
+        if let Some(bulge) = bulges.get_mut(index - 1) {
+          *bulge = 0.0;
+        }
+      }
+    }
+    true
+  }
+
+  pub fn length(&self) -> f64 {
+    self.segments().iter().map(|segment| segment.length()).sum()
+  }
+
+  /// Approximate the polyline with straight segments only
+  pub fn flatten(&self) -> Vec<Point> {
+    let mut points: Vec<Point> = self
+      .segments()
+      .iter()
+      .flat_map(|segment| segment.flatten())
+      .collect();
+    points.extend(self.points.last().copied());
+    points
   }
 }
 
 impl From<Vec<Point>> for Polyline {
   fn from(points: Vec<Point>) -> Self {
-    Self { points }
+    Self::new(points)
   }
 }
 
@@ -0,0 +1,211 @@
+use crate::{Scale, TakeoffState};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// Identifies one page of a file in a plan set
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PageKey {
+  pub file_id: String,
+  pub page_number: u32,
+}
+
+impl PageKey {
+  pub fn new(file_id: impl Into<String>, page_number: u32) -> Self {
+    Self {
+      file_id: file_id.into(),
+      page_number,
+    }
+  }
+}
+
+/// A takeoff across many pages, each with its own scale, transform and measurements
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TakeoffProject {
+  /// One state per page, identified by its `file_id` and `page_number`
+  pub pages: Vec<TakeoffState>,
+  /// The page currently being worked on
+  pub active_page: Option<PageKey>,
+}
+
+impl TakeoffProject {
+  pub fn new() -> Self {
+    Self {
+      pages: Vec::new(),
+      active_page: None,
+    }
+  }
+
+  /// Get a page by key
+  pub fn page(&self, key: &PageKey) -> Option<&TakeoffState> {
+    self
+      .pages
+      .iter()
+      .find(|page| page.page_key().as_ref() == Some(key))
+  }
+
+  /// Get a mutable page by key
+  pub fn page_mut(&mut self, key: &PageKey) -> Option<&mut TakeoffState> {
+    self
+      .pages
+      .iter_mut()
+      .find(|page| page.page_key().as_ref() == Some(key))
+  }
+
+  /// Get a page, creating an empty one if it does not exist yet
+  pub fn add_page(&mut self, key: PageKey) -> &mut TakeoffState {
+    match self
+      .pages
+      .iter()
+      .position(|page| page.page_key().as_ref() == Some(&key))
+    {
+      Some(index) => &mut self.pages[index],
+      None => {
+        self.pages.push(TakeoffState::for_page(key));
+        self.pages.last_mut().unwrap()
+      }
+    }
+  }
+
+  /// Store a page, replacing any page with the same key
+  ///
+  /// States without a page key are ignored.
+  pub fn insert_page(&mut self, state: TakeoffState) -> bool {
+    let Some(key) = state.page_key() else {
+      return false;
+    };
+    *self.add_page(key) = state;
+    true
+  }
+
+  /// Remove a page, clearing the active page if it was removed
+  pub fn remove_page(&mut self, key: &PageKey) -> Option<TakeoffState> {
+    let index = self
+      .pages
+      .iter()
+      .position(|page| page.page_key().as_ref() == Some(key))?;
+    if self.active_page.as_ref() == Some(key) {
+      self.active_page = None;
+    }
+    Some(self.pages.remove(index))
+  }
+
+  /// Keys of every page in the project
+  pub fn page_keys(&self) -> Vec<PageKey> {
+    self
+      .pages
+      .iter()
+      .filter_map(|page| page.page_key())
+      .collect()
+  }
+
+  /// Switch the active page, creating it if needed
+  pub fn set_active_page(&mut self, key: PageKey) -> &mut TakeoffState {
+    self.active_page = Some(key.clone());
+    self.add_page(key)
+  }
+
+  pub fn active_page(&self) -> Option<&TakeoffState> {
+    self.page(self.active_page.as_ref()?)
+  }
+
+  pub fn active_page_mut(&mut self) -> Option<&mut TakeoffState> {
+    let key = self.active_page.clone()?;
+    self.page_mut(&key)
+  }
+
+  /// Set the scale of a page, creating it if needed
+  pub fn set_page_scale(&mut self, key: PageKey, scale: Option<Scale>) {
+    self.add_page(key).scale = scale;
+  }
+
+  /// Copy the scale of one page to other pages, creating them if needed
+  ///
+  /// Returns the number of pages updated, or 0 if the source page has no scale.
+  pub fn copy_scale_to_pages(&mut self, from: &PageKey, to: &[PageKey]) -> usize {
+    let Some(scale) = self.page(from).and_then(|page| page.scale) else {
+      return 0;
+    };
+    let mut updated = 0;
+    for key in to.iter().filter(|key| *key != from) {
+      self.set_page_scale(key.clone(), Some(scale));
+      updated += 1;
+    }
+    updated
+  }
+
+  /// Keys of pages that have not been scaled yet
+  pub fn pages_without_scale(&self) -> Vec<PageKey> {
+    self
+      .pages
+      .iter()
+      .filter(|page| page.scale.is_none())
+      .filter_map(|page| page.page_key())
+      .collect()
+  }
+}
+
+impl Default for TakeoffProject {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Measurement, Point, Unit};
+
+  #[test]
+  fn test_pages_keep_separate_state() {
+    let mut project = TakeoffProject::new();
+    let first = PageKey::new("plans.pdf", 1);
+    let second = PageKey::new("plans.pdf", 2);
+
+    project
+      .set_active_page(first.clone())
+      .add_measurement(Measurement::Count {
+        id: "c1".to_string(),
+        point: Point::new(0.0, 0.0),
+        metadata: None,
+      });
+    project.set_active_page(second.clone());
+
+    assert_eq!(project.active_page.as_ref(), Some(&second));
+    assert!(project.active_page().unwrap().measurements.is_empty());
+    assert_eq!(project.page(&first).unwrap().count, 1);
+    assert_eq!(project.page_keys(), vec![first, second]);
+  }
+
+  #[test]
+  fn test_copy_scale_and_unscaled_pages() {
+    let mut project = TakeoffProject::new();
+    let keys: Vec<PageKey> = (1..=4).map(|n| PageKey::new("plans.pdf", n)).collect();
+    for key in &keys {
+      project.add_page(key.clone());
+    }
+    let scale = Scale::new(48.0, 1.0, Unit::Feet);
+    project.set_page_scale(keys[0].clone(), Some(scale));
+
+    assert_eq!(project.pages_without_scale(), keys[1..].to_vec());
+
+    let updated = project.copy_scale_to_pages(&keys[0], &keys[1..3]);
+    assert_eq!(updated, 2);
+    assert_eq!(project.page(&keys[2]).unwrap().scale, Some(scale));
+    assert_eq!(project.pages_without_scale(), vec![keys[3].clone()]);
+
+    // Copying from an unscaled page does nothing
+    assert_eq!(project.copy_scale_to_pages(&keys[3], &keys[..1]), 0);
+  }
+
+  #[test]
+  fn test_remove_active_page() {
+    let mut project = TakeoffProject::new();
+    let key = PageKey::new("site.pdf", 1);
+    project.set_active_page(key.clone());
+    assert!(project.remove_page(&key).is_some());
+    assert!(project.active_page.is_none());
+    assert!(project.pages.is_empty());
+  }
+}
@@ -1,34 +1,95 @@
+use crate::coords::exact_sin_cos;
 use crate::Point;
-use geo::{Area, Coord, Polygon as GeoPolygon, Rect};
+use geo::{Coord, LineString, Polygon as GeoPolygon};
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 
-/// Represents a rectangle defined by two points
+/// Represents a rectangle defined by two opposite corners
+///
+/// Without a rotation the edges are horizontal and vertical. A rotated
+/// rectangle has its first edge leaving `start` at `rotation` degrees
+/// counter-clockwise from the x axis; `end` is still the opposite corner.
 #[napi(object)]
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Rectangle {
   pub start: Point,
   pub end: Point,
+  /// Angle of the first edge in degrees, counter-clockwise; none means 0
+  pub rotation: Option<f64>,
 }
 
 impl Rectangle {
   pub fn new(start: Point, end: Point) -> Self {
-    Self { start, end }
+    Self {
+      start,
+      end,
+      rotation: None,
+    }
   }
 
-  /// Calculate the width of the rectangle
+  /// Create a rectangle whose first edge leaves `start` at `degrees`
+  ///
+  /// A multiple of 90 degrees leaves the edges horizontal and vertical, so it
+  /// is stored as no rotation.
+  pub fn rotated(start: Point, end: Point, degrees: f64) -> Self {
+    Self {
+      start,
+      end,
+      rotation: (!is_axis_aligned(degrees)).then_some(degrees),
+    }
+  }
+
+  /// Create a rectangle from a baseline and a point on the opposite side
+  ///
+  /// The first edge runs from `start` to `baseline_end`; the rectangle extends
+  /// from it as far as `side` lies from the baseline, on whichever side `side` is.
+  pub fn from_baseline(start: Point, baseline_end: Point, side: Point) -> Self {
+    let degrees = (baseline_end.y - start.y)
+      .atan2(baseline_end.x - start.x)
+      .to_degrees();
+    let (sin, cos) = exact_sin_cos(degrees);
+    let (vx, vy) = (-sin, cos);
+    let height = (side.x - start.x) * vx + (side.y - start.y) * vy;
+    let end = Point::new(baseline_end.x + vx * height, baseline_end.y + vy * height);
+    Self::rotated(start, end, degrees)
+  }
+
+  /// Rotation of the first edge in degrees
+  pub fn rotation(&self) -> f64 {
+    self.rotation.unwrap_or(0.0)
+  }
+
+  /// Check if the edges are not horizontal and vertical
+  pub fn is_rotated(&self) -> bool {
+    !is_axis_aligned(self.rotation())
+  }
+
+  /// Unit vectors along the first edge and the edge after it
+  fn axes(&self) -> ((f64, f64), (f64, f64)) {
+    let (sin, cos) = exact_sin_cos(self.rotation());
+    ((cos, sin), (-sin, cos))
+  }
+
+  /// Signed extents of the rectangle along its two axes
+  fn extents(&self) -> (f64, f64) {
+    let ((ux, uy), (vx, vy)) = self.axes();
+    let (dx, dy) = (self.end.x - self.start.x, self.end.y - self.start.y);
+    (dx * ux + dy * uy, dx * vx + dy * vy)
+  }
+
+  /// Calculate the width of the rectangle, along its first edge
   pub fn width(&self) -> f64 {
-    (self.end.x - self.start.x).abs()
+    self.extents().0.abs()
   }
 
-  /// Calculate the height of the rectangle
+  /// Calculate the height of the rectangle, across its first edge
   pub fn height(&self) -> f64 {
-    (self.end.y - self.start.y).abs()
+    self.extents().1.abs()
   }
 
   /// Calculate the area of the rectangle
   pub fn area(&self) -> f64 {
-    self.to_polygon().unsigned_area()
+    self.width() * self.height()
   }
 
   /// Calculate the perimeter of the rectangle
@@ -36,10 +97,132 @@ impl Rectangle {
     2.0 * (self.width() + self.height())
   }
 
+  /// Get the four corners, starting at `start` and going around the rectangle
+  pub fn corners(&self) -> Vec<Point> {
+    if !self.is_rotated() {
+      return vec![
+        self.start,
+        Point::new(self.end.x, self.start.y),
+        self.end,
+        Point::new(self.start.x, self.end.y),
+      ];
+    }
+    let ((ux, uy), (vx, vy)) = self.axes();
+    let (width, height) = self.extents();
+    vec![
+      self.start,
+      Point::new(self.start.x + ux * width, self.start.y + uy * width),
+      self.end,
+      Point::new(self.start.x + vx * height, self.start.y + vy * height),
+    ]
+  }
+
+  /// Move one corner, numbered as in `corners`, keeping the opposite one and the rotation
+  pub fn set_corner(&mut self, index: usize, point: Point) -> bool {
+    let corners = self.corners();
+    let ((ux, uy), (vx, vy)) = self.axes();
+    let along = |from: Point, (ax, ay): (f64, f64)| {
+      let t = (point.x - from.x) * ax + (point.y - from.y) * ay;
+      Point::new(from.x + ax * t, from.y + ay * t)
+    };
+    match index {
+      0 => self.start = point,
+      1 => {
+        self.start = along(corners[3], (vx, vy));
+        self.end = along(corners[3], (ux, uy));
+      }
+      2 => self.end = point,
+      3 => {
+        self.start = along(corners[1], (ux, uy));
+        self.end = along(corners[1], (vx, vy));
+      }
+      _ => return false,
+    }
+    true
+  }
+
   pub fn to_polygon(&self) -> GeoPolygon<f64> {
-    let start: Coord<f64> = self.start.into();
-    let end: Coord<f64> = self.end.into();
-    let rect = Rect::new(start, end);
-    rect.to_polygon()
+    let ring: Vec<Coord<f64>> = self.corners().into_iter().map(Coord::from).collect();
+    GeoPolygon::new(LineString::from(ring), vec![])
+  }
+}
+
+/// Check if an angle in degrees is a multiple of 90, allowing for rounding
+fn is_axis_aligned(degrees: f64) -> bool {
+  let remainder = degrees.rem_euclid(90.0);
+  remainder.min(90.0 - remainder) < 1e-9
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_near(a: Point, b: Point) {
+    assert!(a.distance_to(&b) < 1e-9, "{a:?} != {b:?}");
+  }
+
+  #[test]
+  fn test_rotated_rectangle() {
+    // A 30 x 40 rectangle turned 30 degrees
+    let (sin, cos) = 30.0_f64.to_radians().sin_cos();
+    let start = Point::new(10.0, 10.0);
+    let corner = Point::new(10.0 + 30.0 * cos, 10.0 + 30.0 * sin);
+    let end = Point::new(corner.x - 40.0 * sin, corner.y + 40.0 * cos);
+    let rect = Rectangle::rotated(start, end, 30.0);
+    assert!(rect.is_rotated());
+    assert!((rect.width() - 30.0).abs() < 1e-9);
+    assert!((rect.height() - 40.0).abs() < 1e-9);
+    assert!((rect.area() - 1200.0).abs() < 1e-9);
+    assert!((rect.perimeter() - 140.0).abs() < 1e-9);
+    let corners = rect.corners();
+    assert_near(corners[1], corner);
+    assert_near(corners[3], Point::new(10.0 - 40.0 * sin, 10.0 + 40.0 * cos));
+
+    let three_click = Rectangle::from_baseline(start, corner, Point::new(end.x, end.y));
+    assert!((three_click.rotation() - 30.0).abs() < 1e-9);
+    assert_near(three_click.end, end);
+    // The third click only sets the distance from the baseline
+    let slid = Rectangle::from_baseline(start, corner, Point::new(end.x + cos, end.y + sin));
+    assert_near(slid.end, end);
+  }
+
+  #[test]
+  fn test_quarter_turns_are_not_rotated() {
+    let rect = Rectangle::rotated(Point::new(0.0, 0.0), Point::new(-20.0, 10.0), 90.0);
+    assert_eq!(rect.rotation, None);
+    assert_eq!(
+      Rectangle::rotated(rect.start, rect.end, -180.0).rotation,
+      None
+    );
+
+    // A vertical baseline gives an unrotated rectangle with the same corners
+    let three_click = Rectangle::from_baseline(
+      Point::new(0.0, 0.0),
+      Point::new(0.0, 10.0),
+      Point::new(-20.0, 5.0),
+    );
+    assert_eq!(three_click.rotation, None);
+    assert_near(three_click.end, Point::new(-20.0, 10.0));
+    assert!((three_click.area() - 200.0).abs() < 1e-9);
+
+    // A stored quarter turn is treated the same as none
+    let stored = Rectangle {
+      rotation: Some(90.0),
+      ..rect
+    };
+    assert!(!stored.is_rotated());
+    assert_eq!(stored.corners(), rect.corners());
+  }
+
+  #[test]
+  fn test_set_corner_keeps_rotation() {
+    let mut rect = Rectangle::rotated(Point::new(0.0, 0.0), Point::new(0.0, 20.0), 45.0);
+    let before = rect.corners();
+    assert!(rect.set_corner(1, Point::new(20.0, 20.0)));
+    let after = rect.corners();
+    assert_near(after[3], before[3]);
+    assert_near(after[1], Point::new(20.0, 20.0));
+    assert_eq!(rect.rotation, Some(45.0));
+    assert!(!rect.set_corner(4, Point::new(0.0, 0.0)));
   }
 }
@@ -1,7 +1,110 @@
+use crate::units::{format_mixed_fraction, parse_mixed_number};
 use crate::Unit;
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 
+/// Meters in one inch, used for metric ratio scales
+const METERS_PER_INCH: f64 = 0.0254;
+
+/// Family of a printed drawing scale
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScaleCategory {
+  /// Fractional inches to a foot, e.g. `1/4" = 1'-0"`
+  Architectural,
+  /// One inch to a number of feet, e.g. `1" = 20'`
+  Engineering,
+  /// Ratio scales, e.g. `1:100`
+  Metric,
+}
+
+/// A standard printed drawing scale
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScalePreset {
+  /// Conventional notation, e.g. `1/4" = 1'-0"`
+  pub notation: String,
+  pub category: ScaleCategory,
+  /// Distance on paper, in inches
+  pub paper_inches: f64,
+  /// Real-world distance represented by `paper_inches`
+  pub real_distance: f64,
+  /// Unit for the real-world distance
+  pub unit: Unit,
+}
+
+impl ScalePreset {
+  fn architectural(paper_inches: f64) -> Self {
+    Self {
+      notation: format!("{}\" = 1'-0\"", format_mixed_fraction(paper_inches, 32)),
+      category: ScaleCategory::Architectural,
+      paper_inches,
+      real_distance: 1.0,
+      unit: Unit::Feet,
+    }
+  }
+
+  fn engineering(feet: f64) -> Self {
+    Self {
+      notation: format!("1\" = {feet}'"),
+      category: ScaleCategory::Engineering,
+      paper_inches: 1.0,
+      real_distance: feet,
+      unit: Unit::Feet,
+    }
+  }
+
+  fn metric(ratio: f64) -> Self {
+    Self {
+      notation: format!("1:{ratio}"),
+      category: ScaleCategory::Metric,
+      paper_inches: 1.0,
+      real_distance: ratio * METERS_PER_INCH,
+      unit: Unit::Meters,
+    }
+  }
+
+  /// Built-in catalog of standard architectural, engineering and metric scales
+  pub fn catalog() -> Vec<ScalePreset> {
+    let architectural = [
+      1.0 / 16.0,
+      3.0 / 32.0,
+      1.0 / 8.0,
+      3.0 / 16.0,
+      1.0 / 4.0,
+      3.0 / 8.0,
+      1.0 / 2.0,
+      3.0 / 4.0,
+      1.0,
+      1.5,
+      3.0,
+    ];
+    let engineering = [10.0, 20.0, 30.0, 40.0, 50.0, 60.0, 100.0];
+    let metric = [
+      1.0, 2.0, 5.0, 10.0, 20.0, 25.0, 50.0, 100.0, 200.0, 250.0, 500.0, 1000.0,
+    ];
+    architectural
+      .into_iter()
+      .map(Self::architectural)
+      .chain(engineering.into_iter().map(Self::engineering))
+      .chain(metric.into_iter().map(Self::metric))
+      .collect()
+  }
+
+  /// Find a catalog preset by its notation, ignoring spacing
+  pub fn find(notation: &str) -> Option<ScalePreset> {
+    let key = normalize_notation(notation).replace(' ', "");
+    Self::catalog()
+      .into_iter()
+      .find(|preset| preset.notation.replace(' ', "") == key)
+  }
+
+  /// Create the scale of this preset for a page rendered at `dpi`
+  pub fn to_scale(&self, dpi: f64) -> Scale {
+    Scale::new(self.paper_inches * dpi, self.real_distance, self.unit)
+  }
+}
+
 /// Scale information for converting pixel measurements to real-world measurements
 #[napi(object)]
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -52,4 +155,208 @@ impl Scale {
     let real_in_scale_unit = self.pixel_to_real(pixel_distance);
     self.unit.convert(real_in_scale_unit, &target_unit)
   }
+
+  /// Parse a printed scale such as `1/4" = 1'-0"`, `1" = 20'` or `1:100`
+  ///
+  /// `dpi` is the resolution the page is rendered at, so one paper inch is `dpi` pixels.
+  pub fn parse(notation: &str, dpi: f64) -> Option<Scale> {
+    if dpi <= 0.0 {
+      return None;
+    }
+    let notation = normalize_notation(notation);
+    if let Some((paper, real)) = notation.split_once(':') {
+      let paper = parse_mixed_number(paper)?;
+      let real = parse_mixed_number(real)?;
+      if paper <= 0.0 || real <= 0.0 {
+        return None;
+      }
+      return Some(ScalePreset::metric(real / paper).to_scale(dpi));
+    }
+    let (paper, real) = notation.split_once('=')?;
+    let (paper, paper_unit) = parse_length(paper)?;
+    let (real, real_unit) = parse_length(real)?;
+    let paper_inches = paper_unit.convert(paper, &Unit::Inches);
+    if paper_inches <= 0.0 || real <= 0.0 {
+      return None;
+    }
+    Some(Scale::new(paper_inches * dpi, real, real_unit))
+  }
+
+  /// Create the scale of a catalog preset for a page rendered at `dpi`
+  pub fn from_preset(preset: &ScalePreset, dpi: f64) -> Scale {
+    preset.to_scale(dpi)
+  }
+
+  /// Format the scale in conventional notation for a page rendered at `dpi`
+  ///
+  /// Catalog scales print as in the catalog. Other metric scales print as a ratio
+  /// and other imperial scales as paper inches to one foot.
+  pub fn to_notation(&self, dpi: f64) -> String {
+    let meters_per_pixel = self.pixel_to_real_unit(1.0, Unit::Meters);
+    if let Some(preset) = ScalePreset::catalog().into_iter().find(|preset| {
+      let preset_meters_per_pixel = preset.to_scale(dpi).pixel_to_real_unit(1.0, Unit::Meters);
+      (meters_per_pixel - preset_meters_per_pixel).abs() <= preset_meters_per_pixel * 1e-6
+    }) {
+      return preset.notation;
+    }
+
+    let paper_inches = self.pixel_distance / dpi;
+    match self.unit {
+      Unit::Meters | Unit::Centimeters => {
+        let ratio =
+          self.unit.convert(self.real_distance, &Unit::Meters) / (paper_inches * METERS_PER_INCH);
+        format!("1:{}", trim_decimal(ratio))
+      }
+      Unit::Yards | Unit::Feet | Unit::Inches => {
+        let inches_per_foot = paper_inches / self.unit.convert(self.real_distance, &Unit::Feet);
+        let feet_per_inch = 1.0 / inches_per_foot;
+        if inches_per_foot < 1.0 && (feet_per_inch - feet_per_inch.round()).abs() < 1e-6 {
+          format!("1\" = {}'", feet_per_inch.round())
+        } else if (inches_per_foot * 64.0 - (inches_per_foot * 64.0).round()).abs() < 1e-6 {
+          format!("{}\" = 1'-0\"", format_mixed_fraction(inches_per_foot, 64))
+        } else {
+          format!("{}\" = 1'-0\"", trim_decimal(inches_per_foot))
+        }
+      }
+    }
+  }
+}
+
+/// Replace typographic quotes and primes with plain ones
+fn normalize_notation(notation: &str) -> String {
+  notation
+    .trim()
+    .replace(['\u{2018}', '\u{2019}', '\u{2032}'], "'")
+    .replace(['\u{201C}', '\u{201D}', '\u{2033}'], "\"")
+    .replace("''", "\"")
+}
+
+/// Parse a length like `1/4"`, `1'-6"`, `20'`, `3 in`, `1 inch`, `1 foot`, `10 m` or `5 mm`
+///
+/// Unit names may be singular or plural. Longer names come first in the suffix
+/// list, since `millimeters` also ends in `meters`.
+fn parse_length(text: &str) -> Option<(f64, Unit)> {
+  let text = text.trim().to_lowercase();
+  if let Some((feet, inches)) = text.split_once('\'') {
+    let feet = parse_mixed_number(feet)?;
+    let inches = inches.trim().trim_start_matches('-').trim_end_matches('"');
+    let inches = if inches.trim().is_empty() {
+      0.0
+    } else {
+      parse_mixed_number(inches)?
+    };
+    return Some((feet + inches / 12.0, Unit::Feet));
+  }
+  let suffixes: [(&str, f64, Unit); 19] = [
+    ("\"", 1.0, Unit::Inches),
+    ("inches", 1.0, Unit::Inches),
+    ("inch", 1.0, Unit::Inches),
+    ("in", 1.0, Unit::Inches),
+    ("feet", 1.0, Unit::Feet),
+    ("foot", 1.0, Unit::Feet),
+    ("ft", 1.0, Unit::Feet),
+    ("yards", 1.0, Unit::Yards),
+    ("yard", 1.0, Unit::Yards),
+    ("yd", 1.0, Unit::Yards),
+    ("millimeters", 0.1, Unit::Centimeters),
+    ("millimeter", 0.1, Unit::Centimeters),
+    ("mm", 0.1, Unit::Centimeters),
+    ("centimeters", 1.0, Unit::Centimeters),
+    ("centimeter", 1.0, Unit::Centimeters),
+    ("cm", 1.0, Unit::Centimeters),
+    ("meters", 1.0, Unit::Meters),
+    ("meter", 1.0, Unit::Meters),
+    ("m", 1.0, Unit::Meters),
+  ];
+  suffixes.iter().find_map(|(suffix, factor, unit)| {
+    let number = text.strip_suffix(suffix)?;
+    Some((parse_mixed_number(number)? * factor, *unit))
+  })
+}
+
+/// Format a number with up to two decimals, dropping trailing zeros
+fn trim_decimal(value: f64) -> String {
+  let text = format!("{value:.2}");
+  text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(a: f64, b: f64) {
+    assert!((a - b).abs() < 1e-9, "{a} != {b}");
+  }
+
+  #[test]
+  fn test_parse_architectural() {
+    let scale = Scale::parse("1/4\" = 1'-0\"", 72.0).unwrap();
+    assert_close(scale.pixel_distance, 18.0);
+    assert_close(scale.real_distance, 1.0);
+    assert_eq!(scale.unit, Unit::Feet);
+    // 18 px is one foot
+    assert_close(scale.pixel_to_real_unit(18.0, Unit::Inches), 12.0);
+
+    let scale = Scale::parse("1 1/2\u{201D}=1\u{2019}-0\u{201D}", 100.0).unwrap();
+    assert_close(scale.pixel_distance, 150.0);
+    assert_close(scale.real_distance, 1.0);
+
+    let scale = Scale::parse("3/32 in = 1 ft", 96.0).unwrap();
+    assert_close(scale.pixel_distance, 9.0);
+  }
+
+  #[test]
+  fn test_parse_engineering_and_metric() {
+    let scale = Scale::parse("1\" = 20'", 72.0).unwrap();
+    assert_close(scale.pixel_to_real_unit(72.0, Unit::Feet), 20.0);
+
+    let scale = Scale::parse("1:100", 72.0).unwrap();
+    // One paper inch is 100 real inches
+    assert_close(scale.pixel_to_real_unit(72.0, Unit::Inches), 100.0);
+
+    let scale = Scale::parse("10 mm = 1 m", 254.0).unwrap();
+    assert_close(scale.pixel_to_real_unit(100.0, Unit::Meters), 1.0);
+
+    // Singular unit names
+    let scale = Scale::parse("1\" = 1 foot", 72.0).unwrap();
+    assert_close(scale.pixel_to_real_unit(72.0, Unit::Feet), 1.0);
+    let scale = Scale::parse("1 inch = 20 feet", 72.0).unwrap();
+    assert_close(scale.pixel_to_real_unit(72.0, Unit::Feet), 20.0);
+    let scale = Scale::parse("1 millimeter = 1 meter", 254.0).unwrap();
+    assert_close(scale.pixel_to_real_unit(10.0, Unit::Meters), 1.0);
+  }
+
+  #[test]
+  fn test_parse_rejects_garbage() {
+    assert!(Scale::parse("NTS", 72.0).is_none());
+    assert!(Scale::parse("1/0\" = 1'", 72.0).is_none());
+    assert!(Scale::parse("1:100", 0.0).is_none());
+    assert!(Scale::parse("abc = 1'", 72.0).is_none());
+  }
+
+  #[test]
+  fn test_catalog_round_trip() {
+    for preset in ScalePreset::catalog() {
+      let scale = Scale::parse(&preset.notation, 150.0).unwrap();
+      assert_eq!(scale.to_notation(150.0), preset.notation);
+      assert_eq!(ScalePreset::find(&preset.notation), Some(preset));
+    }
+  }
+
+  #[test]
+  fn test_to_notation_outside_catalog() {
+    let dpi = 72.0;
+    assert_eq!(
+      Scale::new(dpi * 5.0 / 64.0, 1.0, Unit::Feet).to_notation(dpi),
+      "5/64\" = 1'-0\""
+    );
+    assert_eq!(
+      Scale::new(dpi, 80.0, Unit::Feet).to_notation(dpi),
+      "1\" = 80'"
+    );
+    assert_eq!(
+      Scale::new(dpi, 75.0 * METERS_PER_INCH, Unit::Meters).to_notation(dpi),
+      "1:75"
+    );
+  }
 }
@@ -0,0 +1,175 @@
+use crate::{Point, Polygon, Rectangle, Scale};
+use geo::{Intersects, Point as GeoPoint, Polygon as GeoPolygon};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// A part of a page drawn at its own scale, such as a detail next to a plan
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScaleRegion {
+  pub id: String,
+  pub name: Option<String>,
+  pub rectangle: Option<Rectangle>,
+  pub polygon: Option<Polygon>,
+  pub scale: Scale,
+}
+
+impl ScaleRegion {
+  pub fn from_rectangle(id: impl Into<String>, rectangle: Rectangle, scale: Scale) -> Self {
+    Self {
+      id: id.into(),
+      name: None,
+      rectangle: Some(rectangle),
+      polygon: None,
+      scale,
+    }
+  }
+
+  pub fn from_polygon(id: impl Into<String>, polygon: Polygon, scale: Scale) -> Self {
+    Self {
+      id: id.into(),
+      name: None,
+      rectangle: None,
+      polygon: Some(polygon),
+      scale,
+    }
+  }
+
+  fn to_polygon(&self) -> Option<GeoPolygon<f64>> {
+    if let Some(rect) = &self.rectangle {
+      Some(rect.to_polygon())
+    } else {
+      self.polygon.as_ref().map(|poly| poly.to_polygon())
+    }
+  }
+
+  /// Check if a point lies inside the region or on its boundary
+  pub fn contains(&self, point: &Point) -> bool {
+    self
+      .to_polygon()
+      .is_some_and(|region| region.intersects(&GeoPoint::new(point.x, point.y)))
+  }
+}
+
+/// Warning raised when a measurement spans more than one scale
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScaleRegionWarning {
+  pub message: String,
+  /// Regions the measurement touches
+  pub region_ids: Vec<String>,
+}
+
+/// The scale picked for a set of points
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaleResolution {
+  /// Scale of the region holding the first point, or the page scale
+  pub scale: Option<Scale>,
+  /// Region holding the first point, if any
+  pub region_id: Option<String>,
+  /// Set when the points fall in different regions
+  pub warning: Option<ScaleRegionWarning>,
+}
+
+/// Find the topmost region containing a point
+///
+/// Regions later in the list are drawn on top of earlier ones.
+pub fn region_at<'a>(regions: &'a [ScaleRegion], point: &Point) -> Option<&'a ScaleRegion> {
+  regions.iter().rev().find(|region| region.contains(point))
+}
+
+/// Pick the scale for a measurement drawn through `points`
+///
+/// The first point decides the region; `fallback` is used outside every region.
+/// A warning is returned if any other point lies in a different region.
+pub fn resolve_scale(
+  regions: &[ScaleRegion],
+  fallback: Option<Scale>,
+  points: &[Point],
+) -> ScaleResolution {
+  let Some(first) = points.first() else {
+    return ScaleResolution {
+      scale: fallback,
+      region_id: None,
+      warning: None,
+    };
+  };
+  let region = region_at(regions, first);
+  let region_id = region.map(|r| r.id.clone());
+
+  let mut touched: Vec<Option<String>> = vec![region_id.clone()];
+  for point in &points[1..] {
+    let id = region_at(regions, point).map(|r| r.id.clone());
+    if !touched.contains(&id) {
+      touched.push(id);
+    }
+  }
+  let warning = (touched.len() > 1).then(|| ScaleRegionWarning {
+    message: "Measurement crosses a scale region boundary".to_string(),
+    region_ids: touched.into_iter().flatten().collect(),
+  });
+
+  ScaleResolution {
+    scale: region.map(|r| r.scale).or(fallback),
+    region_id,
+    warning,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Unit;
+
+  fn regions() -> Vec<ScaleRegion> {
+    vec![
+      ScaleRegion::from_rectangle(
+        "detail",
+        Rectangle::new(Point::new(0.0, 0.0), Point::new(100.0, 100.0)),
+        Scale::new(36.0, 1.0, Unit::Feet),
+      ),
+      ScaleRegion::from_polygon(
+        "inset",
+        Polygon::new(vec![
+          Point::new(50.0, 50.0),
+          Point::new(90.0, 50.0),
+          Point::new(70.0, 90.0),
+        ]),
+        Scale::new(72.0, 1.0, Unit::Feet),
+      ),
+    ]
+  }
+
+  #[test]
+  fn test_resolve_inside_region() {
+    let page = Scale::new(9.0, 1.0, Unit::Feet);
+    let resolution = resolve_scale(
+      &regions(),
+      Some(page),
+      &[Point::new(10.0, 10.0), Point::new(20.0, 10.0)],
+    );
+    assert_eq!(resolution.region_id.as_deref(), Some("detail"));
+    assert_eq!(resolution.scale.unwrap().pixel_distance, 36.0);
+    assert!(resolution.warning.is_none());
+
+    // The later region is on top
+    let resolution = resolve_scale(&regions(), Some(page), &[Point::new(70.0, 60.0)]);
+    assert_eq!(resolution.region_id.as_deref(), Some("inset"));
+
+    let resolution = resolve_scale(&regions(), Some(page), &[Point::new(500.0, 500.0)]);
+    assert_eq!(resolution.scale, Some(page));
+    assert!(resolution.region_id.is_none());
+  }
+
+  #[test]
+  fn test_resolve_crossing_boundary() {
+    let resolution = resolve_scale(
+      &regions(),
+      None,
+      &[Point::new(10.0, 10.0), Point::new(200.0, 10.0)],
+    );
+    assert_eq!(resolution.region_id.as_deref(), Some("detail"));
+    let warning = resolution.warning.unwrap();
+    assert_eq!(warning.region_ids, vec!["detail".to_string()]);
+  }
+}
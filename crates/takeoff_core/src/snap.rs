@@ -0,0 +1,356 @@
+use crate::{ArcSegment, Measurement, Point};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// What a snapped point was attached to
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SnapType {
+  /// A vertex or count marker of an existing measurement
+  Vertex,
+  /// A crossing of two existing edges
+  Intersection,
+  /// The middle of an existing edge
+  Midpoint,
+  /// The nearest point on an existing edge
+  Edge,
+  /// Horizontal or vertical from the previous point
+  Ortho,
+  /// A multiple of the angle increment from the previous point
+  Angle,
+}
+
+/// Which snaps are enabled and how close the pointer must be
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapSettings {
+  pub vertex: bool,
+  pub edge: bool,
+  pub midpoint: bool,
+  pub intersection: bool,
+  /// Lock to horizontal or vertical from the previous point
+  pub ortho: bool,
+  /// Lock to multiples of this angle from the previous point, in degrees (e.g. 15 or 45)
+  pub angle_increment: Option<f64>,
+  /// Snap distance in screen pixels
+  pub tolerance: f64,
+}
+
+impl Default for SnapSettings {
+  fn default() -> Self {
+    Self {
+      vertex: true,
+      edge: true,
+      midpoint: true,
+      intersection: true,
+      ortho: false,
+      angle_increment: None,
+      tolerance: 10.0,
+    }
+  }
+}
+
+/// A pointer position after snapping
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapResult {
+  pub point: Point,
+  /// The snap that fired, or `None` if the point was left as is
+  pub snap_type: Option<SnapType>,
+}
+
+impl SnapResult {
+  fn none(point: Point) -> Self {
+    Self {
+      point,
+      snap_type: None,
+    }
+  }
+}
+
+/// Snaps pointer positions to existing measurements and drawing constraints
+///
+/// Object snaps win over ortho and angle locks, in the order vertex,
+/// intersection, midpoint, edge.
+#[derive(Debug, Clone)]
+pub struct Snapper {
+  settings: SnapSettings,
+  segments: Vec<ArcSegment>,
+  markers: Vec<Point>,
+  view_scale: f64,
+}
+
+impl Snapper {
+  pub fn new(settings: SnapSettings) -> Self {
+    Self {
+      settings,
+      segments: Vec::new(),
+      markers: Vec::new(),
+      view_scale: 1.0,
+    }
+  }
+
+  pub fn settings(&self) -> &SnapSettings {
+    &self.settings
+  }
+
+  pub fn set_settings(&mut self, settings: SnapSettings) {
+    self.settings = settings;
+  }
+
+  /// Set the measurements to snap to
+  ///
+  /// Only measurements near the pointer matter, so large takeoffs can pass the
+  /// result of `SpatialIndex::query_point` instead of every measurement.
+  pub fn set_measurements<'a>(&mut self, measurements: impl IntoIterator<Item = &'a Measurement>) {
+    self.segments.clear();
+    self.markers.clear();
+    for measurement in measurements {
+      self.segments.extend(measurement.segments());
+      if let Measurement::Count { point, .. } = measurement {
+        self.markers.push(*point);
+      }
+    }
+  }
+
+  /// Snap tolerance in world units, for finding the measurements to snap to
+  pub fn search_radius(&self) -> f64 {
+    self.tolerance()
+  }
+
+  /// Set the zoom of the view (`Transform.scale`) so the tolerance stays in screen pixels
+  pub fn set_view_scale(&mut self, view_scale: f64) {
+    self.view_scale = view_scale;
+  }
+
+  /// Snap tolerance in world units
+  fn tolerance(&self) -> f64 {
+    if self.view_scale > 0.0 {
+      self.settings.tolerance / self.view_scale
+    } else {
+      self.settings.tolerance
+    }
+  }
+
+  /// Snap a point, using `anchor` (the previous point) for ortho and angle locks
+  pub fn snap(&self, point: Point, anchor: Option<Point>) -> SnapResult {
+    if let Some(result) = self.snap_to_objects(point) {
+      return result;
+    }
+    anchor
+      .and_then(|anchor| self.snap_to_angle(point, anchor))
+      .unwrap_or(SnapResult::none(point))
+  }
+
+  fn snap_to_objects(&self, point: Point) -> Option<SnapResult> {
+    let tolerance = self.tolerance();
+    let nearby: Vec<&ArcSegment> = self
+      .segments
+      .iter()
+      .filter(|segment| segment.distance_to_point(&point) <= tolerance)
+      .collect();
+
+    let mut candidates: Vec<(SnapType, Vec<Point>)> = Vec::new();
+    if self.settings.vertex {
+      let vertices = nearby
+        .iter()
+        .flat_map(|segment| [segment.start, segment.end])
+        .chain(self.markers.iter().copied())
+        .collect();
+      candidates.push((SnapType::Vertex, vertices));
+    }
+    if self.settings.intersection {
+      let mut crossings = Vec::new();
+      for (i, a) in nearby.iter().enumerate() {
+        for b in &nearby[i + 1..] {
+          crossings.extend(a.intersections(b));
+        }
+      }
+      candidates.push((SnapType::Intersection, crossings));
+    }
+    if self.settings.midpoint {
+      let midpoints = nearby.iter().map(|segment| segment.point_at(0.5)).collect();
+      candidates.push((SnapType::Midpoint, midpoints));
+    }
+    if self.settings.edge {
+      let edges = nearby
+        .iter()
+        .map(|segment| segment.closest_point(&point))
+        .collect();
+      candidates.push((SnapType::Edge, edges));
+    }
+
+    candidates.into_iter().find_map(|(snap_type, points)| {
+      points
+        .into_iter()
+        .map(|candidate| (candidate.distance_to(&point), candidate))
+        .filter(|(distance, _)| *distance <= tolerance)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, candidate)| SnapResult {
+          point: candidate,
+          snap_type: Some(snap_type),
+        })
+    })
+  }
+
+  fn snap_to_angle(&self, point: Point, anchor: Point) -> Option<SnapResult> {
+    let (step, snap_type) = if self.settings.ortho {
+      (90.0_f64, SnapType::Ortho)
+    } else {
+      (
+        self.settings.angle_increment.filter(|step| *step > 0.0)?,
+        SnapType::Angle,
+      )
+    };
+    let (dx, dy) = (point.x - anchor.x, point.y - anchor.y);
+    if dx == 0.0 && dy == 0.0 {
+      return None;
+    }
+    let step = step.to_radians();
+    let angle = (dy.atan2(dx) / step).round() * step;
+    let (ux, uy) = (angle.cos(), angle.sin());
+    // Axis directions are exact, so ortho points share a coordinate with the anchor
+    let point = if uy.abs() < 1e-12 {
+      Point::new(point.x, anchor.y)
+    } else if ux.abs() < 1e-12 {
+      Point::new(anchor.x, point.y)
+    } else {
+      // Keep the distance along the locked direction
+      let along = dx * ux + dy * uy;
+      Point::new(anchor.x + along * ux, anchor.y + along * uy)
+    };
+    Some(SnapResult {
+      point,
+      snap_type: Some(snap_type),
+    })
+  }
+}
+
+impl Default for Snapper {
+  fn default() -> Self {
+    Self::new(SnapSettings::default())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Line, Polygon, Scale, Unit};
+
+  fn line(id: &str, start: Point, end: Point) -> Measurement {
+    Measurement::Linear {
+      id: id.to_string(),
+      line: Some(Line::new(start, end)),
+      polyline: None,
+      scale: Scale::new(1.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    }
+  }
+
+  fn snapper() -> Snapper {
+    let mut snapper = Snapper::new(SnapSettings::default());
+    snapper.set_measurements(&[
+      line("a", Point::new(0.0, 0.0), Point::new(100.0, 0.0)),
+      line("b", Point::new(40.0, -50.0), Point::new(40.0, 50.0)),
+      Measurement::Area {
+        id: "room".to_string(),
+        rectangle: None,
+        polygon: Some(Polygon::new(vec![
+          Point::new(200.0, 0.0),
+          Point::new(300.0, 0.0),
+          Point::new(300.0, 100.0),
+        ])),
+        circle: None,
+        ellipse: None,
+        scale: Scale::new(1.0, 1.0, Unit::Feet),
+        display_unit: Unit::Feet,
+        depth: None,
+        pitch: None,
+        metadata: None,
+      },
+      Measurement::Count {
+        id: "c".to_string(),
+        point: Point::new(500.0, 500.0),
+        metadata: None,
+      },
+    ]);
+    snapper
+  }
+
+  #[test]
+  fn test_object_snaps() {
+    let snapper = snapper();
+    let result = snapper.snap(Point::new(98.0, 3.0), None);
+    assert_eq!(result.snap_type, Some(SnapType::Vertex));
+    assert_eq!(result.point, Point::new(100.0, 0.0));
+
+    let result = snapper.snap(Point::new(42.0, 2.0), None);
+    assert_eq!(result.snap_type, Some(SnapType::Intersection));
+    assert_eq!(result.point, Point::new(40.0, 0.0));
+
+    let result = snapper.snap(Point::new(248.0, 3.0), None);
+    assert_eq!(result.snap_type, Some(SnapType::Midpoint));
+    assert_eq!(result.point, Point::new(250.0, 0.0));
+
+    // The closing edge of the polygon
+    let result = snapper.snap(Point::new(230.0, 25.0), None);
+    assert_eq!(result.snap_type, Some(SnapType::Edge));
+    assert!((result.point.x - 227.5).abs() < 1e-9);
+    assert!((result.point.y - 27.5).abs() < 1e-9);
+
+    let result = snapper.snap(Point::new(504.0, 497.0), None);
+    assert_eq!(result.snap_type, Some(SnapType::Vertex));
+
+    let result = snapper.snap(Point::new(150.0, 50.0), None);
+    assert_eq!(result, SnapResult::none(Point::new(150.0, 50.0)));
+  }
+
+  #[test]
+  fn test_tolerance_uses_view_scale() {
+    let mut snapper = snapper();
+    assert!(snapper
+      .snap(Point::new(100.0, 8.0), None)
+      .snap_type
+      .is_some());
+    // Zoomed in 4x, 10 screen pixels is 2.5 world units
+    snapper.set_view_scale(4.0);
+    assert!(snapper
+      .snap(Point::new(100.0, 8.0), None)
+      .snap_type
+      .is_none());
+    assert!(snapper
+      .snap(Point::new(100.0, 2.0), None)
+      .snap_type
+      .is_some());
+  }
+
+  #[test]
+  fn test_ortho_and_angle_locks() {
+    let anchor = Point::new(1000.0, 1000.0);
+    let mut snapper = Snapper::new(SnapSettings {
+      ortho: true,
+      ..SnapSettings::default()
+    });
+    let result = snapper.snap(Point::new(1050.0, 1010.0), Some(anchor));
+    assert_eq!(result.snap_type, Some(SnapType::Ortho));
+    assert_eq!(result.point, Point::new(1050.0, 1000.0));
+    // Without an anchor there is nothing to lock to
+    assert!(snapper
+      .snap(Point::new(1050.0, 1010.0), None)
+      .snap_type
+      .is_none());
+
+    snapper.set_settings(SnapSettings {
+      angle_increment: Some(45.0),
+      ..SnapSettings::default()
+    });
+    let result = snapper.snap(Point::new(1050.0, 1040.0), Some(anchor));
+    assert_eq!(result.snap_type, Some(SnapType::Angle));
+    assert!((result.point.x - result.point.y).abs() < 1e-9);
+    assert!((result.point.x - 1045.0).abs() < 1e-9);
+  }
+}
@@ -0,0 +1,356 @@
+use crate::{Measurement, Point};
+use rstar::{PointDistance, RTree, RTreeObject, SelectionFunction, AABB};
+use std::collections::{HashMap, HashSet};
+
+/// A measurement's bounding box in the tree
+#[derive(Debug, Clone)]
+struct BoundsEntry {
+  id: String,
+  order: u64,
+  envelope: AABB<[f64; 2]>,
+}
+
+impl RTreeObject for BoundsEntry {
+  type Envelope = AABB<[f64; 2]>;
+
+  fn envelope(&self) -> Self::Envelope {
+    self.envelope
+  }
+}
+
+/// One editable vertex of a measurement in the tree
+#[derive(Debug, Clone)]
+struct VertexEntry {
+  id: String,
+  order: u64,
+  index: usize,
+  point: [f64; 2],
+}
+
+impl RTreeObject for VertexEntry {
+  type Envelope = AABB<[f64; 2]>;
+
+  fn envelope(&self) -> Self::Envelope {
+    AABB::from_point(self.point)
+  }
+}
+
+impl PointDistance for VertexEntry {
+  fn distance_2(&self, point: &[f64; 2]) -> f64 {
+    let (dx, dy) = (self.point[0] - point[0], self.point[1] - point[1]);
+    dx * dx + dy * dy
+  }
+}
+
+/// Selects the entries of one measurement, searching only inside its bounding box
+struct SelectMeasurement {
+  order: u64,
+  envelope: AABB<[f64; 2]>,
+}
+
+impl SelectionFunction<BoundsEntry> for SelectMeasurement {
+  fn should_unpack_parent(&self, envelope: &AABB<[f64; 2]>) -> bool {
+    rstar::Envelope::intersects(envelope, &self.envelope)
+  }
+
+  fn should_unpack_leaf(&self, leaf: &BoundsEntry) -> bool {
+    leaf.order == self.order
+  }
+}
+
+impl SelectionFunction<VertexEntry> for SelectMeasurement {
+  fn should_unpack_parent(&self, envelope: &AABB<[f64; 2]>) -> bool {
+    rstar::Envelope::intersects(envelope, &self.envelope)
+  }
+
+  fn should_unpack_leaf(&self, leaf: &VertexEntry) -> bool {
+    leaf.order == self.order
+  }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Record {
+  order: u64,
+  envelope: AABB<[f64; 2]>,
+}
+
+/// A vertex found near a point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IndexedVertex<'a> {
+  pub measurement_id: &'a str,
+  /// Index as in `Measurement::vertices`
+  pub vertex_index: usize,
+  pub point: Point,
+  pub distance: f64,
+}
+
+/// R-tree over measurement bounding boxes and vertices
+///
+/// Keeps each measurement's z-order: measurements inserted later are drawn on
+/// top, and updating a measurement keeps its place. Query results are IDs,
+/// bottom to top; look the measurements up in the state with `resolve`.
+#[derive(Debug, Clone, Default)]
+pub struct SpatialIndex {
+  bounds: RTree<BoundsEntry>,
+  vertices: RTree<VertexEntry>,
+  records: HashMap<String, Record>,
+  next_order: u64,
+}
+
+impl SpatialIndex {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Build an index over measurements in drawing order
+  pub fn from_measurements(measurements: &[Measurement]) -> Self {
+    let mut index = Self::new();
+    index.rebuild(measurements);
+    index
+  }
+
+  /// Replace the contents of the index
+  pub fn rebuild(&mut self, measurements: &[Measurement]) {
+    self.records.clear();
+    let mut bounds = Vec::with_capacity(measurements.len());
+    let mut vertices = Vec::new();
+    for (order, measurement) in measurements.iter().enumerate() {
+      let order = order as u64;
+      let Some(envelope) = envelope_of(measurement) else {
+        continue;
+      };
+      bounds.push(BoundsEntry {
+        id: measurement.id().to_string(),
+        order,
+        envelope,
+      });
+      vertices.extend(vertex_entries(measurement, order));
+      self
+        .records
+        .insert(measurement.id().to_string(), Record { order, envelope });
+    }
+    self.bounds = RTree::bulk_load(bounds);
+    self.vertices = RTree::bulk_load(vertices);
+    self.next_order = measurements.len() as u64;
+  }
+
+  /// Add a measurement on top, or replace one with the same ID in place
+  pub fn insert(&mut self, measurement: &Measurement) {
+    let order = match self.remove_entries(measurement.id()) {
+      Some(record) => record.order,
+      None => {
+        self.next_order += 1;
+        self.next_order - 1
+      }
+    };
+    // Measurements without any points cannot be found by location
+    let Some(envelope) = envelope_of(measurement) else {
+      return;
+    };
+    self.bounds.insert(BoundsEntry {
+      id: measurement.id().to_string(),
+      order,
+      envelope,
+    });
+    for vertex in vertex_entries(measurement, order) {
+      self.vertices.insert(vertex);
+    }
+    self
+      .records
+      .insert(measurement.id().to_string(), Record { order, envelope });
+  }
+
+  /// Remove a measurement by ID
+  pub fn remove(&mut self, id: &str) -> bool {
+    self.remove_entries(id).is_some()
+  }
+
+  fn remove_entries(&mut self, id: &str) -> Option<Record> {
+    let record = self.records.remove(id)?;
+    let select = || SelectMeasurement {
+      order: record.order,
+      envelope: record.envelope,
+    };
+    self.bounds.remove_with_selection_function(select());
+    self
+      .vertices
+      .drain_with_selection_function(select())
+      .count();
+    Some(record)
+  }
+
+  pub fn len(&self) -> usize {
+    self.records.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.records.is_empty()
+  }
+
+  pub fn contains(&self, id: &str) -> bool {
+    self.records.contains_key(id)
+  }
+
+  /// Drawing order of a measurement; higher is on top
+  pub fn z_order(&self, id: &str) -> Option<u64> {
+    self.records.get(id).map(|record| record.order)
+  }
+
+  /// IDs of measurements whose bounding box overlaps a rectangle, bottom to top
+  pub fn query_rect(&self, min: Point, max: Point) -> Vec<&str> {
+    let envelope = AABB::from_corners([min.x, min.y], [max.x, max.y]);
+    self.sorted(
+      self
+        .bounds
+        .locate_in_envelope_intersecting(&envelope)
+        .map(|entry| entry.id.as_str()),
+    )
+  }
+
+  /// IDs of measurements whose bounding box is within `radius` of a point, bottom to top
+  pub fn query_point(&self, point: Point, radius: f64) -> Vec<&str> {
+    self.query_rect(
+      Point::new(point.x - radius, point.y - radius),
+      Point::new(point.x + radius, point.y + radius),
+    )
+  }
+
+  /// Vertices within `radius` of a point, nearest first
+  pub fn vertices_within(&self, point: Point, radius: f64) -> Vec<IndexedVertex<'_>> {
+    let mut found: Vec<IndexedVertex> = self
+      .vertices
+      .locate_within_distance([point.x, point.y], radius * radius)
+      .map(|entry| {
+        let vertex = Point::new(entry.point[0], entry.point[1]);
+        IndexedVertex {
+          measurement_id: &entry.id,
+          vertex_index: entry.index,
+          point: vertex,
+          distance: vertex.distance_to(&point),
+        }
+      })
+      .collect();
+    found.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    found
+  }
+
+  fn sorted<'a>(&self, ids: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut found: Vec<(u64, &str)> = ids
+      .filter_map(|id| Some((self.records.get(id)?.order, id)))
+      .collect();
+    found.sort_by_key(|(order, _)| *order);
+    found.into_iter().map(|(_, id)| id).collect()
+  }
+
+  /// Pick the measurements with the given IDs out of `measurements`, in its order
+  ///
+  /// Used to look up query results in the measurements the index was built
+  /// from, such as `TakeoffState::measurements`, so results are always current.
+  pub fn resolve<'a>(measurements: &'a [Measurement], ids: &[&str]) -> Vec<&'a Measurement> {
+    if ids.is_empty() {
+      return Vec::new();
+    }
+    let ids: HashSet<&str> = ids.iter().copied().collect();
+    measurements
+      .iter()
+      .filter(|measurement| ids.contains(measurement.id()))
+      .collect()
+  }
+}
+
+fn envelope_of(measurement: &Measurement) -> Option<AABB<[f64; 2]>> {
+  let (min, max) = measurement.bounds()?;
+  Some(AABB::from_corners([min.x, min.y], [max.x, max.y]))
+}
+
+fn vertex_entries(measurement: &Measurement, order: u64) -> impl Iterator<Item = VertexEntry> + '_ {
+  measurement
+    .vertices()
+    .into_iter()
+    .enumerate()
+    .map(move |(index, point)| VertexEntry {
+      id: measurement.id().to_string(),
+      order,
+      index,
+      point: [point.x, point.y],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Line, Scale, Unit};
+
+  fn count(id: &str, x: f64, y: f64) -> Measurement {
+    Measurement::Count {
+      id: id.to_string(),
+      point: Point::new(x, y),
+      metadata: None,
+    }
+  }
+
+  fn line(id: &str, start: Point, end: Point) -> Measurement {
+    Measurement::Linear {
+      id: id.to_string(),
+      line: Some(Line::new(start, end)),
+      polyline: None,
+      scale: Scale::new(1.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    }
+  }
+
+  #[test]
+  fn test_queries() {
+    let mut measurements = vec![
+      line("wall", Point::new(0.0, 0.0), Point::new(100.0, 0.0)),
+      count("c1", 50.0, 5.0),
+      count("c2", 500.0, 500.0),
+    ];
+    let index = SpatialIndex::from_measurements(&measurements);
+    assert_eq!(index.len(), 3);
+    assert_eq!(
+      index.query_rect(Point::new(40.0, -10.0), Point::new(60.0, 10.0)),
+      vec!["wall", "c1"]
+    );
+    assert_eq!(index.query_point(Point::new(498.0, 498.0), 5.0), vec!["c2"]);
+    assert!(index.query_point(Point::new(300.0, 300.0), 5.0).is_empty());
+
+    let vertices = index.vertices_within(Point::new(97.0, 1.0), 10.0);
+    assert_eq!(vertices.len(), 1);
+    assert_eq!(vertices[0].measurement_id, "wall");
+    assert_eq!(vertices[0].vertex_index, 1);
+
+    // Results are looked up in the current measurements, not copies
+    measurements[1] = count("c1", 50.0, 6.0);
+    let found = SpatialIndex::resolve(&measurements, &["c1", "missing", "wall"]);
+    assert_eq!(found, vec![&measurements[0], &measurements[1]]);
+  }
+
+  #[test]
+  fn test_insert_update_remove() {
+    let mut index = SpatialIndex::new();
+    index.insert(&count("a", 0.0, 0.0));
+    index.insert(&count("b", 1.0, 1.0));
+    assert_eq!(index.query_point(Point::new(0.5, 0.5), 1.0), vec!["a", "b"]);
+
+    // Moving a measurement keeps its z-order and drops its old location
+    index.insert(&count("a", 2.0, 2.0));
+    assert_eq!(index.len(), 2);
+    assert!(index.vertices_within(Point::new(0.0, 0.0), 0.5).is_empty());
+    assert_eq!(index.query_point(Point::new(1.5, 1.5), 1.0), vec!["a", "b"]);
+    assert!(index.z_order("a") < index.z_order("b"));
+
+    assert!(index.remove("b"));
+    assert!(!index.remove("b"));
+    assert_eq!(index.query_point(Point::new(1.5, 1.5), 1.0), vec!["a"]);
+    assert!(index.vertices_within(Point::new(1.0, 1.0), 0.5).is_empty());
+
+    // New measurements go on top
+    index.insert(&count("c", 2.0, 2.0));
+    assert_eq!(index.query_point(Point::new(2.0, 2.0), 0.1), vec!["a", "c"]);
+  }
+}
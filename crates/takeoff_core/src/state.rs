@@ -1,4 +1,10 @@
-use crate::{Measurement, Scale, Transform};
+use crate::condition::condition_totals;
+use crate::cost::cost_summary;
+use crate::scale_region::resolve_scale;
+use crate::{
+  Assembly, Condition, ConditionTotals, CostSummary, DerivedItem, Measurement, PageKey, Point,
+  Scale, ScaleRegion, ScaleRegionWarning, ScaleResolution, Transform,
+};
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +20,12 @@ pub struct TakeoffState {
   pub file_id: Option<String>,
   /// Page number for PDF files (null for images)
   pub page_number: Option<u32>,
+  /// Parts of the page drawn at a different scale than `scale`
+  pub scale_regions: Option<Vec<ScaleRegion>>,
+  /// Items of work the measurements are taken off for
+  pub conditions: Option<Vec<Condition>>,
+  /// Sets of items that one measurement drives
+  pub assemblies: Option<Vec<Assembly>>,
 }
 
 // #[napi(object)]
@@ -26,6 +38,26 @@ impl TakeoffState {
       count: 0,
       file_id: None,
       page_number: None,
+      scale_regions: None,
+      conditions: None,
+      assemblies: None,
+    }
+  }
+
+  /// Create an empty state for a page of a file
+  pub fn for_page(key: PageKey) -> Self {
+    Self {
+      file_id: Some(key.file_id),
+      page_number: Some(key.page_number),
+      ..Self::new()
+    }
+  }
+
+  /// Get the page this state belongs to, if it has both a file ID and page number
+  pub fn page_key(&self) -> Option<PageKey> {
+    match (&self.file_id, self.page_number) {
+      (Some(file_id), Some(page_number)) => Some(PageKey::new(file_id.clone(), page_number)),
+      _ => None,
     }
   }
 
@@ -37,21 +69,71 @@ impl TakeoffState {
     }
   }
 
+  /// Insert a measurement at a specific position, clamped to the end of the list
+  pub fn insert_measurement(&mut self, index: usize, measurement: Measurement) {
+    let index = index.min(self.measurements.len());
+    self.measurements.insert(index, measurement);
+    self.recalculate_count();
+  }
+
   pub fn remove_measurement(&mut self, id: &str) -> bool {
     let initial_len = self.measurements.len();
     self.measurements.retain(|m| m.id() != id);
     let removed = self.measurements.len() < initial_len;
     if removed {
-      // Recalculate count
-      self.count = self
-        .measurements
-        .iter()
-        .filter(|m| matches!(m, Measurement::Count { .. }))
-        .count() as u32;
+      self.recalculate_count();
     }
     removed
   }
 
+  /// Replace the measurement with the same ID
+  pub fn update_measurement(&mut self, measurement: Measurement) -> bool {
+    match self.measurement_mut(measurement.id()) {
+      Some(existing) => {
+        *existing = measurement;
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Get a measurement by ID
+  pub fn measurement(&self, id: &str) -> Option<&Measurement> {
+    self.measurements.iter().find(|m| m.id() == id)
+  }
+
+  /// Get a mutable measurement by ID
+  pub fn measurement_mut(&mut self, id: &str) -> Option<&mut Measurement> {
+    self.measurements.iter_mut().find(|m| m.id() == id)
+  }
+
+  /// Get the position of a measurement in the list
+  pub fn measurement_index(&self, id: &str) -> Option<usize> {
+    self.measurements.iter().position(|m| m.id() == id)
+  }
+
+  /// Get the measurements with a tag, ignoring case, bottom to top
+  pub fn measurements_with_tag(&self, tag: &str) -> Vec<&Measurement> {
+    self
+      .measurements
+      .iter()
+      .filter(|m| m.metadata().is_some_and(|metadata| metadata.has_tag(tag)))
+      .collect()
+  }
+
+  /// Get the measurements on a layer, bottom to top
+  pub fn measurements_on_layer(&self, layer_id: &str) -> Vec<&Measurement> {
+    self
+      .measurements
+      .iter()
+      .filter(|m| {
+        m.metadata()
+          .and_then(|metadata| metadata.layer_id.as_deref())
+          == Some(layer_id)
+      })
+      .collect()
+  }
+
   pub fn clear_measurements(&mut self) {
     self.measurements.clear();
     self.count = 0;
@@ -64,6 +146,157 @@ impl TakeoffState {
   pub fn update_transform(&mut self, transform: Transform) {
     self.transform = transform;
   }
+
+  /// Get the scale regions of the page
+  pub fn scale_regions(&self) -> &[ScaleRegion] {
+    self.scale_regions.as_deref().unwrap_or(&[])
+  }
+
+  /// Add a scale region, replacing any region with the same ID
+  pub fn add_scale_region(&mut self, region: ScaleRegion) {
+    let regions = self.scale_regions.get_or_insert_with(Vec::new);
+    match regions.iter_mut().find(|r| r.id == region.id) {
+      Some(existing) => *existing = region,
+      None => regions.push(region),
+    }
+  }
+
+  /// Insert a scale region at a position in the list, or at the end if past it
+  pub fn insert_scale_region(&mut self, index: usize, region: ScaleRegion) {
+    let regions = self.scale_regions.get_or_insert_with(Vec::new);
+    let index = index.min(regions.len());
+    regions.insert(index, region);
+  }
+
+  pub fn remove_scale_region(&mut self, id: &str) -> bool {
+    let Some(regions) = self.scale_regions.as_mut() else {
+      return false;
+    };
+    let initial_len = regions.len();
+    regions.retain(|r| r.id != id);
+    regions.len() < initial_len
+  }
+
+  /// Get the conditions of the page
+  pub fn conditions(&self) -> &[Condition] {
+    self.conditions.as_deref().unwrap_or(&[])
+  }
+
+  pub fn condition(&self, id: &str) -> Option<&Condition> {
+    self.conditions().iter().find(|c| c.id == id)
+  }
+
+  /// Add a condition, replacing any condition with the same ID
+  pub fn add_condition(&mut self, condition: Condition) {
+    let conditions = self.conditions.get_or_insert_with(Vec::new);
+    match conditions.iter_mut().find(|c| c.id == condition.id) {
+      Some(existing) => *existing = condition,
+      None => conditions.push(condition),
+    }
+  }
+
+  /// Insert a condition at a position in the list, or at the end if past it
+  pub fn insert_condition(&mut self, index: usize, condition: Condition) {
+    let conditions = self.conditions.get_or_insert_with(Vec::new);
+    let index = index.min(conditions.len());
+    conditions.insert(index, condition);
+  }
+
+  /// Remove a condition; measurements assigned to it are left out of the totals
+  pub fn remove_condition(&mut self, id: &str) -> bool {
+    let Some(conditions) = self.conditions.as_mut() else {
+      return false;
+    };
+    let initial_len = conditions.len();
+    conditions.retain(|c| c.id != id);
+    conditions.len() < initial_len
+  }
+
+  /// Get the measurements assigned to a condition, bottom to top
+  pub fn measurements_for_condition(&self, condition_id: &str) -> Vec<&Measurement> {
+    self
+      .measurements
+      .iter()
+      .filter(|m| m.condition_id() == Some(condition_id))
+      .collect()
+  }
+
+  /// Total length, area, volume and count of each condition, in condition order
+  pub fn condition_totals(&self) -> Vec<ConditionTotals> {
+    condition_totals(self.conditions(), &self.measurements)
+  }
+
+  /// Price the measurements with rates, by condition and by trade
+  ///
+  /// Returns `None` if any rate used is not a decimal number or an amount overflows.
+  pub fn cost_summary(&self) -> Option<CostSummary> {
+    cost_summary(self.conditions(), &self.measurements)
+  }
+
+  /// Get the assemblies of the page
+  pub fn assemblies(&self) -> &[Assembly] {
+    self.assemblies.as_deref().unwrap_or(&[])
+  }
+
+  pub fn assembly(&self, id: &str) -> Option<&Assembly> {
+    self.assemblies().iter().find(|a| a.id == id)
+  }
+
+  /// Add an assembly, replacing any assembly with the same ID
+  pub fn add_assembly(&mut self, assembly: Assembly) {
+    let assemblies = self.assemblies.get_or_insert_with(Vec::new);
+    match assemblies.iter_mut().find(|a| a.id == assembly.id) {
+      Some(existing) => *existing = assembly,
+      None => assemblies.push(assembly),
+    }
+  }
+
+  /// Insert an assembly at a position in the list, or at the end if past it
+  pub fn insert_assembly(&mut self, index: usize, assembly: Assembly) {
+    let assemblies = self.assemblies.get_or_insert_with(Vec::new);
+    let index = index.min(assemblies.len());
+    assemblies.insert(index, assembly);
+  }
+
+  pub fn remove_assembly(&mut self, id: &str) -> bool {
+    let Some(assemblies) = self.assemblies.as_mut() else {
+      return false;
+    };
+    let initial_len = assemblies.len();
+    assemblies.retain(|a| a.id != id);
+    assemblies.len() < initial_len
+  }
+
+  /// Evaluate the items of the assembly a measurement uses
+  ///
+  /// Returns `None` if it uses no assembly or an unknown one.
+  pub fn derived_items(&self, measurement: &Measurement) -> Option<Vec<DerivedItem>> {
+    let assembly_id = measurement.metadata()?.assembly_id.as_deref()?;
+    Some(self.assembly(assembly_id)?.evaluate(measurement))
+  }
+
+  /// Get the scale that applies at a point: its region's scale, or the page scale
+  pub fn scale_at(&self, point: &Point) -> Option<Scale> {
+    self.resolve_scale(std::slice::from_ref(point)).scale
+  }
+
+  /// Pick the scale for a measurement drawn through `points`
+  pub fn resolve_scale(&self, points: &[Point]) -> ScaleResolution {
+    resolve_scale(self.scale_regions(), self.scale, points)
+  }
+
+  /// Check whether a measurement crosses a scale region boundary
+  pub fn scale_region_warning(&self, measurement: &Measurement) -> Option<ScaleRegionWarning> {
+    self.resolve_scale(&measurement.vertices()).warning
+  }
+
+  fn recalculate_count(&mut self) {
+    self.count = self
+      .measurements
+      .iter()
+      .filter(|m| matches!(m, Measurement::Count { .. }))
+      .count() as u32;
+  }
 }
 
 impl Default for TakeoffState {
@@ -1,8 +1,9 @@
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 use uom::si::area::{square_centimeter, square_foot, square_inch, square_meter, square_yard};
-use uom::si::f64::{Area, Length};
+use uom::si::f64::{Area, Length, Volume};
 use uom::si::length::{centimeter, foot, inch, meter, yard};
+use uom::si::volume::{cubic_foot, cubic_meter, cubic_yard};
 /// Measurement units supported by the system
 #[napi(string_enum)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -82,6 +83,79 @@ impl Unit {
       Unit::Centimeters => "Centimeters",
     }
   }
+
+  /// Volume unit usually quoted alongside this unit (CY for imperial, m³ for metric)
+  pub fn volume_unit(&self) -> VolumeUnit {
+    match self {
+      Unit::Yards | Unit::Feet | Unit::Inches => VolumeUnit::CubicYards,
+      Unit::Meters | Unit::Centimeters => VolumeUnit::CubicMeters,
+    }
+  }
+}
+
+/// Volume units supported by the system
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VolumeUnit {
+  CubicYards,
+  CubicFeet,
+  CubicMeters,
+}
+
+impl VolumeUnit {
+  pub fn get_unit(&self, value: f64) -> Volume {
+    match self {
+      VolumeUnit::CubicYards => Volume::new::<cubic_yard>(value),
+      VolumeUnit::CubicFeet => Volume::new::<cubic_foot>(value),
+      VolumeUnit::CubicMeters => Volume::new::<cubic_meter>(value),
+    }
+  }
+
+  /// Read a `uom` volume in this unit
+  pub fn get(&self, volume: Volume) -> f64 {
+    match self {
+      VolumeUnit::CubicYards => volume.get::<cubic_yard>(),
+      VolumeUnit::CubicFeet => volume.get::<cubic_foot>(),
+      VolumeUnit::CubicMeters => volume.get::<cubic_meter>(),
+    }
+  }
+
+  /// Convert a value from one volume unit to another
+  pub fn convert(&self, value: f64, to: &VolumeUnit) -> f64 {
+    to.get(self.get_unit(value))
+  }
+
+  /// Get the display string for this unit
+  pub fn display(&self) -> &'static str {
+    match self {
+      VolumeUnit::CubicYards => "CY",
+      VolumeUnit::CubicFeet => "CF",
+      VolumeUnit::CubicMeters => "m³",
+    }
+  }
+}
+
+/// A length with its unit, such as a slab thickness or footing width
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Dimension {
+  pub value: f64,
+  pub unit: Unit,
+}
+
+impl Dimension {
+  pub fn new(value: f64, unit: Unit) -> Self {
+    Self { value, unit }
+  }
+
+  pub fn to_length(&self) -> Length {
+    self.unit.get_unit(self.value)
+  }
+
+  /// Get the value in another unit
+  pub fn convert(&self, to: Unit) -> f64 {
+    self.unit.convert(self.value, &to)
+  }
 }
 
 /// Unit conversion utilities
@@ -116,4 +190,345 @@ impl UnitUtils {
   pub fn metric_units() -> Vec<Unit> {
     vec![Unit::Meters, Unit::Centimeters]
   }
+
+  pub fn convert_volume(value: f64, from: VolumeUnit, to: VolumeUnit) -> f64 {
+    from.convert(value, &to)
+  }
+
+  /// Get all available volume units
+  pub fn volume_units() -> Vec<VolumeUnit> {
+    vec![
+      VolumeUnit::CubicYards,
+      VolumeUnit::CubicFeet,
+      VolumeUnit::CubicMeters,
+    ]
+  }
+
+  /// Volume of an area in square `area_unit` at a given depth
+  pub fn area_to_volume(area: f64, area_unit: Unit, depth: Dimension, to: VolumeUnit) -> f64 {
+    to.get(area_unit.get_area_unit(area) * depth.to_length())
+  }
+
+  /// Volume of a length in `length_unit` with a given width and depth
+  pub fn length_to_volume(
+    length: f64,
+    length_unit: Unit,
+    width: Dimension,
+    depth: Dimension,
+    to: VolumeUnit,
+  ) -> f64 {
+    to.get(length_unit.get_unit(length) * width.to_length() * depth.to_length())
+  }
+}
+
+/// How lengths are written out
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LengthStyle {
+  /// Decimal value in the display unit, e.g. `12.50 ft` or `3.81 m`
+  Decimal,
+  /// Feet and whole inches, e.g. `12'-6"`, or just inches under a foot, e.g. `8"`
+  FeetInches,
+  /// Feet, inches and a fraction of an inch, e.g. `12'-6 1/2"`, or just inches
+  /// under a foot, e.g. `3/64"`
+  FeetInchesFraction,
+}
+
+/// How area units are written out
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AreaSuffix {
+  /// `ft²`, `m²`
+  Superscript,
+  /// `sq ft`, `sq m`
+  SquareAbbreviation,
+  /// Estimating shorthand: `SF` for square feet and `SY` for square yards
+  Estimator,
+}
+
+/// Options for formatting lengths and areas for display
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayFormat {
+  pub length_style: LengthStyle,
+  /// Decimal places for decimal lengths and areas (default 2)
+  pub precision: Option<u32>,
+  /// Smallest fraction of an inch for `FeetInchesFraction`, from 2 to 64 (default 16)
+  pub fraction_denominator: Option<u32>,
+  /// Suffix style for areas (default `Superscript`)
+  pub area_suffix: Option<AreaSuffix>,
+}
+
+impl Default for DisplayFormat {
+  fn default() -> Self {
+    Self {
+      length_style: LengthStyle::Decimal,
+      precision: None,
+      fraction_denominator: None,
+      area_suffix: None,
+    }
+  }
+}
+
+impl DisplayFormat {
+  pub fn decimal(precision: u32) -> Self {
+    Self {
+      precision: Some(precision),
+      ..Self::default()
+    }
+  }
+
+  pub fn feet_inches() -> Self {
+    Self {
+      length_style: LengthStyle::FeetInches,
+      ..Self::default()
+    }
+  }
+
+  /// Feet and inches rounded to `1/denominator` of an inch
+  pub fn feet_inches_fraction(denominator: u32) -> Self {
+    Self {
+      length_style: LengthStyle::FeetInchesFraction,
+      fraction_denominator: Some(denominator),
+      ..Self::default()
+    }
+  }
+
+  fn precision(&self) -> usize {
+    self.precision.unwrap_or(2) as usize
+  }
+
+  /// Fraction denominator clamped to a power of two between 2 and 64
+  fn denominator(&self) -> u32 {
+    self
+      .fraction_denominator
+      .unwrap_or(16)
+      .clamp(2, 64)
+      .next_power_of_two()
+      .min(64)
+  }
+
+  /// Format a length given in `unit`
+  pub fn format_length(&self, value: f64, unit: Unit) -> String {
+    match self.length_style {
+      LengthStyle::Decimal => {
+        format!("{:.*} {}", self.precision(), value, unit.display())
+      }
+      LengthStyle::FeetInches => format_feet_inches(unit.convert(value, &Unit::Inches), 1),
+      LengthStyle::FeetInchesFraction => {
+        format_feet_inches(unit.convert(value, &Unit::Inches), self.denominator())
+      }
+    }
+  }
+
+  /// Format an area given in square `unit`
+  pub fn format_area(&self, value: f64, unit: Unit) -> String {
+    let suffix = match (self.area_suffix.unwrap_or(AreaSuffix::Superscript), unit) {
+      (AreaSuffix::Estimator, Unit::Feet) => "SF".to_string(),
+      (AreaSuffix::Estimator, Unit::Yards) => "SY".to_string(),
+      (AreaSuffix::Superscript, unit) => format!("{}²", unit.display()),
+      (_, unit) => format!("sq {}", unit.display()),
+    };
+    format!("{:.*} {}", self.precision(), value, suffix)
+  }
+
+  /// Format a volume given in `unit`
+  pub fn format_volume(&self, value: f64, unit: VolumeUnit) -> String {
+    format!("{:.*} {}", self.precision(), value, unit.display())
+  }
+}
+
+/// Format inches as feet and inches rounded to `1/denominator` of an inch
+///
+/// Lengths that round to less than a foot are written in inches only.
+fn format_feet_inches(inches: f64, denominator: u32) -> String {
+  // Round once up front so 11 63/64" rounding up carries into the feet
+  let steps = (inches.abs() * denominator as f64).round();
+  let sign = if inches < 0.0 && steps > 0.0 { "-" } else { "" };
+  let steps_per_foot = 12.0 * denominator as f64;
+  let feet = (steps / steps_per_foot).floor();
+  let inches = format_mixed_fraction(
+    (steps - feet * steps_per_foot) / denominator as f64,
+    denominator,
+  );
+  if feet == 0.0 {
+    format!("{sign}{inches}\"")
+  } else {
+    format!("{sign}{feet}'-{inches}\"")
+  }
+}
+
+/// Parse a number written as a decimal, a fraction or a mixed fraction
+/// (`0.25`, `3/16`, `1 1/2` or `1-1/2`)
+pub(crate) fn parse_mixed_number(text: &str) -> Option<f64> {
+  let text = text.trim();
+  if text.is_empty() {
+    return None;
+  }
+  let parts: Vec<&str> = text
+    .split(|c: char| c.is_whitespace() || c == '-')
+    .filter(|part| !part.is_empty())
+    .collect();
+  match parts.as_slice() {
+    [single] => parse_fraction(single),
+    [whole, fraction] if fraction.contains('/') => {
+      Some(whole.parse::<f64>().ok()? + parse_fraction(fraction)?)
+    }
+    _ => None,
+  }
+}
+
+fn parse_fraction(text: &str) -> Option<f64> {
+  match text.split_once('/') {
+    Some((numerator, denominator)) => {
+      let denominator: f64 = denominator.trim().parse().ok()?;
+      if denominator == 0.0 {
+        return None;
+      }
+      Some(numerator.trim().parse::<f64>().ok()? / denominator)
+    }
+    None => text.parse().ok(),
+  }
+}
+
+/// Format a non-negative value as a mixed fraction rounded to `1/max_denominator`
+///
+/// Fractions are reduced, so 0.5 with a denominator of 16 prints as `1/2`.
+pub(crate) fn format_mixed_fraction(value: f64, max_denominator: u32) -> String {
+  let max_denominator = max_denominator.max(1) as u64;
+  let total = (value * max_denominator as f64).round() as u64;
+  let whole = total / max_denominator;
+  let remainder = total % max_denominator;
+  let divisor = gcd(remainder, max_denominator);
+  let (numerator, denominator) = (remainder / divisor, max_denominator / divisor);
+  match (whole, numerator) {
+    (whole, 0) => whole.to_string(),
+    (0, numerator) => format!("{numerator}/{denominator}"),
+    (whole, numerator) => format!("{whole} {numerator}/{denominator}"),
+  }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+  if b == 0 {
+    a
+  } else {
+    gcd(b, a % b)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_format_decimal() {
+    let format = DisplayFormat::default();
+    assert_eq!(format.format_length(12.5, Unit::Feet), "12.50 ft");
+    assert_eq!(
+      DisplayFormat::decimal(3).format_length(3.81, Unit::Meters),
+      "3.810 m"
+    );
+    assert_eq!(
+      DisplayFormat::decimal(0).format_length(3.81, Unit::Meters),
+      "4 m"
+    );
+  }
+
+  #[test]
+  fn test_format_feet_inches() {
+    let format = DisplayFormat::feet_inches();
+    assert_eq!(format.format_length(12.5, Unit::Feet), "12'-6\"");
+    assert_eq!(format.format_length(11.99, Unit::Feet), "12'-0\"");
+    assert_eq!(format.format_length(-1.5, Unit::Feet), "-1'-6\"");
+    // Under a foot is inches only
+    assert_eq!(format.format_length(8.0, Unit::Inches), "8\"");
+    assert_eq!(format.format_length(-0.5, Unit::Feet), "-6\"");
+    assert_eq!(format.format_length(0.0, Unit::Feet), "0\"");
+    // Metric values are converted
+    assert_eq!(format.format_length(1.0, Unit::Meters), "3'-3\"");
+  }
+
+  #[test]
+  fn test_format_feet_inches_fraction() {
+    let value = 12.0 + 6.5 / 12.0;
+    assert_eq!(
+      DisplayFormat::feet_inches_fraction(16).format_length(value, Unit::Feet),
+      "12'-6 1/2\""
+    );
+    assert_eq!(
+      DisplayFormat::feet_inches_fraction(64).format_length(3.0 / 64.0, Unit::Inches),
+      "3/64\""
+    );
+    // 1/3" rounds to the nearest eighth
+    assert_eq!(
+      DisplayFormat::feet_inches_fraction(8).format_length(1.0 / 3.0, Unit::Inches),
+      "3/8\""
+    );
+    // Rounding up carries into the next foot
+    assert_eq!(
+      DisplayFormat::feet_inches_fraction(2).format_length(11.9 / 12.0, Unit::Feet),
+      "1'-0\""
+    );
+    // Unsupported denominators are clamped to a power of two
+    assert_eq!(DisplayFormat::feet_inches_fraction(100).denominator(), 64);
+    assert_eq!(DisplayFormat::feet_inches_fraction(10).denominator(), 16);
+    assert_eq!(DisplayFormat::feet_inches_fraction(1).denominator(), 2);
+  }
+
+  #[test]
+  fn test_format_area() {
+    let mut format = DisplayFormat::default();
+    assert_eq!(format.format_area(120.0, Unit::Feet), "120.00 ft²");
+    format.area_suffix = Some(AreaSuffix::SquareAbbreviation);
+    assert_eq!(format.format_area(120.0, Unit::Feet), "120.00 sq ft");
+    format.area_suffix = Some(AreaSuffix::Estimator);
+    format.precision = Some(0);
+    assert_eq!(format.format_area(120.0, Unit::Feet), "120 SF");
+    assert_eq!(format.format_area(13.3, Unit::Yards), "13 SY");
+    assert_eq!(format.format_area(2.0, Unit::Meters), "2 sq m");
+  }
+
+  #[test]
+  fn test_volume_conversion() {
+    // uom defines cubic feet and yards to seven significant figures
+    let cf = VolumeUnit::CubicYards.convert(1.0, &VolumeUnit::CubicFeet);
+    assert!((cf - 27.0).abs() < 1e-5);
+    assert!(
+      (UnitUtils::convert_volume(1.0, VolumeUnit::CubicMeters, VolumeUnit::CubicFeet) - 35.3147)
+        .abs()
+        < 1e-4
+    );
+    // 100 SF slab, 4" thick is 33.33 CF
+    let slab = UnitUtils::area_to_volume(
+      100.0,
+      Unit::Feet,
+      Dimension::new(4.0, Unit::Inches),
+      VolumeUnit::CubicFeet,
+    );
+    assert!((slab - 100.0 / 3.0).abs() < 1e-4);
+    // 27 ft of 1' x 1' footing is one cubic yard
+    let footing = UnitUtils::length_to_volume(
+      27.0,
+      Unit::Feet,
+      Dimension::new(12.0, Unit::Inches),
+      Dimension::new(1.0, Unit::Feet),
+      VolumeUnit::CubicYards,
+    );
+    assert!((footing - 1.0).abs() < 1e-5);
+    assert_eq!(
+      DisplayFormat::decimal(1).format_volume(footing, VolumeUnit::CubicYards),
+      "1.0 CY"
+    );
+  }
+
+  #[test]
+  fn test_parse_and_format_fractions() {
+    assert_eq!(parse_mixed_number("1 1/2"), Some(1.5));
+    assert_eq!(parse_mixed_number("1-1/2"), Some(1.5));
+    assert_eq!(parse_mixed_number("3/16"), Some(0.1875));
+    assert_eq!(parse_mixed_number("1/0"), None);
+    assert_eq!(format_mixed_fraction(1.5, 16), "1 1/2");
+    assert_eq!(format_mixed_fraction(0.25, 16), "1/4");
+    assert_eq!(format_mixed_fraction(3.0, 16), "3");
+  }
 }
@@ -0,0 +1,577 @@
+use crate::measurement::generate_id;
+use crate::{ArcSegment, Measurement, Point, Polygon};
+use geo::{
+  Area, BooleanOps, Contains, Coord, Intersects, LineString, MultiPolygon, OpType,
+  Polygon as GeoPolygon,
+};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+
+/// Relative tolerance for repeated points, spikes and zero-area rings
+const TOLERANCE: f64 = 1e-9;
+
+/// What is wrong with a polygon
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationIssueKind {
+  /// Two edges of a ring that are not neighbours cross or touch
+  SelfIntersection,
+  /// A point is the same as the one before it
+  RepeatedPoint,
+  /// The edges on either side of a point double back over each other
+  Spike,
+  /// A ring has fewer than 3 distinct points
+  TooFewPoints,
+  /// A ring encloses no area
+  ZeroArea,
+  /// A hole is not entirely inside the outer ring
+  HoleOutside,
+  /// A hole overlaps or lies inside another hole
+  HoleOverlap,
+}
+
+/// One problem found in a polygon and where it is
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationIssue {
+  pub kind: ValidationIssueKind,
+  /// Where the problem is: the crossing, the vertex, or the ring's first point
+  pub point: Point,
+  /// Ring the problem is on: 0 for the outer ring, then 1 for the first hole and so on
+  pub ring: u32,
+  /// Vertex of a repeated point or spike, numbered as in `Polygon::vertices`
+  pub vertex_index: Option<u32>,
+  /// Crossing edge, numbered as in `Polygon::insert_point`: the first of the two
+  /// on one ring, or the hole's edge where a hole crosses another ring
+  pub edge_index: Option<u32>,
+}
+
+impl ValidationIssue {
+  fn new(kind: ValidationIssueKind, point: Point, ring: usize) -> Self {
+    Self {
+      kind,
+      point,
+      ring: ring as u32,
+      vertex_index: None,
+      edge_index: None,
+    }
+  }
+}
+
+/// A ring's edges with repeated points dropped, keeping their original indices
+struct Ring {
+  index: usize,
+  /// Index of the ring's first vertex in `Polygon::vertices`
+  offset: usize,
+  /// Vertex index within the ring and the segment starting there
+  segments: Vec<(usize, ArcSegment)>,
+  /// Whether the signed area is zero, which a ring that crosses itself can also have
+  flat: bool,
+}
+
+/// Find self-intersections, repeated points, spikes, degenerate rings and
+/// misplaced holes
+///
+/// An empty list means the polygon is valid. Crossings on arcs are found on the
+/// flattened arc, so their locations are approximate.
+pub fn validate_polygon(polygon: &Polygon) -> Vec<ValidationIssue> {
+  let mut issues = Vec::new();
+  let mut rings = Vec::new();
+  let mut offset = 0;
+  let outer = polygon.segments();
+  let holes = polygon.holes().iter().map(|hole| {
+    let n = hole.len();
+    (0..n)
+      .map(|i| ArcSegment::new(hole[i], hole[(i + 1) % n], 0.0))
+      .collect::<Vec<_>>()
+  });
+  for (ring_index, segments) in std::iter::once(outer).chain(holes).enumerate() {
+    let count = segments.len();
+    let ring = check_ring(segments, ring_index, offset, &mut issues);
+    offset += count;
+    rings.extend(ring);
+  }
+  find_crossings(&rings, &mut issues);
+  for ring in rings.iter().filter(|ring| ring.flat) {
+    let crossed = issues.iter().any(|issue| {
+      issue.kind == ValidationIssueKind::SelfIntersection && issue.ring == ring.index as u32
+    });
+    if !crossed {
+      issues.push(ValidationIssue::new(
+        ValidationIssueKind::ZeroArea,
+        ring.segments[0].1.start,
+        ring.index,
+      ));
+    }
+  }
+  check_hole_placement(polygon, &mut issues);
+  issues
+}
+
+/// Check if a polygon has none of the problems `validate_polygon` looks for
+pub fn is_valid_polygon(polygon: &Polygon) -> bool {
+  validate_polygon(polygon).is_empty()
+}
+
+/// Split a polygon into valid parts
+///
+/// Where edges cross, the shape is cut at the crossings and the pieces that are
+/// inside by the even-odd rule are kept, so a bow-tie becomes two triangles.
+/// The holes are cut out of the result together, so a hole outside the outer
+/// ring is dropped and a hole inside another merges into it. Repeated points,
+/// spikes and zero-area pieces are dropped. A valid polygon is returned
+/// unchanged; otherwise arcs are flattened.
+pub fn repair_polygon(polygon: &Polygon) -> Vec<Polygon> {
+  if is_valid_polygon(polygon) {
+    return vec![polygon.clone()];
+  }
+  let empty = MultiPolygon::<f64>::new(Vec::new());
+  let outer = ring_polygon(&polygon.flattened_points()).boolean_op(&empty, OpType::Union);
+  let holes = polygon.holes().iter().fold(empty.clone(), |holes, hole| {
+    let hole = ring_polygon(hole).boolean_op(&empty, OpType::Union);
+    holes.boolean_op(&hole, OpType::Union)
+  });
+  let tolerance = TOLERANCE * polygon.perimeter().powi(2);
+  outer
+    .boolean_op(&holes, OpType::Difference)
+    .iter()
+    .filter(|piece| piece.unsigned_area() > tolerance)
+    .map(Polygon::from_geo)
+    .collect()
+}
+
+/// Problems with the shape of an area measurement, or `None` for other kinds
+pub fn validate_area(measurement: &Measurement) -> Option<Vec<ValidationIssue>> {
+  measurement
+    .area_polygon()
+    .map(|polygon| validate_polygon(&polygon))
+}
+
+/// Split an area measurement into valid new ones, one per part
+///
+/// The parts take the scale, display unit, depth, pitch and metadata of the
+/// source. An empty result means nothing valid is left. Returns `None` for other kinds.
+pub fn repair_area(measurement: &Measurement) -> Option<Vec<Measurement>> {
+  let Measurement::Area {
+    scale,
+    display_unit,
+    depth,
+    pitch,
+    metadata,
+    ..
+  } = measurement
+  else {
+    return None;
+  };
+  let Some(polygon) = measurement.area_polygon() else {
+    return Some(Vec::new());
+  };
+  Some(
+    repair_polygon(&polygon)
+      .into_iter()
+      .map(|piece| Measurement::Area {
+        id: generate_id("area"),
+        rectangle: None,
+        polygon: Some(piece),
+        circle: None,
+        ellipse: None,
+        scale: *scale,
+        display_unit: *display_unit,
+        depth: *depth,
+        pitch: *pitch,
+        metadata: metadata.clone(),
+      })
+      .collect(),
+  )
+}
+
+/// Report repeated points, spikes and too few points for one ring
+///
+/// Returns the ring with repeated points dropped, or `None` if too few are left
+/// to check for crossings.
+fn check_ring(
+  segments: Vec<ArcSegment>,
+  ring: usize,
+  offset: usize,
+  issues: &mut Vec<ValidationIssue>,
+) -> Option<Ring> {
+  let first = segments.first().map_or(Point::new(0.0, 0.0), |s| s.start);
+  let perimeter: f64 = segments.iter().map(|s| s.length()).sum();
+  let n = segments.len();
+  let mut kept: Vec<(usize, ArcSegment)> = Vec::new();
+  for (index, segment) in segments.into_iter().enumerate() {
+    if segment.chord() <= TOLERANCE * perimeter.max(1.0) {
+      // The point ending this segment repeats its start
+      issues.push(ValidationIssue {
+        vertex_index: Some((offset + (index + 1) % n) as u32),
+        ..ValidationIssue::new(ValidationIssueKind::RepeatedPoint, segment.end, ring)
+      });
+      continue;
+    }
+    kept.push((index, segment));
+  }
+
+  if kept.len() < 3 {
+    issues.push(ValidationIssue::new(
+      ValidationIssueKind::TooFewPoints,
+      first,
+      ring,
+    ));
+    return None;
+  }
+
+  for i in 0..kept.len() {
+    let (_, before) = kept[(i + kept.len() - 1) % kept.len()];
+    let (index, after) = kept[i];
+    if before.is_straight() && after.is_straight() && is_spike(before.start, after.start, after.end)
+    {
+      issues.push(ValidationIssue {
+        vertex_index: Some((offset + index) as u32),
+        ..ValidationIssue::new(ValidationIssueKind::Spike, after.start, ring)
+      });
+    }
+  }
+
+  let area: f64 = kept
+    .iter()
+    .map(|(_, s)| (s.start.x * s.end.y - s.end.x * s.start.y) / 2.0 + s.segment_area())
+    .sum();
+  Some(Ring {
+    index: ring,
+    offset,
+    flat: area.abs() <= TOLERANCE * perimeter * perimeter,
+    segments: kept,
+  })
+}
+
+/// Report holes wholly outside the outer ring or inside another hole
+///
+/// Rings whose edges meet are skipped, as `find_crossings` reports them.
+fn check_hole_placement(polygon: &Polygon, issues: &mut Vec<ValidationIssue>) {
+  let mut rings = vec![polygon.flattened_points()];
+  rings.extend(polygon.holes().iter().cloned());
+  let shapes: Vec<GeoPolygon<f64>> = rings.iter().map(|ring| ring_polygon(ring)).collect();
+  let apart = |a: usize, b: usize| {
+    rings[a].len() >= 3
+      && rings[b].len() >= 3
+      && !shapes[a].exterior().intersects(shapes[b].exterior())
+  };
+  for hole in 1..rings.len() {
+    if apart(0, hole) && !shapes[0].contains(&shapes[hole]) {
+      issues.push(ValidationIssue::new(
+        ValidationIssueKind::HoleOutside,
+        rings[hole][0],
+        hole,
+      ));
+    }
+    for other in 1..rings.len() {
+      if other != hole && apart(hole, other) && shapes[other].contains(&shapes[hole]) {
+        issues.push(ValidationIssue::new(
+          ValidationIssueKind::HoleOverlap,
+          rings[hole][0],
+          hole,
+        ));
+      }
+    }
+  }
+}
+
+fn ring_polygon(points: &[Point]) -> GeoPolygon<f64> {
+  let points: Vec<Coord<f64>> = points.iter().map(|p| (*p).into()).collect();
+  GeoPolygon::new(LineString::from(points), vec![])
+}
+
+/// Check if the path `before` → `vertex` → `after` turns straight back on itself
+fn is_spike(before: Point, vertex: Point, after: Point) -> bool {
+  let (ax, ay) = (before.x - vertex.x, before.y - vertex.y);
+  let (bx, by) = (after.x - vertex.x, after.y - vertex.y);
+  let lengths = ax.hypot(ay) * bx.hypot(by);
+  (ax * by - ay * bx).abs() <= TOLERANCE * lengths && ax * bx + ay * by > 0.0
+}
+
+/// Report every pair of edges that cross, other than neighbours on the same ring
+///
+/// A hole crossing the outer ring pokes out of it, and a hole crossing another
+/// hole overlaps it.
+fn find_crossings(rings: &[Ring], issues: &mut Vec<ValidationIssue>) {
+  for (a, ring_a) in rings.iter().enumerate() {
+    for (b, ring_b) in rings.iter().enumerate().skip(a) {
+      for (i, (index_a, segment_a)) in ring_a.segments.iter().enumerate() {
+        for (j, (index_b, segment_b)) in ring_b.segments.iter().enumerate() {
+          if a == b {
+            let n = ring_a.segments.len();
+            if j <= i + 1 || (i == 0 && j == n - 1) {
+              continue;
+            }
+          }
+          let Some(point) = segment_a.intersections(segment_b).into_iter().next() else {
+            continue;
+          };
+          let issue = if a == b {
+            ValidationIssue {
+              edge_index: Some((ring_a.offset + index_a) as u32),
+              ..ValidationIssue::new(ValidationIssueKind::SelfIntersection, point, ring_a.index)
+            }
+          } else {
+            let kind = if ring_a.index == 0 {
+              ValidationIssueKind::HoleOutside
+            } else {
+              ValidationIssueKind::HoleOverlap
+            };
+            ValidationIssue {
+              edge_index: Some((ring_b.offset + index_b) as u32),
+              ..ValidationIssue::new(kind, point, ring_b.index)
+            }
+          };
+          issues.push(issue);
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Rectangle, Scale, Unit};
+
+  fn kinds(polygon: &Polygon) -> Vec<ValidationIssueKind> {
+    validate_polygon(polygon)
+      .into_iter()
+      .map(|issue| issue.kind)
+      .collect()
+  }
+
+  fn square() -> Vec<Point> {
+    vec![
+      Point::new(0.0, 0.0),
+      Point::new(10.0, 0.0),
+      Point::new(10.0, 10.0),
+      Point::new(0.0, 10.0),
+    ]
+  }
+
+  fn bow_tie() -> Polygon {
+    Polygon::new(vec![
+      Point::new(0.0, 0.0),
+      Point::new(10.0, 10.0),
+      Point::new(10.0, 0.0),
+      Point::new(0.0, 10.0),
+    ])
+  }
+
+  /// A square with a spike going up its right edge and straight back down
+  fn spiked() -> Polygon {
+    let mut points = square();
+    points.insert(2, Point::new(10.0, 15.0));
+    Polygon::new(points)
+  }
+
+  #[test]
+  fn test_valid_polygons() {
+    assert!(is_valid_polygon(&Polygon::new(square())));
+    let hole = vec![
+      Point::new(2.0, 2.0),
+      Point::new(4.0, 2.0),
+      Point::new(4.0, 4.0),
+    ];
+    assert!(is_valid_polygon(&Polygon::with_holes(square(), vec![hole])));
+    let mut arched = Polygon::new(square());
+    arched.set_bulge(0, -0.5);
+    assert!(is_valid_polygon(&arched));
+  }
+
+  #[test]
+  fn test_self_intersection() {
+    let issues = validate_polygon(&bow_tie());
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, ValidationIssueKind::SelfIntersection);
+    assert_eq!(issues[0].point, Point::new(5.0, 5.0));
+    assert_eq!(issues[0].ring, 0);
+    assert_eq!(issues[0].edge_index, Some(0));
+
+    // A hole poking out of the outer ring crosses it twice
+    let hole = vec![
+      Point::new(5.0, 5.0),
+      Point::new(15.0, 5.0),
+      Point::new(5.0, 8.0),
+    ];
+    let poking = Polygon {
+      holes: Some(vec![hole]),
+      ..Polygon::new(square())
+    };
+    let issues = validate_polygon(&poking);
+    assert_eq!(issues.len(), 2);
+    assert_eq!(issues[0].kind, ValidationIssueKind::HoleOutside);
+    assert_eq!(issues[0].point, Point::new(10.0, 5.0));
+    assert_eq!(issues[0].ring, 1);
+    assert_eq!(issues[0].edge_index, Some(4));
+    let parts = repair_polygon(&poking);
+    assert_eq!(parts.len(), 1);
+    assert!(is_valid_polygon(&parts[0]));
+  }
+
+  #[test]
+  fn test_misplaced_holes() {
+    let triangle = |x: f64, y: f64, size: f64| {
+      vec![
+        Point::new(x, y),
+        Point::new(x + size, y),
+        Point::new(x, y + size),
+      ]
+    };
+    let outside = Polygon {
+      holes: Some(vec![triangle(20.0, 20.0, 2.0)]),
+      ..Polygon::new(square())
+    };
+    let issues = validate_polygon(&outside);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, ValidationIssueKind::HoleOutside);
+    assert_eq!(issues[0].point, Point::new(20.0, 20.0));
+    assert_eq!(issues[0].ring, 1);
+    let parts = repair_polygon(&outside);
+    assert_eq!(parts.len(), 1);
+    assert!(parts[0].holes().is_empty());
+    assert!((parts[0].area() - 100.0).abs() < 1e-9);
+
+    let nested = Polygon {
+      holes: Some(vec![triangle(1.0, 1.0, 6.0), triangle(2.0, 2.0, 2.0)]),
+      ..Polygon::new(square())
+    };
+    let issues = validate_polygon(&nested);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, ValidationIssueKind::HoleOverlap);
+    assert_eq!(issues[0].point, Point::new(2.0, 2.0));
+    assert_eq!(issues[0].ring, 2);
+    let parts = repair_polygon(&nested);
+    assert_eq!(parts.len(), 1);
+    assert!(is_valid_polygon(&parts[0]));
+    assert!((parts[0].area() - 82.0).abs() < 1e-9);
+
+    // Two holes crossing each other merge into one
+    let crossing = Polygon {
+      holes: Some(vec![triangle(1.0, 1.0, 4.0), triangle(2.0, 2.0, 4.0)]),
+      ..Polygon::new(square())
+    };
+    assert!(!is_valid_polygon(&crossing));
+    assert!(kinds(&crossing)
+      .iter()
+      .all(|kind| *kind == ValidationIssueKind::HoleOverlap));
+    let parts = repair_polygon(&crossing);
+    assert_eq!(parts.len(), 1);
+    assert_eq!(parts[0].holes().len(), 1);
+    assert!(is_valid_polygon(&parts[0]));
+  }
+
+  #[test]
+  fn test_repeated_points_and_spikes() {
+    let mut points = square();
+    points.insert(2, Point::new(10.0, 0.0));
+    let issues = validate_polygon(&Polygon::new(points));
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].kind, ValidationIssueKind::RepeatedPoint);
+    assert_eq!(issues[0].vertex_index, Some(2));
+
+    // The closing point repeating the first
+    let mut points = square();
+    points.push(Point::new(0.0, 0.0));
+    assert_eq!(
+      kinds(&Polygon::new(points)),
+      vec![ValidationIssueKind::RepeatedPoint]
+    );
+
+    let issues = validate_polygon(&spiked());
+    let spike = issues
+      .iter()
+      .find(|issue| issue.kind == ValidationIssueKind::Spike)
+      .unwrap();
+    assert_eq!(spike.vertex_index, Some(2));
+    assert_eq!(spike.point, Point::new(10.0, 15.0));
+  }
+
+  #[test]
+  fn test_degenerate_rings() {
+    let flat = Polygon::new(vec![
+      Point::new(0.0, 0.0),
+      Point::new(5.0, 0.0),
+      Point::new(10.0, 0.0),
+    ]);
+    assert!(kinds(&flat).contains(&ValidationIssueKind::ZeroArea));
+
+    let two = Polygon::new(vec![
+      Point::new(0.0, 0.0),
+      Point::new(5.0, 0.0),
+      Point::new(5.0, 0.0),
+    ]);
+    assert_eq!(
+      kinds(&two),
+      vec![
+        ValidationIssueKind::RepeatedPoint,
+        ValidationIssueKind::TooFewPoints
+      ]
+    );
+  }
+
+  #[test]
+  fn test_repair() {
+    // Both lobes count, rather than cancelling out
+    assert!((bow_tie().area() - 50.0).abs() < 1e-9);
+    let parts = repair_polygon(&bow_tie());
+    assert_eq!(parts.len(), 2);
+    for part in &parts {
+      assert!(is_valid_polygon(part));
+      assert!((part.area() - 25.0).abs() < 1e-9);
+    }
+
+    let parts = repair_polygon(&spiked());
+    assert_eq!(parts.len(), 1);
+    assert!(is_valid_polygon(&parts[0]));
+    assert!((parts[0].area() - 100.0).abs() < 1e-9);
+
+    let valid = Polygon::new(square());
+    assert_eq!(repair_polygon(&valid), vec![valid]);
+  }
+
+  #[test]
+  fn test_areas() {
+    let area = Measurement::Area {
+      id: "room".to_string(),
+      rectangle: None,
+      polygon: Some(bow_tie()),
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert_eq!(validate_area(&area).map(|issues| issues.len()), Some(1));
+    let parts = repair_area(&area).unwrap();
+    assert_eq!(parts.len(), 2);
+    assert!(parts.iter().all(|part| part.id() != "room"));
+
+    let flat = Measurement::Area {
+      id: "flat".to_string(),
+      rectangle: Some(Rectangle::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0))),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale: Scale::new(10.0, 1.0, Unit::Feet),
+      display_unit: Unit::Feet,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    };
+    assert!(!validate_area(&flat).unwrap().is_empty());
+    assert!(repair_area(&flat).unwrap().is_empty());
+
+    let count = Measurement::Count {
+      id: "c".to_string(),
+      point: Point::new(0.0, 0.0),
+      metadata: None,
+    };
+    assert!(validate_area(&count).is_none());
+    assert!(repair_area(&count).is_none());
+  }
+}
@@ -0,0 +1,111 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use takeoff_core::{
+  Line, Measurement, Point, Polyline, Scale, Snapper, SpatialIndex, Transform, Unit,
+};
+use takeoff_tools::SelectionTool;
+
+/// A page with a grid of count markers and short polyline runs between them
+fn takeoff(size: usize) -> Vec<Measurement> {
+  let mut measurements = Vec::new();
+  for row in 0..size {
+    for column in 0..size {
+      let (x, y) = (column as f64 * 50.0, row as f64 * 50.0);
+      measurements.push(Measurement::Count {
+        id: format!("count-{row}-{column}"),
+        point: Point::new(x, y),
+        metadata: None,
+      });
+      measurements.push(Measurement::Linear {
+        id: format!("run-{row}-{column}"),
+        line: None,
+        polyline: Some(Polyline::new(vec![
+          Point::new(x + 5.0, y + 5.0),
+          Point::new(x + 25.0, y + 10.0),
+          Point::new(x + 40.0, y + 30.0),
+        ])),
+        scale: Scale::new(1.0, 1.0, Unit::Feet),
+        display_unit: Unit::Feet,
+        width: None,
+        depth: None,
+        pitch: None,
+        metadata: None,
+      });
+    }
+  }
+  measurements.push(Measurement::Linear {
+    id: "diagonal".to_string(),
+    line: Some(Line::new(
+      Point::new(0.0, 0.0),
+      Point::new(size as f64 * 50.0, size as f64 * 50.0),
+    )),
+    polyline: None,
+    scale: Scale::new(1.0, 1.0, Unit::Feet),
+    display_unit: Unit::Feet,
+    width: None,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  });
+  measurements
+}
+
+fn bench_selection(c: &mut Criterion) {
+  // 5,000 measurements
+  let measurements = takeoff(50);
+  let index = SpatialIndex::from_measurements(&measurements);
+  let tool = SelectionTool::default();
+  let transform = Transform::identity();
+  let point = Point::new(1226.0, 1211.0);
+
+  let mut group = c.benchmark_group("selection");
+  group.bench_function("linear", |b| {
+    b.iter(|| tool.find_selection(black_box(point), &measurements, transform))
+  });
+  group.bench_function("indexed", |b| {
+    b.iter(|| tool.find_selection_indexed(black_box(point), &measurements, &index, transform))
+  });
+  group.finish();
+}
+
+fn bench_snapping(c: &mut Criterion) {
+  let measurements = takeoff(50);
+  let index = SpatialIndex::from_measurements(&measurements);
+  let point = Point::new(1226.0, 1211.0);
+
+  let mut group = c.benchmark_group("snapping");
+  group.bench_function("linear", |b| {
+    b.iter(|| {
+      let mut snapper = Snapper::default();
+      snapper.set_measurements(&measurements);
+      snapper.snap(black_box(point), None)
+    })
+  });
+  group.bench_function("indexed", |b| {
+    b.iter(|| {
+      let mut snapper = Snapper::default();
+      let ids = index.query_point(point, snapper.search_radius());
+      snapper.set_measurements(SpatialIndex::resolve(&measurements, &ids));
+      snapper.snap(black_box(point), None)
+    })
+  });
+  group.finish();
+}
+
+fn bench_updates(c: &mut Criterion) {
+  let measurements = takeoff(50);
+  let mut index = SpatialIndex::from_measurements(&measurements);
+  let mut moved = measurements[1234].clone();
+
+  c.bench_function("index/rebuild", |b| {
+    b.iter(|| SpatialIndex::from_measurements(black_box(&measurements)))
+  });
+  c.bench_function("index/update", |b| {
+    b.iter(|| {
+      moved.set_vertex(0, Point::new(600.0, 600.0));
+      index.insert(black_box(&moved));
+    })
+  });
+}
+
+criterion_group!(benches, bench_selection, bench_snapping, bench_updates);
+criterion_main!(benches);
@@ -0,0 +1,194 @@
+use crate::{ScaleContext, ScaledTool, SnapContext, SnappingTool, TakeoffTool, ToolAction};
+use takeoff_core::{Circle, Ellipse, Measurement, Point, Scale, Unit};
+
+/// How the circle tool's clicks define the shape
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CircleMode {
+  /// Click the center, then a point on the circle
+  #[default]
+  CenterRadius,
+  /// Click three points on the circle
+  ThreePoint,
+  /// Click the center, the end of one axis, then a point setting the other radius
+  Ellipse,
+}
+
+/// Tool for creating circle and ellipse area measurements
+pub struct CircleTool {
+  mode: CircleMode,
+  points: Vec<Point>,
+  is_drawing: bool,
+  scale: ScaleContext,
+  display_unit: Unit,
+  snapping: SnapContext,
+}
+
+impl CircleTool {
+  pub fn new(scale: Option<Scale>, display_unit: Unit) -> Self {
+    Self {
+      mode: CircleMode::CenterRadius,
+      points: Vec::new(),
+      is_drawing: false,
+      scale: ScaleContext::new(scale),
+      display_unit,
+      snapping: SnapContext::default(),
+    }
+  }
+
+  pub fn set_display_unit(&mut self, unit: Unit) {
+    self.display_unit = unit;
+  }
+
+  pub fn mode(&self) -> CircleMode {
+    self.mode
+  }
+
+  /// Change how clicks define the shape, dropping any shape in progress
+  pub fn set_mode(&mut self, mode: CircleMode) {
+    self.mode = mode;
+    self.reset();
+  }
+
+  fn snap(&mut self, point: Point) -> Point {
+    let anchor = self.points.last().copied();
+    self.snapping.snap(point, anchor)
+  }
+
+  fn generate_id(&self) -> String {
+    let prefix = match self.mode {
+      CircleMode::Ellipse => "ellipse",
+      CircleMode::CenterRadius | CircleMode::ThreePoint => "circle",
+    };
+    format!(
+      "{prefix}_{}",
+      uuid::Uuid::new_v4().to_string().replace('-', "")
+    )
+  }
+
+  /// Number of clicks that complete a shape
+  fn clicks(&self) -> usize {
+    match self.mode {
+      CircleMode::CenterRadius => 2,
+      CircleMode::ThreePoint | CircleMode::Ellipse => 3,
+    }
+  }
+
+  /// The shape with the last click at `point`, if there are enough points to draw it
+  fn measurement_to(&mut self, id: String, point: Point) -> Option<Measurement> {
+    let (circle, ellipse) = match (self.mode, self.points.as_slice()) {
+      (CircleMode::CenterRadius, [center, ..]) => {
+        (Some(Circle::new(*center, center.distance_to(&point))), None)
+      }
+      (CircleMode::ThreePoint, [a, b, ..]) => {
+        (Some(Circle::from_three_points(*a, *b, point)?), None)
+      }
+      // With only two points, show the circle they span
+      (CircleMode::ThreePoint, [a]) => (
+        Some(Circle::new(
+          Point::new((a.x + point.x) / 2.0, (a.y + point.y) / 2.0),
+          a.distance_to(&point) / 2.0,
+        )),
+        None,
+      ),
+      (CircleMode::Ellipse, [center, axis_end, ..]) => {
+        (None, Some(Ellipse::from_axes(*center, *axis_end, point)))
+      }
+      // Until the second axis is set the ellipse is a circle
+      (CircleMode::Ellipse, [center]) => {
+        (Some(Circle::new(*center, center.distance_to(&point))), None)
+      }
+      _ => return None,
+    };
+    let outline = circle
+      .map(|c| c.quadrants())
+      .or(ellipse.map(|e| e.handles()[1..].to_vec()))
+      .unwrap_or_default();
+    let scale = self.scale.resolve(&outline);
+    Some(Measurement::Area {
+      id,
+      rectangle: None,
+      polygon: None,
+      circle,
+      ellipse,
+      scale,
+      display_unit: self.display_unit,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    })
+  }
+
+  fn reset(&mut self) {
+    self.points.clear();
+    self.is_drawing = false;
+  }
+}
+
+impl ScaledTool for CircleTool {
+  fn scale_context(&self) -> &ScaleContext {
+    &self.scale
+  }
+
+  fn scale_context_mut(&mut self) -> &mut ScaleContext {
+    &mut self.scale
+  }
+}
+
+impl SnappingTool for CircleTool {
+  fn snap_context(&self) -> &SnapContext {
+    &self.snapping
+  }
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext {
+    &mut self.snapping
+  }
+}
+
+impl TakeoffTool for CircleTool {
+  fn name(&self) -> &'static str {
+    "circle"
+  }
+
+  fn on_mouse_down(&mut self, point: Point) -> ToolAction {
+    let point = self.snap(point);
+    if self.points.len() + 1 < self.clicks() {
+      self.points.push(point);
+      self.is_drawing = true;
+      return ToolAction::None;
+    }
+    let id = self.generate_id();
+    match self.measurement_to(id, point) {
+      Some(measurement) if measurement.pixel_area().is_some_and(|area| area > 0.0) => {
+        self.reset();
+        ToolAction::CreateMeasurement(measurement)
+      }
+      // A click that would make an empty shape is ignored
+      _ => ToolAction::None,
+    }
+  }
+
+  fn on_mouse_move(&mut self, point: Point) -> ToolAction {
+    if !self.is_drawing {
+      return ToolAction::None;
+    }
+    let point = self.snap(point);
+    match self.measurement_to("preview".to_string(), point) {
+      Some(measurement) => ToolAction::UpdatePreview(measurement),
+      None => ToolAction::None,
+    }
+  }
+
+  fn on_mouse_up(&mut self, _point: Point) -> ToolAction {
+    // Circles are drawn with clicks in mouse_down, like polygons
+    ToolAction::None
+  }
+
+  fn cancel(&mut self) -> ToolAction {
+    self.reset();
+    ToolAction::Cancel
+  }
+
+  fn is_drawing(&self) -> bool {
+    self.is_drawing
+  }
+}
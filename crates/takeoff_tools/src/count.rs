@@ -1,14 +1,18 @@
-use crate::{TakeoffTool, ToolAction};
+use crate::{SnapContext, SnappingTool, TakeoffTool, ToolAction};
 use takeoff_core::{Measurement, Point};
 
 /// Tool for creating count markers
 pub struct CountTool {
   is_placing: bool,
+  snapping: SnapContext,
 }
 
 impl CountTool {
   pub fn new() -> Self {
-    Self { is_placing: false }
+    Self {
+      is_placing: false,
+      snapping: SnapContext::default(),
+    }
   }
 
   fn generate_id() -> String {
@@ -25,6 +29,16 @@ impl Default for CountTool {
   }
 }
 
+impl SnappingTool for CountTool {
+  fn snap_context(&self) -> &SnapContext {
+    &self.snapping
+  }
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext {
+    &mut self.snapping
+  }
+}
+
 impl TakeoffTool for CountTool {
   fn name(&self) -> &'static str {
     "count"
@@ -35,15 +49,19 @@ impl TakeoffTool for CountTool {
     ToolAction::None
   }
 
-  fn on_mouse_move(&mut self, _point: Point) -> ToolAction {
+  fn on_mouse_move(&mut self, point: Point) -> ToolAction {
+    // Markers have no preview, but the snap indicator still follows the pointer
+    self.snapping.snap(point, None);
     ToolAction::None
   }
 
   fn on_mouse_up(&mut self, point: Point) -> ToolAction {
     if self.is_placing {
+      let point = self.snapping.snap(point, None);
       let measurement = Measurement::Count {
         id: Self::generate_id(),
         point,
+        metadata: None,
       };
       self.is_placing = false;
       ToolAction::CreateMeasurement(measurement)
@@ -1,18 +1,26 @@
+pub mod circle;
 pub mod count;
 pub mod line;
 pub mod polygon;
 pub mod polyline;
 pub mod rectangle;
 pub mod scale;
+pub mod scale_context;
 pub mod selection;
+pub mod snap_context;
+pub mod transform;
 
+pub use circle::{CircleMode, CircleTool};
 pub use count::CountTool;
 pub use line::LineTool;
-pub use polygon::PolygonTool;
+pub use polygon::{InvalidPolygonPolicy, PolygonTool};
 pub use polyline::PolylineTool;
 pub use rectangle::RectangleTool;
 pub use scale::ScaleTool;
-pub use selection::{SelectionResult, SelectionTool};
+pub use scale_context::{ScaleContext, ScaledTool};
+pub use selection::{HitKind, MarqueeMode, SelectionMode, SelectionResult, SelectionTool};
+pub use snap_context::{SnapContext, SnappingTool};
+pub use transform::{TransformMode, TransformTool, Transformation};
 
 use takeoff_core::{Measurement, Point};
 
@@ -45,8 +53,16 @@ pub enum ToolAction {
   None,
   /// Create a new measurement
   CreateMeasurement(Measurement),
+  /// Create several new measurements at once (e.g. the valid parts of a repaired shape)
+  CreateMeasurements(Vec<Measurement>),
+  /// Apply a transformation to the current measurements with these IDs
+  TransformMeasurements(Vec<String>, Transformation),
+  /// Cut holes out of the area measurement with this ID
+  AddCutouts(String, Vec<Vec<Point>>),
   /// Update preview (for drawing operations)
   UpdatePreview(Measurement),
+  /// Update the preview of several measurements (for transform operations)
+  UpdatePreviews(Vec<Measurement>),
   /// Cancel current operation
   Cancel,
 }
@@ -1,4 +1,4 @@
-use crate::{TakeoffTool, ToolAction};
+use crate::{ScaleContext, ScaledTool, SnapContext, SnappingTool, TakeoffTool, ToolAction};
 use takeoff_core::{Line, Measurement, Point, Scale, Unit};
 
 /// Tool for creating linear measurements with lines
@@ -6,8 +6,9 @@ pub struct LineTool {
   start_point: Option<Point>,
   end_point: Option<Point>,
   is_drawing: bool,
-  scale: Option<Scale>,
+  scale: ScaleContext,
   display_unit: Unit,
+  snapping: SnapContext,
 }
 
 impl LineTool {
@@ -16,15 +17,12 @@ impl LineTool {
       start_point: None,
       end_point: None,
       is_drawing: false,
-      scale,
+      scale: ScaleContext::new(scale),
       display_unit,
+      snapping: SnapContext::default(),
     }
   }
 
-  pub fn set_scale(&mut self, scale: Option<Scale>) {
-    self.scale = scale;
-  }
-
   pub fn set_display_unit(&mut self, unit: Unit) {
     self.display_unit = unit;
   }
@@ -34,6 +32,26 @@ impl LineTool {
   }
 }
 
+impl ScaledTool for LineTool {
+  fn scale_context(&self) -> &ScaleContext {
+    &self.scale
+  }
+
+  fn scale_context_mut(&mut self) -> &mut ScaleContext {
+    &mut self.scale
+  }
+}
+
+impl SnappingTool for LineTool {
+  fn snap_context(&self) -> &SnapContext {
+    &self.snapping
+  }
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext {
+    &mut self.snapping
+  }
+}
+
 impl TakeoffTool for LineTool {
   fn name(&self) -> &'static str {
     "line"
@@ -41,6 +59,7 @@ impl TakeoffTool for LineTool {
 
   fn on_mouse_down(&mut self, point: Point) -> ToolAction {
     if self.start_point.is_none() {
+      let point = self.snapping.snap(point, None);
       self.start_point = Some(point);
       self.is_drawing = true;
       ToolAction::None
@@ -51,16 +70,21 @@ impl TakeoffTool for LineTool {
 
   fn on_mouse_move(&mut self, point: Point) -> ToolAction {
     if self.is_drawing {
+      let point = self.snapping.snap(point, self.start_point);
       self.end_point = Some(point);
       if let (Some(start), Some(end)) = (self.start_point, self.end_point) {
         let line = Line::new(start, end);
-        let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
+        let scale = self.scale.resolve(&[start, end]);
         ToolAction::UpdatePreview(Measurement::Linear {
           id: "preview".to_string(),
           line: Some(line),
           polyline: None,
           scale,
           display_unit: self.display_unit,
+          width: None,
+          depth: None,
+          pitch: None,
+          metadata: None,
         })
       } else {
         ToolAction::None
@@ -72,16 +96,21 @@ impl TakeoffTool for LineTool {
 
   fn on_mouse_up(&mut self, point: Point) -> ToolAction {
     if self.is_drawing {
+      let point = self.snapping.snap(point, self.start_point);
       self.end_point = Some(point);
       if let (Some(start), Some(end)) = (self.start_point, self.end_point) {
         let line = Line::new(start, end);
-        let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
+        let scale = self.scale.resolve(&[start, end]);
         let measurement = Measurement::Linear {
           id: Self::generate_id(),
           line: Some(line),
           polyline: None,
           scale,
           display_unit: self.display_unit,
+          width: None,
+          depth: None,
+          pitch: None,
+          metadata: None,
         };
         self.start_point = None;
         self.end_point = None;
@@ -1,30 +1,59 @@
-use crate::{TakeoffTool, ToolAction};
-use takeoff_core::{Measurement, Point, Polygon, Scale, Unit};
+use crate::{ScaleContext, ScaledTool, SnapContext, SnappingTool, TakeoffTool, ToolAction};
+use takeoff_core::arc::bulge_from_three_points;
+use takeoff_core::{
+  repair_polygon, validate_polygon, Measurement, Point, Polygon, Scale, Unit, ValidationIssue,
+};
+
+/// What the polygon tool does when a closed shape crosses itself or is degenerate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidPolygonPolicy {
+  /// Create the shape as drawn
+  #[default]
+  Allow,
+  /// Ignore the closing click so the shape stays open
+  Reject,
+  /// Split the shape into valid parts and create each of them
+  Repair,
+}
 
 /// Tool for creating area measurements with polygons
 pub struct PolygonTool {
   points: Vec<Point>,
+  bulges: Vec<f64>,
   is_drawing: bool,
-  scale: Option<Scale>,
+  scale: ScaleContext,
   display_unit: Unit,
+  snapping: SnapContext,
   close_threshold: f64,
+  /// ID of the area measurement that closed rings are cut out of (cutout mode)
+  cutout_target: Option<String>,
+  /// Whether new segments are drawn as three-point arcs
+  arc_mode: bool,
+  /// Point the pending arc passes through
+  arc_through: Option<Point>,
+  invalid_policy: InvalidPolygonPolicy,
+  /// Problems found in the last shape closed
+  validation_issues: Vec<ValidationIssue>,
 }
 
 impl PolygonTool {
   pub fn new(scale: Option<Scale>, display_unit: Unit) -> Self {
     Self {
       points: Vec::new(),
+      bulges: Vec::new(),
       is_drawing: false,
-      scale,
+      scale: ScaleContext::new(scale),
       display_unit,
+      snapping: SnapContext::default(),
       close_threshold: 10.0,
+      cutout_target: None,
+      arc_mode: false,
+      arc_through: None,
+      invalid_policy: InvalidPolygonPolicy::Allow,
+      validation_issues: Vec::new(),
     }
   }
 
-  pub fn set_scale(&mut self, scale: Option<Scale>) {
-    self.scale = scale;
-  }
-
   pub fn set_display_unit(&mut self, unit: Unit) {
     self.display_unit = unit;
   }
@@ -33,6 +62,60 @@ impl PolygonTool {
     self.close_threshold = threshold;
   }
 
+  /// Enter cutout mode for an area measurement, or leave it with `None`
+  ///
+  /// In cutout mode each closed ring is returned as `ToolAction::AddCutouts` for
+  /// the target's ID instead of a new measurement, so the hole is cut out of the
+  /// measurement as it is when the ring closes.
+  pub fn set_cutout_target(&mut self, target: Option<&Measurement>) -> bool {
+    match target {
+      Some(measurement @ Measurement::Area { .. }) => {
+        self.cutout_target = Some(measurement.id().to_string());
+        true
+      }
+      Some(_) => false,
+      None => {
+        self.cutout_target = None;
+        true
+      }
+    }
+  }
+
+  /// Check if closed rings are cut out of an existing area
+  pub fn is_cutout_mode(&self) -> bool {
+    self.cutout_target.is_some()
+  }
+
+  /// Draw the following segments as arcs
+  ///
+  /// In arc mode each segment takes two clicks: a point on the arc, then the end
+  /// point. Clicking near the start after a point on the arc closes with an arc.
+  pub fn set_arc_mode(&mut self, arc_mode: bool) {
+    self.arc_mode = arc_mode;
+    self.arc_through = None;
+  }
+
+  pub fn is_arc_mode(&self) -> bool {
+    self.arc_mode
+  }
+
+  /// Choose whether self-intersecting and degenerate shapes are allowed,
+  /// rejected or repaired when closing
+  pub fn set_invalid_policy(&mut self, policy: InvalidPolygonPolicy) {
+    self.invalid_policy = policy;
+  }
+
+  pub fn invalid_policy(&self) -> InvalidPolygonPolicy {
+    self.invalid_policy
+  }
+
+  /// Problems found in the last shape closed, so the UI can mark them
+  ///
+  /// Filled whatever the policy; empty if the shape was valid.
+  pub fn validation_issues(&self) -> &[ValidationIssue] {
+    &self.validation_issues
+  }
+
   fn generate_id() -> String {
     format!(
       "polygon_{}",
@@ -48,6 +131,67 @@ impl PolygonTool {
     let start = &self.points[0];
     start.distance_to(point) < self.close_threshold
   }
+
+  fn preview(&mut self, hover: Option<Point>) -> ToolAction {
+    let mut points = self.points.clone();
+    let mut bulges = self.bulges.clone();
+    if let (Some(hover), Some(last)) = (hover, points.last()) {
+      bulges.push(match self.arc_through {
+        Some(through) => bulge_from_three_points(*last, through, hover),
+        None => 0.0,
+      });
+      points.push(hover);
+    }
+    let scale = self.scale.resolve(&points);
+    ToolAction::UpdatePreview(Measurement::Area {
+      id: "preview".to_string(),
+      polygon: Some(Polygon::with_bulges(points, bulges)),
+      circle: None,
+      ellipse: None,
+      rectangle: None,
+      scale,
+      display_unit: self.display_unit,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    })
+  }
+
+  /// Previous point for ortho and angle locks; none while picking a point on an arc
+  fn snap_anchor(&self) -> Option<Point> {
+    if self.arc_mode && self.arc_through.is_none() {
+      None
+    } else {
+      self.points.last().copied()
+    }
+  }
+
+  fn reset(&mut self) {
+    self.points.clear();
+    self.bulges.clear();
+    self.arc_through = None;
+    self.is_drawing = false;
+  }
+}
+
+impl ScaledTool for PolygonTool {
+  fn scale_context(&self) -> &ScaleContext {
+    &self.scale
+  }
+
+  fn scale_context_mut(&mut self) -> &mut ScaleContext {
+    &mut self.scale
+  }
+}
+
+impl SnappingTool for PolygonTool {
+  fn snap_context(&self) -> &SnapContext {
+    &self.snapping
+  }
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext {
+    &mut self.snapping
+  }
 }
 
 impl TakeoffTool for PolygonTool {
@@ -56,6 +200,7 @@ impl TakeoffTool for PolygonTool {
   }
 
   fn on_mouse_down(&mut self, point: Point) -> ToolAction {
+    let point = self.snapping.snap(point, self.snap_anchor());
     if self.points.is_empty() {
       // First point - start drawing
       self.points.push(point);
@@ -64,48 +209,80 @@ impl TakeoffTool for PolygonTool {
     } else if self.is_close_to_start(&point) && self.points.len() >= 2 {
       // Close polygon by clicking near start
       if self.points.len() >= 3 {
-        let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
-        let measurement = Measurement::Area {
-          id: Self::generate_id(),
-          polygon: Some(Polygon::new(self.points.clone())),
-          rectangle: None,
-          scale,
-          display_unit: self.display_unit,
+        let closing_bulge = match (self.arc_through, self.points.last()) {
+          (Some(through), Some(last)) => bulge_from_three_points(*last, through, self.points[0]),
+          _ => 0.0,
+        };
+        let mut bulges = self.bulges.clone();
+        bulges.push(closing_bulge);
+        let polygon = Polygon::with_bulges(self.points.clone(), bulges);
+        self.validation_issues = validate_polygon(&polygon);
+        let parts = match self.invalid_policy {
+          InvalidPolygonPolicy::Reject if !self.validation_issues.is_empty() => {
+            return ToolAction::None;
+          }
+          InvalidPolygonPolicy::Repair if !self.validation_issues.is_empty() => {
+            repair_polygon(&polygon)
+          }
+          _ => vec![polygon],
         };
-        self.points.clear();
-        self.is_drawing = false;
-        ToolAction::CreateMeasurement(measurement)
+        self.reset();
+        if let Some(target) = &self.cutout_target {
+          let rings: Vec<Vec<Point>> = parts.iter().map(|part| part.flattened_points()).collect();
+          return if rings.is_empty() {
+            ToolAction::None
+          } else {
+            ToolAction::AddCutouts(target.clone(), rings)
+          };
+        }
+        let mut measurements: Vec<Measurement> = parts
+          .into_iter()
+          .map(|polygon| {
+            let scale = self.scale.resolve(&polygon.points);
+            Measurement::Area {
+              id: Self::generate_id(),
+              polygon: Some(polygon),
+              circle: None,
+              ellipse: None,
+              rectangle: None,
+              scale,
+              display_unit: self.display_unit,
+              depth: None,
+              pitch: None,
+              metadata: None,
+            }
+          })
+          .collect();
+        match measurements.len() {
+          0 => ToolAction::None,
+          1 => ToolAction::CreateMeasurement(measurements.remove(0)),
+          _ => ToolAction::CreateMeasurements(measurements),
+        }
       } else {
         ToolAction::None
       }
+    } else if self.arc_mode && self.arc_through.is_none() {
+      // First click of an arc segment picks a point on the arc
+      self.arc_through = Some(point);
+      self.preview(None)
     } else {
       // Add new point
+      let bulge = match (self.arc_through.take(), self.points.last()) {
+        (Some(through), Some(last)) => bulge_from_three_points(*last, through, point),
+        _ => 0.0,
+      };
       self.points.push(point);
+      self.bulges.push(bulge);
       // Update preview
-      let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
-      ToolAction::UpdatePreview(Measurement::Area {
-        id: "preview".to_string(),
-        polygon: Some(Polygon::new(self.points.clone())),
-        rectangle: None,
-        scale,
-        display_unit: self.display_unit,
-      })
+      self.preview(None)
     }
   }
 
   fn on_mouse_move(&mut self, point: Point) -> ToolAction {
     if self.is_drawing && !self.points.is_empty() {
+      let point = self.snapping.snap(point, self.snap_anchor());
       // Update preview with current hover point
-      let mut preview_points = self.points.clone();
-      preview_points.push(point);
-      let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
-      ToolAction::UpdatePreview(Measurement::Area {
-        id: "preview".to_string(),
-        polygon: Some(Polygon::new(preview_points)),
-        rectangle: None,
-        scale,
-        display_unit: self.display_unit,
-      })
+      self.preview(Some(point))
     } else {
       ToolAction::None
     }
@@ -117,8 +294,7 @@ impl TakeoffTool for PolygonTool {
   }
 
   fn cancel(&mut self) -> ToolAction {
-    self.points.clear();
-    self.is_drawing = false;
+    self.reset();
     ToolAction::Cancel
   }
 
@@ -1,38 +1,114 @@
-use crate::{TakeoffTool, ToolAction};
+use crate::{ScaleContext, ScaledTool, SnapContext, SnappingTool, TakeoffTool, ToolAction};
+use takeoff_core::arc::bulge_from_three_points;
 use takeoff_core::{Measurement, Point, Polyline, Scale, Unit};
 
 /// Tool for creating linear measurements with polylines (multiple connected line segments)
 pub struct PolylineTool {
   points: Vec<Point>,
+  bulges: Vec<f64>,
   is_drawing: bool,
-  scale: Option<Scale>,
+  scale: ScaleContext,
   display_unit: Unit,
+  snapping: SnapContext,
+  /// Whether new segments are drawn as three-point arcs
+  arc_mode: bool,
+  /// Point the pending arc passes through
+  arc_through: Option<Point>,
 }
 
 impl PolylineTool {
   pub fn new(scale: Option<Scale>, display_unit: Unit) -> Self {
     Self {
       points: Vec::new(),
+      bulges: Vec::new(),
       is_drawing: false,
-      scale,
+      scale: ScaleContext::new(scale),
       display_unit,
+      snapping: SnapContext::default(),
+      arc_mode: false,
+      arc_through: None,
     }
   }
 
-  pub fn set_scale(&mut self, scale: Option<Scale>) {
-    self.scale = scale;
-  }
-
   pub fn set_display_unit(&mut self, unit: Unit) {
     self.display_unit = unit;
   }
 
+  /// Draw the following segments as arcs
+  ///
+  /// In arc mode each segment takes two clicks: a point on the arc, then the end point.
+  pub fn set_arc_mode(&mut self, arc_mode: bool) {
+    self.arc_mode = arc_mode;
+    self.arc_through = None;
+  }
+
+  pub fn is_arc_mode(&self) -> bool {
+    self.arc_mode
+  }
+
   fn generate_id() -> String {
     format!(
       "polyline_{}",
       uuid::Uuid::new_v4().to_string().replace('-', "")
     )
   }
+
+  fn preview(&mut self, hover: Option<Point>) -> ToolAction {
+    let mut points = self.points.clone();
+    let mut bulges = self.bulges.clone();
+    if let (Some(through), Some(hover), Some(last)) = (self.arc_through, hover, points.last()) {
+      bulges.push(bulge_from_three_points(*last, through, hover));
+      points.push(hover);
+    }
+    let scale = self.scale.resolve(&points);
+    ToolAction::UpdatePreview(Measurement::Linear {
+      id: "preview".to_string(),
+      line: None,
+      polyline: Some(Polyline::with_bulges(points, bulges)),
+      scale,
+      display_unit: self.display_unit,
+      width: None,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    })
+  }
+
+  /// Previous point for ortho and angle locks; none while picking a point on an arc
+  fn snap_anchor(&self) -> Option<Point> {
+    if self.arc_mode && self.arc_through.is_none() {
+      None
+    } else {
+      self.points.last().copied()
+    }
+  }
+
+  fn reset(&mut self) {
+    self.points.clear();
+    self.bulges.clear();
+    self.arc_through = None;
+    self.is_drawing = false;
+  }
+}
+
+impl ScaledTool for PolylineTool {
+  fn scale_context(&self) -> &ScaleContext {
+    &self.scale
+  }
+
+  fn scale_context_mut(&mut self) -> &mut ScaleContext {
+    &mut self.scale
+  }
+}
+
+impl SnappingTool for PolylineTool {
+  fn snap_context(&self) -> &SnapContext {
+    &self.snapping
+  }
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext {
+    &mut self.snapping
+  }
 }
 
 impl TakeoffTool for PolylineTool {
@@ -41,37 +117,34 @@ impl TakeoffTool for PolylineTool {
   }
 
   fn on_mouse_down(&mut self, point: Point) -> ToolAction {
+    let point = self.snapping.snap(point, self.snap_anchor());
     if !self.is_drawing {
       // First point - start drawing
       self.points.push(point);
       self.is_drawing = true;
       ToolAction::None
+    } else if self.arc_mode && self.arc_through.is_none() {
+      // First click of an arc segment picks a point on the arc
+      self.arc_through = Some(point);
+      self.preview(None)
     } else {
       // Add new point
+      let bulge = match (self.arc_through.take(), self.points.last()) {
+        (Some(through), Some(last)) => bulge_from_three_points(*last, through, point),
+        _ => 0.0,
+      };
       self.points.push(point);
+      self.bulges.push(bulge);
       // Update preview - show polyline so far plus line to current point
-      let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
-      ToolAction::UpdatePreview(Measurement::Linear {
-        id: "preview".to_string(),
-        line: None,
-        polyline: Some(Polyline::new(self.points.clone())),
-        scale,
-        display_unit: self.display_unit,
-      })
+      self.preview(None)
     }
   }
 
-  fn on_mouse_move(&mut self, _point: Point) -> ToolAction {
+  fn on_mouse_move(&mut self, point: Point) -> ToolAction {
     if self.is_drawing && !self.points.is_empty() {
+      let point = self.snapping.snap(point, self.snap_anchor());
       // Update preview with polyline so far
-      let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
-      ToolAction::UpdatePreview(Measurement::Linear {
-        id: "preview".to_string(),
-        line: None,
-        polyline: Some(Polyline::new(self.points.clone())),
-        scale,
-        display_unit: self.display_unit,
-      })
+      self.preview(Some(point))
     } else {
       ToolAction::None
     }
@@ -85,20 +158,23 @@ impl TakeoffTool for PolylineTool {
   fn cancel(&mut self) -> ToolAction {
     if self.points.len() >= 2 {
       // Finish the polyline with current points
-      let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
+      let points = self.points.clone();
+      let scale = self.scale.resolve(&points);
       let measurement = Measurement::Linear {
         id: Self::generate_id(),
         line: None,
-        polyline: Some(Polyline::new(self.points.clone())),
+        polyline: Some(Polyline::with_bulges(points, self.bulges.clone())),
         scale,
         display_unit: self.display_unit,
+        width: None,
+        depth: None,
+        pitch: None,
+        metadata: None,
       };
-      self.points.clear();
-      self.is_drawing = false;
+      self.reset();
       ToolAction::CreateMeasurement(measurement)
     } else {
-      self.points.clear();
-      self.is_drawing = false;
+      self.reset();
       ToolAction::Cancel
     }
   }
@@ -1,13 +1,22 @@
-use crate::{TakeoffTool, ToolAction};
+use crate::{ScaleContext, ScaledTool, SnapContext, SnappingTool, TakeoffTool, ToolAction};
 use takeoff_core::{Measurement, Point, Rectangle, Scale, Unit};
 
 /// Tool for creating area measurements with rectangles
+///
+/// By default a rectangle is dragged from corner to corner, square to the axes
+/// or turned by `set_rotation`. In three-point mode the first two clicks set a
+/// baseline at any angle and the third sets how far the rectangle extends from it.
 pub struct RectangleTool {
   start_point: Option<Point>,
   end_point: Option<Point>,
+  /// End of the baseline in three-point mode
+  baseline_end: Option<Point>,
+  rotation: Option<f64>,
+  three_point: bool,
   is_drawing: bool,
-  scale: Option<Scale>,
+  scale: ScaleContext,
   display_unit: Unit,
+  snapping: SnapContext,
 }
 
 impl RectangleTool {
@@ -15,23 +24,96 @@ impl RectangleTool {
     Self {
       start_point: None,
       end_point: None,
+      baseline_end: None,
+      rotation: None,
+      three_point: false,
       is_drawing: false,
-      scale,
+      scale: ScaleContext::new(scale),
       display_unit,
+      snapping: SnapContext::default(),
     }
   }
 
-  pub fn set_scale(&mut self, scale: Option<Scale>) {
-    self.scale = scale;
-  }
-
   pub fn set_display_unit(&mut self, unit: Unit) {
     self.display_unit = unit;
   }
 
+  /// Turn dragged rectangles by `degrees` counter-clockwise, or square them to the axes with `None`
+  pub fn set_rotation(&mut self, degrees: Option<f64>) {
+    self.rotation = degrees;
+  }
+
+  pub fn rotation(&self) -> Option<f64> {
+    self.rotation
+  }
+
+  /// Draw with three clicks: both ends of a baseline, then a point on the opposite side
+  pub fn set_three_point(&mut self, three_point: bool) {
+    self.three_point = three_point;
+    self.reset();
+  }
+
+  pub fn is_three_point(&self) -> bool {
+    self.three_point
+  }
+
   fn generate_id() -> String {
     format!("rect_{}", uuid::Uuid::new_v4().to_string().replace('-', ""))
   }
+
+  /// The rectangle with its last corner or side at `point`
+  fn rectangle_to(&self, point: Point) -> Option<Rectangle> {
+    let start = self.start_point?;
+    Some(match (self.three_point, self.baseline_end) {
+      (true, Some(baseline_end)) => Rectangle::from_baseline(start, baseline_end, point),
+      // While placing the baseline the rectangle is still flat
+      (true, None) => Rectangle::from_baseline(start, point, point),
+      (false, _) => Rectangle::rotated(start, point, self.rotation.unwrap_or(0.0)),
+    })
+  }
+
+  fn measurement(&mut self, id: String, rectangle: Rectangle) -> Measurement {
+    let scale = self.scale.resolve(&rectangle.corners());
+    Measurement::Area {
+      id,
+      rectangle: Some(rectangle),
+      polygon: None,
+      circle: None,
+      ellipse: None,
+      scale,
+      display_unit: self.display_unit,
+      depth: None,
+      pitch: None,
+      metadata: None,
+    }
+  }
+
+  fn reset(&mut self) {
+    self.start_point = None;
+    self.end_point = None;
+    self.baseline_end = None;
+    self.is_drawing = false;
+  }
+}
+
+impl ScaledTool for RectangleTool {
+  fn scale_context(&self) -> &ScaleContext {
+    &self.scale
+  }
+
+  fn scale_context_mut(&mut self) -> &mut ScaleContext {
+    &mut self.scale
+  }
+}
+
+impl SnappingTool for RectangleTool {
+  fn snap_context(&self) -> &SnapContext {
+    &self.snapping
+  }
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext {
+    &mut self.snapping
+  }
 }
 
 impl TakeoffTool for RectangleTool {
@@ -41,29 +123,43 @@ impl TakeoffTool for RectangleTool {
 
   fn on_mouse_down(&mut self, point: Point) -> ToolAction {
     if self.start_point.is_none() {
+      let point = self.snapping.snap(point, None);
       self.start_point = Some(point);
       self.is_drawing = true;
       ToolAction::None
-    } else {
+    } else if !self.three_point {
       ToolAction::None
+    } else if self.baseline_end.is_none() {
+      let point = self.snapping.snap(point, self.start_point);
+      self.baseline_end = Some(point);
+      ToolAction::None
+    } else {
+      let point = self.snapping.snap(point, self.baseline_end);
+      let Some(rectangle) = self.rectangle_to(point) else {
+        return ToolAction::None;
+      };
+      let measurement = self.measurement(Self::generate_id(), rectangle);
+      self.reset();
+      ToolAction::CreateMeasurement(measurement)
     }
   }
 
   fn on_mouse_move(&mut self, point: Point) -> ToolAction {
     if self.is_drawing {
-      self.end_point = Some(point);
-      if let (Some(start), Some(end)) = (self.start_point, self.end_point) {
-        let rectangle = Rectangle::new(start, end);
-        let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
-        ToolAction::UpdatePreview(Measurement::Area {
-          id: "preview".to_string(),
-          rectangle: Some(rectangle),
-          polygon: None,
-          scale,
-          display_unit: self.display_unit,
-        })
+      // Dragged rectangles follow their rotation, so only object snaps apply to
+      // the corner; three-point edges lock from the previous click
+      let anchor = if self.three_point {
+        self.baseline_end.or(self.start_point)
       } else {
-        ToolAction::None
+        None
+      };
+      let point = self.snapping.snap(point, anchor);
+      self.end_point = Some(point);
+      match self.rectangle_to(point) {
+        Some(rectangle) => {
+          ToolAction::UpdatePreview(self.measurement("preview".to_string(), rectangle))
+        }
+        None => ToolAction::None,
       }
     } else {
       ToolAction::None
@@ -71,34 +167,22 @@ impl TakeoffTool for RectangleTool {
   }
 
   fn on_mouse_up(&mut self, point: Point) -> ToolAction {
-    if self.is_drawing {
+    if self.is_drawing && !self.three_point {
+      let point = self.snapping.snap(point, None);
       self.end_point = Some(point);
-      if let (Some(start), Some(end)) = (self.start_point, self.end_point) {
-        let rectangle = Rectangle::new(start, end);
-        let scale = self.scale.unwrap_or(Scale::new(1.0, 1.0, Unit::Meters));
-        let measurement = Measurement::Area {
-          id: Self::generate_id(),
-          rectangle: Some(rectangle),
-          polygon: None,
-          scale,
-          display_unit: self.display_unit,
-        };
-        self.start_point = None;
-        self.end_point = None;
-        self.is_drawing = false;
-        ToolAction::CreateMeasurement(measurement)
-      } else {
-        ToolAction::None
-      }
+      let Some(rectangle) = self.rectangle_to(point) else {
+        return ToolAction::None;
+      };
+      let measurement = self.measurement(Self::generate_id(), rectangle);
+      self.reset();
+      ToolAction::CreateMeasurement(measurement)
     } else {
       ToolAction::None
     }
   }
 
   fn cancel(&mut self) -> ToolAction {
-    self.start_point = None;
-    self.end_point = None;
-    self.is_drawing = false;
+    self.reset();
     ToolAction::Cancel
   }
 
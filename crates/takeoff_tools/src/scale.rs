@@ -71,6 +71,10 @@ impl TakeoffTool for ScaleTool {
           polyline: None,
           scale: Scale::new(1.0, 1.0, Unit::Meters), // Dummy scale for preview
           display_unit: Unit::Meters,
+          width: None,
+          depth: None,
+          pitch: None,
+          metadata: None,
         })
       } else {
         ToolAction::None
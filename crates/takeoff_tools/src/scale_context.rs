@@ -0,0 +1,57 @@
+use takeoff_core::scale_region::resolve_scale;
+use takeoff_core::{Point, Scale, ScaleRegion, ScaleRegionWarning, Unit};
+
+/// Where a drawing tool's measurements get their scale from
+///
+/// A measurement drawn inside a scale region takes the region's scale; one drawn
+/// outside every region takes the fallback page scale.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScaleContext {
+  /// Page scale, used outside every region
+  pub fallback: Option<Scale>,
+  pub regions: Vec<ScaleRegion>,
+  /// Warning for the last measurement if it crossed a scale region boundary
+  pub warning: Option<ScaleRegionWarning>,
+}
+
+impl ScaleContext {
+  pub fn new(fallback: Option<Scale>) -> Self {
+    Self {
+      fallback,
+      ..Self::default()
+    }
+  }
+
+  /// Pick the scale for a measurement through `points`, keeping any warning
+  ///
+  /// Without any scale, one pixel is one meter.
+  pub fn resolve(&mut self, points: &[Point]) -> Scale {
+    let resolution = resolve_scale(&self.regions, self.fallback, points);
+    self.warning = resolution.warning;
+    resolution
+      .scale
+      .unwrap_or(Scale::new(1.0, 1.0, Unit::Meters))
+  }
+}
+
+/// Drawing tools whose measurements take their scale from a `ScaleContext`
+pub trait ScaledTool {
+  fn scale_context(&self) -> &ScaleContext;
+
+  fn scale_context_mut(&mut self) -> &mut ScaleContext;
+
+  /// Set the page scale, used outside every scale region
+  fn set_scale(&mut self, scale: Option<Scale>) {
+    self.scale_context_mut().fallback = scale;
+  }
+
+  /// Set the scale regions of the page; measurements drawn inside one use its scale
+  fn set_scale_regions(&mut self, regions: Vec<ScaleRegion>) {
+    self.scale_context_mut().regions = regions;
+  }
+
+  /// Warning for the last measurement if it crossed a scale region boundary
+  fn scale_warning(&self) -> Option<&ScaleRegionWarning> {
+    self.scale_context().warning.as_ref()
+  }
+}
@@ -1,5 +1,47 @@
 use crate::{TakeoffTool, ToolAction};
-use takeoff_core::{Measurement, Point, Transform};
+use geo::{Contains, LineString, Point as GeoPoint, Polygon as GeoPolygon};
+use takeoff_core::{ArcSegment, Measurement, Point, SpatialIndex, Transform};
+
+/// Which part of a measurement was hit, in priority order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HitKind {
+  Vertex,
+  Edge,
+  Interior,
+}
+
+/// Which measurements a marquee or lasso picks up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarqueeMode {
+  /// Only measurements entirely inside the region
+  Window,
+  /// Measurements inside or touching the region's boundary
+  Crossing,
+}
+
+impl MarqueeMode {
+  /// CAD convention: dragging left to right is a window, right to left is crossing
+  pub fn from_drag(start: Point, end: Point) -> Self {
+    if end.x >= start.x {
+      MarqueeMode::Window
+    } else {
+      MarqueeMode::Crossing
+    }
+  }
+}
+
+/// How newly picked measurements combine with the current selection set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionMode {
+  /// Replace the selection set
+  Replace,
+  /// Add to the selection set (shift)
+  Add,
+  /// Remove from the selection set
+  Remove,
+  /// Flip each picked measurement in or out of the set (ctrl)
+  Toggle,
+}
 
 /// Result of a selection operation
 #[derive(Debug, Clone, PartialEq)]
@@ -8,11 +50,16 @@ pub struct SelectionResult {
   pub measurement_id: String,
   /// Index of the selected vertex (None if measurement selected but no vertex)
   pub vertex_index: Option<usize>,
+  /// Index of the selected edge, as in `Measurement::segments` (None unless an edge was hit)
+  pub edge_index: Option<usize>,
+  /// What part of the measurement was hit
+  pub hit_kind: HitKind,
   /// The point that was selected
   pub point: Point,
 }
 
 /// Tool for selecting and editing measurements
+#[derive(Debug, Clone)]
 pub struct SelectionTool {
   /// Threshold distance for selecting vertices (in world coordinates)
   vertex_threshold: f64,
@@ -24,6 +71,12 @@ pub struct SelectionTool {
   is_dragging: bool,
   /// The point where dragging started
   drag_start: Option<Point>,
+  /// Where `select_at` was last clicked, to cycle through overlapping hits
+  last_click: Option<Point>,
+  /// Position in the hit list of the last `select_at` click
+  cycle_index: usize,
+  /// Every selected measurement ID, in the order they were selected
+  selected_ids: Vec<String>,
 }
 
 impl SelectionTool {
@@ -34,6 +87,9 @@ impl SelectionTool {
       selected_vertex_index: None,
       is_dragging: false,
       drag_start: None,
+      last_click: None,
+      cycle_index: 0,
+      selected_ids: Vec::new(),
     }
   }
 
@@ -48,43 +104,59 @@ impl SelectionTool {
     measurement: &Measurement,
     threshold: f64,
   ) -> Option<usize> {
-    let vertices = self.get_measurement_vertices(measurement);
+    nearest_within(
+      self
+        .get_measurement_vertices(measurement)
+        .iter()
+        .map(|vertex| vertex.distance_to(&point)),
+      threshold,
+    )
+  }
 
-    for (index, vertex) in vertices.iter().enumerate() {
-      if vertex.distance_to(&point) <= threshold {
-        return Some(index);
-      }
+  /// Find the nearest edge to a point within the threshold
+  fn find_nearest_edge(
+    &self,
+    point: Point,
+    measurement: &Measurement,
+    threshold: f64,
+  ) -> Option<usize> {
+    nearest_within(
+      measurement
+        .segments()
+        .iter()
+        .map(|segment| segment.distance_to_point(&point)),
+      threshold,
+    )
+  }
+
+  /// Hit-test one measurement: vertex first, then edge, then interior
+  fn hit_test(
+    &self,
+    point: Point,
+    measurement: &Measurement,
+    threshold: f64,
+  ) -> Option<SelectionResult> {
+    let result = |hit_kind, vertex_index, edge_index| SelectionResult {
+      measurement_id: measurement.id().to_string(),
+      vertex_index,
+      edge_index,
+      hit_kind,
+      point,
+    };
+    if let Some(index) = self.find_nearest_vertex(point, measurement, threshold) {
+      Some(result(HitKind::Vertex, Some(index), None))
+    } else if let Some(index) = self.find_nearest_edge(point, measurement, threshold) {
+      Some(result(HitKind::Edge, None, Some(index)))
+    } else if measurement.contains_point(&point) {
+      Some(result(HitKind::Interior, None, None))
+    } else {
+      None
     }
-    None
   }
 
   /// Get all vertices for a measurement
   pub fn get_measurement_vertices(&self, measurement: &Measurement) -> Vec<Point> {
-    match measurement {
-      Measurement::Linear { line, polyline, .. } => {
-        if let Some(l) = line {
-          vec![l.start, l.end]
-        } else if let Some(points) = polyline {
-          points.points.clone()
-        } else {
-          Vec::new()
-        }
-      }
-      Measurement::Area {
-        rectangle, polygon, ..
-      } => {
-        if let Some(rect) = rectangle {
-          vec![rect.start, rect.end]
-        } else if let Some(points) = polygon {
-          points.points.clone()
-        } else {
-          Vec::new()
-        }
-      }
-      Measurement::Count { point, .. } => {
-        vec![*point]
-      }
-    }
+    measurement.vertices()
   }
 
   /// Get a specific vertex from a measurement
@@ -100,56 +172,71 @@ impl SelectionTool {
     vertex_index: usize,
     new_point: Point,
   ) -> bool {
-    match measurement {
-      Measurement::Linear { line, polyline, .. } => {
-        if let Some(l) = line {
-          match vertex_index {
-            0 => l.start = new_point,
-            1 => l.end = new_point,
-            _ => return false,
-          }
-          true
-        } else if let Some(poly) = polyline {
-          if let Some(point) = poly.points.get_mut(vertex_index) {
-            *point = new_point;
-            true
-          } else {
-            false
-          }
-        } else {
-          false
-        }
-      }
-      Measurement::Area {
-        rectangle, polygon, ..
-      } => {
-        if let Some(rect) = rectangle {
-          match vertex_index {
-            0 => rect.start = new_point,
-            1 => rect.end = new_point,
-            _ => return false,
-          }
-          true
-        } else if let Some(poly) = polygon {
-          if let Some(point) = poly.points.get_mut(vertex_index) {
-            *point = new_point;
-            true
-          } else {
-            false
-          }
-        } else {
-          false
-        }
-      }
-      Measurement::Count { point, .. } => {
-        if vertex_index == 0 {
-          *point = new_point;
-          true
-        } else {
-          false
-        }
-      }
-    }
+    measurement.set_vertex(vertex_index, new_point)
+  }
+
+  /// Add a vertex where a point hits an edge of a measurement
+  ///
+  /// Returns the index of the new vertex, or `None` if no edge is within the threshold.
+  pub fn insert_vertex_at(
+    &self,
+    measurement: &mut Measurement,
+    point: Point,
+    transform: Transform,
+  ) -> Option<usize> {
+    let threshold = self.vertex_threshold / transform.scale;
+    let edge_index = self.find_nearest_edge(point, measurement, threshold)?;
+    measurement.insert_vertex(edge_index, point)
+  }
+
+  /// Remove a vertex from a measurement, keeping the minimum its shape needs
+  pub fn remove_vertex(&self, measurement: &mut Measurement, vertex_index: usize) -> bool {
+    measurement.remove_vertex(vertex_index)
+  }
+
+  /// Find every measurement at a given point, best hit first
+  ///
+  /// Vertex hits come before edge hits, which come before interior hits.
+  /// Within a kind, measurements later in the list are drawn on top and come first.
+  pub fn find_candidates(
+    &self,
+    point: Point,
+    measurements: &[Measurement],
+    transform: Transform,
+  ) -> Vec<SelectionResult> {
+    self.rank_hits(point, measurements.iter().rev(), transform)
+  }
+
+  /// Like `find_candidates`, but only hit-tests measurements the index finds near the point
+  ///
+  /// `index` must be built over `measurements`.
+  pub fn find_candidates_indexed(
+    &self,
+    point: Point,
+    measurements: &[Measurement],
+    index: &SpatialIndex,
+    transform: Transform,
+  ) -> Vec<SelectionResult> {
+    let ids = index.query_point(point, self.vertex_threshold / transform.scale);
+    let nearby = SpatialIndex::resolve(measurements, &ids);
+    self.rank_hits(point, nearby.into_iter().rev(), transform)
+  }
+
+  /// Hit-test measurements given top to bottom and sort the hits by kind
+  fn rank_hits<'a>(
+    &self,
+    point: Point,
+    measurements: impl Iterator<Item = &'a Measurement>,
+    transform: Transform,
+  ) -> Vec<SelectionResult> {
+    // Adjust threshold for zoom
+    let threshold = self.vertex_threshold / transform.scale;
+    let mut candidates: Vec<SelectionResult> = measurements
+      .filter_map(|measurement| self.hit_test(point, measurement, threshold))
+      .collect();
+    // Stable, so z-order is kept within each kind
+    candidates.sort_by_key(|candidate| candidate.hit_kind);
+    candidates
   }
 
   /// Find the measurement and vertex at a given point
@@ -159,28 +246,170 @@ impl SelectionTool {
     measurements: &[Measurement],
     transform: Transform,
   ) -> Option<SelectionResult> {
-    // Convert point from screen to world coordinates if needed
-    // For now, assume point is in world coordinates
-    let world_point = point;
-
-    // Check each measurement for nearby vertices
-    for measurement in measurements {
-      let vertex_index = self.find_nearest_vertex(
-        world_point,
-        measurement,
-        self.vertex_threshold / transform.scale, // Adjust threshold for zoom
-      );
-
-      if let Some(index) = vertex_index {
-        return Some(SelectionResult {
-          measurement_id: measurement.id().to_string(),
-          vertex_index: Some(index),
-          point: world_point,
-        });
+    self
+      .find_candidates(point, measurements, transform)
+      .into_iter()
+      .next()
+  }
+
+  /// Like `find_selection`, using a spatial index of the measurements
+  pub fn find_selection_indexed(
+    &self,
+    point: Point,
+    measurements: &[Measurement],
+    index: &SpatialIndex,
+    transform: Transform,
+  ) -> Option<SelectionResult> {
+    self
+      .find_candidates_indexed(point, measurements, index, transform)
+      .into_iter()
+      .next()
+  }
+
+  /// Select the measurement at a point, remembering it as the current selection
+  ///
+  /// Clicking again at the same spot cycles through overlapping measurements.
+  pub fn select_at(
+    &mut self,
+    point: Point,
+    measurements: &[Measurement],
+    transform: Transform,
+  ) -> Option<SelectionResult> {
+    let candidates = self.find_candidates(point, measurements, transform);
+    self.select_candidate(point, candidates, transform)
+  }
+
+  /// Like `select_at`, using a spatial index of the measurements
+  pub fn select_at_indexed(
+    &mut self,
+    point: Point,
+    measurements: &[Measurement],
+    index: &SpatialIndex,
+    transform: Transform,
+  ) -> Option<SelectionResult> {
+    let candidates = self.find_candidates_indexed(point, measurements, index, transform);
+    self.select_candidate(point, candidates, transform)
+  }
+
+  fn select_candidate(
+    &mut self,
+    point: Point,
+    candidates: Vec<SelectionResult>,
+    transform: Transform,
+  ) -> Option<SelectionResult> {
+    let same_spot = self
+      .last_click
+      .is_some_and(|last| last.distance_to(&point) <= self.vertex_threshold / transform.scale);
+    self.cycle_index = if same_spot && !candidates.is_empty() {
+      (self.cycle_index + 1) % candidates.len()
+    } else {
+      0
+    };
+    self.last_click = Some(point);
+
+    let selection = candidates.into_iter().nth(self.cycle_index);
+    self.selected_measurement_id = selection.as_ref().map(|s| s.measurement_id.clone());
+    self.selected_vertex_index = selection.as_ref().and_then(|s| s.vertex_index);
+    self.selected_ids = self.selected_measurement_id.iter().cloned().collect();
+    selection
+  }
+
+  /// Select the measurements picked by a rectangle dragged from `start` to `end`
+  ///
+  /// Returns the whole selection set afterwards.
+  pub fn select_in_rect<'a>(
+    &mut self,
+    start: Point,
+    end: Point,
+    measurements: impl IntoIterator<Item = &'a Measurement>,
+    marquee: MarqueeMode,
+    mode: SelectionMode,
+  ) -> &[String] {
+    let region = [
+      start,
+      Point::new(end.x, start.y),
+      end,
+      Point::new(start.x, end.y),
+    ];
+    self.select_in_lasso(&region, measurements, marquee, mode)
+  }
+
+  /// Select the measurements picked by a freeform lasso, closed back to its first point
+  ///
+  /// Returns the whole selection set afterwards.
+  pub fn select_in_lasso<'a>(
+    &mut self,
+    points: &[Point],
+    measurements: impl IntoIterator<Item = &'a Measurement>,
+    marquee: MarqueeMode,
+    mode: SelectionMode,
+  ) -> &[String] {
+    let picked: Vec<String> = if points.len() < 3 {
+      Vec::new()
+    } else {
+      let region = Region::new(points);
+      measurements
+        .into_iter()
+        .filter(|measurement| region.picks(measurement, marquee))
+        .map(|measurement| measurement.id().to_string())
+        .collect()
+    };
+    self.apply_selection(picked, mode)
+  }
+
+  /// Combine measurement IDs with the selection set, returning the set afterwards
+  pub fn apply_selection(
+    &mut self,
+    ids: impl IntoIterator<Item = String>,
+    mode: SelectionMode,
+  ) -> &[String] {
+    if mode == SelectionMode::Replace {
+      self.selected_ids.clear();
+      self.selected_measurement_id = None;
+    }
+    for id in ids {
+      let position = self
+        .selected_ids
+        .iter()
+        .position(|selected| *selected == id);
+      match (mode, position) {
+        (SelectionMode::Replace | SelectionMode::Add | SelectionMode::Toggle, None) => {
+          self.selected_ids.push(id)
+        }
+        (SelectionMode::Remove | SelectionMode::Toggle, Some(index)) => {
+          self.selected_ids.remove(index);
+        }
+        _ => {}
       }
     }
+    self.keep_primary_in_set();
+    &self.selected_ids
+  }
+
+  /// Drop selected IDs for which `keep` is false, such as measurements that no longer exist
+  pub fn retain_selected(&mut self, keep: impl Fn(&str) -> bool) {
+    self.selected_ids.retain(|id| keep(id));
+    self.keep_primary_in_set();
+  }
+
+  fn keep_primary_in_set(&mut self) {
+    let primary_kept = self
+      .selected_measurement_id
+      .as_ref()
+      .is_some_and(|id| self.selected_ids.contains(id));
+    if !primary_kept {
+      self.selected_measurement_id = self.selected_ids.last().cloned();
+      self.selected_vertex_index = None;
+    }
+  }
+
+  /// Every selected measurement ID, in the order they were selected
+  pub fn selected_ids(&self) -> &[String] {
+    &self.selected_ids
+  }
 
-    None
+  pub fn is_selected(&self, id: &str) -> bool {
+    self.selected_ids.iter().any(|selected| selected == id)
   }
 
   pub fn get_selected_measurement_id(&self) -> Option<&String> {
@@ -194,6 +423,75 @@ impl SelectionTool {
   pub fn clear_selection(&mut self) {
     self.selected_measurement_id = None;
     self.selected_vertex_index = None;
+    self.last_click = None;
+    self.cycle_index = 0;
+    self.selected_ids.clear();
+  }
+}
+
+/// Index of the smallest distance within the threshold
+fn nearest_within(distances: impl Iterator<Item = f64>, threshold: f64) -> Option<usize> {
+  distances
+    .enumerate()
+    .filter(|(_, distance)| *distance <= threshold)
+    .min_by(|a, b| a.1.total_cmp(&b.1))
+    .map(|(index, _)| index)
+}
+
+/// A closed marquee or lasso outline
+struct Region {
+  shape: GeoPolygon<f64>,
+  edges: Vec<ArcSegment>,
+}
+
+impl Region {
+  fn new(points: &[Point]) -> Self {
+    let ring: Vec<(f64, f64)> = points.iter().map(|p| (p.x, p.y)).collect();
+    let edges = points
+      .iter()
+      .zip(points.iter().cycle().skip(1))
+      .map(|(start, end)| ArcSegment::new(*start, *end, 0.0))
+      .collect();
+    Self {
+      shape: GeoPolygon::new(LineString::from(ring), vec![]),
+      edges,
+    }
+  }
+
+  fn contains(&self, point: &Point) -> bool {
+    self.shape.contains(&GeoPoint::new(point.x, point.y))
+  }
+
+  fn picks(&self, measurement: &Measurement, marquee: MarqueeMode) -> bool {
+    let segments = measurement.segments();
+    let mut points = measurement.vertices();
+    for segment in segments.iter().filter(|segment| !segment.is_straight()) {
+      points.extend(segment.flatten());
+    }
+    let crosses = segments.iter().any(|segment| {
+      self
+        .edges
+        .iter()
+        .any(|edge| !segment.intersections(edge).is_empty())
+    });
+    match marquee {
+      MarqueeMode::Window => !crosses && points.iter().all(|point| self.contains(point)),
+      MarqueeMode::Crossing => {
+        crosses
+          || points.iter().any(|point| self.contains(point))
+          // A lasso drawn entirely inside an area still touches it
+          || self
+            .edges
+            .first()
+            .is_some_and(|edge| measurement.contains_point(&edge.start))
+      }
+    }
+  }
+}
+
+impl Default for SelectionTool {
+  fn default() -> Self {
+    Self::new(10.0)
   }
 }
 
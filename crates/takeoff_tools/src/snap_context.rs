@@ -0,0 +1,45 @@
+use takeoff_core::{Point, SnapResult, Snapper};
+
+/// Snapping state of a tool: its snapper, if snapping is on, and the last snap
+#[derive(Debug, Clone, Default)]
+pub struct SnapContext {
+  pub snapper: Option<Snapper>,
+  /// Snap applied to the last pointer event
+  pub last_snap: Option<SnapResult>,
+}
+
+impl SnapContext {
+  /// Snap a pointer position, using `anchor` (the previous point) for ortho and
+  /// angle locks, and remember the result
+  ///
+  /// Without a snapper the point is returned unchanged.
+  pub fn snap(&mut self, point: Point, anchor: Option<Point>) -> Point {
+    self.last_snap = self
+      .snapper
+      .as_ref()
+      .map(|snapper| snapper.snap(point, anchor));
+    self.last_snap.as_ref().map_or(point, |snap| snap.point)
+  }
+}
+
+/// Tools that snap pointer positions through a `SnapContext`
+pub trait SnappingTool {
+  fn snap_context(&self) -> &SnapContext;
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext;
+
+  /// Snap pointer positions with `snapper`, or turn snapping off with `None`
+  fn set_snapper(&mut self, snapper: Option<Snapper>) {
+    self.snap_context_mut().snapper = snapper;
+  }
+
+  /// Get the snapper to update its measurements or view scale
+  fn snapper_mut(&mut self) -> Option<&mut Snapper> {
+    self.snap_context_mut().snapper.as_mut()
+  }
+
+  /// Snap applied to the last pointer event, so the UI can draw an indicator
+  fn last_snap(&self) -> Option<&SnapResult> {
+    self.snap_context().last_snap.as_ref()
+  }
+}
@@ -0,0 +1,236 @@
+use crate::{SnapContext, SnappingTool, TakeoffTool, ToolAction};
+use takeoff_core::{Measurement, Point};
+
+/// What dragging with the transform tool does to the target measurements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformMode {
+  /// Move by the drag offset
+  Move,
+  /// Turn about the pivot by the angle swept by the drag
+  Rotate,
+  /// Flip across the line from the drag start to the pointer
+  Mirror,
+  /// Grow or shrink about the pivot by the ratio of pointer to drag start distance
+  Scale,
+}
+
+/// A move, rotation, mirror or scale worked out from a drag
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transformation {
+  Translate {
+    dx: f64,
+    dy: f64,
+  },
+  Rotate {
+    pivot: Point,
+    degrees: f64,
+  },
+  /// Flip across the line through `a` and `b`
+  Mirror {
+    a: Point,
+    b: Point,
+  },
+  Scale {
+    origin: Point,
+    factor: f64,
+  },
+}
+
+impl Transformation {
+  /// Apply to a measurement, returning false if it can't be transformed this way
+  pub fn apply(&self, measurement: &mut Measurement) -> bool {
+    match *self {
+      Transformation::Translate { dx, dy } => {
+        measurement.translate(dx, dy);
+        true
+      }
+      Transformation::Rotate { pivot, degrees } => {
+        measurement.rotate(pivot, degrees);
+        true
+      }
+      Transformation::Mirror { a, b } => measurement.mirror(a, b),
+      Transformation::Scale { origin, factor } => measurement.scale_about(origin, factor),
+    }
+  }
+}
+
+/// Tool for moving, rotating, mirroring and scaling whole measurements
+///
+/// Set the measurements to transform with `set_targets`, then drag. Rotate and
+/// scale work about the pivot, which defaults to the center of the targets.
+/// When rotating, the snapper's angle lock is measured around the pivot.
+///
+/// The tool keeps the target IDs and commits a `Transformation` for them, so
+/// the host applies it to its current measurements. Its own copies of the
+/// targets only draw previews.
+pub struct TransformTool {
+  mode: TransformMode,
+  target_ids: Vec<String>,
+  /// Copies of the targets for previews and the default pivot
+  previews: Vec<Measurement>,
+  pivot: Option<Point>,
+  base_point: Option<Point>,
+  snapping: SnapContext,
+}
+
+impl TransformTool {
+  pub fn new(mode: TransformMode) -> Self {
+    Self {
+      mode,
+      target_ids: Vec::new(),
+      previews: Vec::new(),
+      pivot: None,
+      base_point: None,
+      snapping: SnapContext::default(),
+    }
+  }
+
+  pub fn mode(&self) -> TransformMode {
+    self.mode
+  }
+
+  pub fn set_mode(&mut self, mode: TransformMode) {
+    self.mode = mode;
+    self.base_point = None;
+  }
+
+  /// Set the measurements the next drag transforms
+  ///
+  /// Call again whenever the targets change outside the tool, so previews and
+  /// the default pivot follow them.
+  pub fn set_targets(&mut self, targets: &[Measurement]) {
+    self.target_ids = targets.iter().map(|m| m.id().to_string()).collect();
+    self.previews = targets.to_vec();
+    self.base_point = None;
+  }
+
+  /// IDs of the measurements the tool transforms
+  pub fn target_ids(&self) -> &[String] {
+    &self.target_ids
+  }
+
+  /// Set the point to rotate and scale about, or `None` for the center of the targets
+  pub fn set_pivot(&mut self, pivot: Option<Point>) {
+    self.pivot = pivot;
+  }
+
+  /// The point rotate and scale work about
+  pub fn pivot(&self) -> Option<Point> {
+    self
+      .pivot
+      .or_else(|| Measurement::center_of(&self.previews))
+  }
+
+  fn snap(&mut self, point: Point) -> Point {
+    let anchor = match self.mode {
+      TransformMode::Rotate | TransformMode::Scale => self.pivot(),
+      TransformMode::Move | TransformMode::Mirror => self.base_point,
+    };
+    self.snapping.snap(point, anchor)
+  }
+
+  /// The transformation of a drag from the base point to `point`
+  fn transformation(&self, point: Point) -> Option<Transformation> {
+    let base = self.base_point?;
+    Some(match self.mode {
+      TransformMode::Move => Transformation::Translate {
+        dx: point.x - base.x,
+        dy: point.y - base.y,
+      },
+      TransformMode::Rotate => {
+        let pivot = self.pivot()?;
+        let swept =
+          (point.y - pivot.y).atan2(point.x - pivot.x) - (base.y - pivot.y).atan2(base.x - pivot.x);
+        Transformation::Rotate {
+          pivot,
+          degrees: swept.to_degrees(),
+        }
+      }
+      TransformMode::Mirror => Transformation::Mirror { a: base, b: point },
+      TransformMode::Scale => {
+        let origin = self.pivot()?;
+        Transformation::Scale {
+          origin,
+          factor: origin.distance_to(&point) / origin.distance_to(&base),
+        }
+      }
+    })
+  }
+
+  /// The previews with a transformation applied, or `None` if any can't take it
+  fn transformed(&self, transformation: Transformation) -> Option<Vec<Measurement>> {
+    let mut measurements = self.previews.clone();
+    for measurement in &mut measurements {
+      if !transformation.apply(measurement) {
+        return None;
+      }
+    }
+    Some(measurements)
+  }
+}
+
+impl SnappingTool for TransformTool {
+  fn snap_context(&self) -> &SnapContext {
+    &self.snapping
+  }
+
+  fn snap_context_mut(&mut self) -> &mut SnapContext {
+    &mut self.snapping
+  }
+}
+
+impl TakeoffTool for TransformTool {
+  fn name(&self) -> &'static str {
+    "transform"
+  }
+
+  fn on_mouse_down(&mut self, point: Point) -> ToolAction {
+    if !self.target_ids.is_empty() {
+      self.base_point = Some(self.snap(point));
+    }
+    ToolAction::None
+  }
+
+  fn on_mouse_move(&mut self, point: Point) -> ToolAction {
+    if self.base_point.is_none() {
+      return ToolAction::None;
+    }
+    let point = self.snap(point);
+    match self
+      .transformation(point)
+      .and_then(|transformation| self.transformed(transformation))
+    {
+      Some(measurements) => ToolAction::UpdatePreviews(measurements),
+      None => ToolAction::None,
+    }
+  }
+
+  fn on_mouse_up(&mut self, point: Point) -> ToolAction {
+    if self.base_point.is_none() {
+      return ToolAction::None;
+    }
+    let point = self.snap(point);
+    let transformation = self.transformation(point);
+    self.base_point = None;
+    let Some(transformation) = transformation else {
+      return ToolAction::None;
+    };
+    match self.transformed(transformation) {
+      Some(previews) => {
+        // Keep previews in step so the next drag continues from the result
+        self.previews = previews;
+        ToolAction::TransformMeasurements(self.target_ids.clone(), transformation)
+      }
+      None => ToolAction::None,
+    }
+  }
+
+  fn cancel(&mut self) -> ToolAction {
+    self.base_point = None;
+    ToolAction::Cancel
+  }
+
+  fn is_drawing(&self) -> bool {
+    self.base_point.is_some()
+  }
+}
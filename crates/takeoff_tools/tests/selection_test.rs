@@ -1,5 +1,7 @@
-use takeoff_core::{Line, Measurement, Point, Rectangle, Scale, Transform, Unit};
-use takeoff_tools::SelectionTool;
+use takeoff_core::{
+  Circle, Line, Measurement, Point, Polygon, Rectangle, Scale, SpatialIndex, Transform, Unit,
+};
+use takeoff_tools::{HitKind, MarqueeMode, SelectionMode, SelectionTool};
 
 #[test]
 fn test_find_selection_linear_line() {
@@ -12,6 +14,10 @@ fn test_find_selection_linear_line() {
     polyline: None,
     scale: Scale::new(1.0, 1.0, Unit::Meters),
     display_unit: Unit::Meters,
+    width: None,
+    depth: None,
+    pitch: None,
+    metadata: None,
   };
 
   let measurements = vec![measurement];
@@ -43,8 +49,13 @@ fn test_find_selection_area_rectangle() {
       Point::new(100.0, 100.0),
     )),
     polygon: None,
+    circle: None,
+    ellipse: None,
     scale: Scale::new(1.0, 1.0, Unit::Meters),
     display_unit: Unit::Meters,
+    depth: None,
+    pitch: None,
+    metadata: None,
   };
 
   let measurements = vec![measurement];
@@ -66,6 +77,10 @@ fn test_update_vertex_linear_line() {
     polyline: None,
     scale: Scale::new(1.0, 1.0, Unit::Meters),
     display_unit: Unit::Meters,
+    width: None,
+    depth: None,
+    pitch: None,
+    metadata: None,
   };
 
   // Update start vertex
@@ -93,6 +108,10 @@ fn test_get_measurement_vertices() {
     polyline: None,
     scale: Scale::new(1.0, 1.0, Unit::Meters),
     display_unit: Unit::Meters,
+    width: None,
+    depth: None,
+    pitch: None,
+    metadata: None,
   };
 
   let vertices = tool.get_measurement_vertices(&measurement);
@@ -100,3 +119,449 @@ fn test_get_measurement_vertices() {
   assert_eq!(vertices[0], Point::new(10.0, 10.0));
   assert_eq!(vertices[1], Point::new(50.0, 50.0));
 }
+
+#[test]
+fn test_update_vertex_polygon_hole() {
+  let tool = SelectionTool::new(10.0);
+  let mut measurement = Measurement::Area {
+    id: "slab".to_string(),
+    rectangle: None,
+    polygon: Some(Polygon::with_holes(
+      vec![
+        Point::new(0.0, 0.0),
+        Point::new(100.0, 0.0),
+        Point::new(100.0, 100.0),
+      ],
+      vec![vec![
+        Point::new(50.0, 20.0),
+        Point::new(60.0, 20.0),
+        Point::new(60.0, 30.0),
+      ]],
+    )),
+    circle: None,
+    ellipse: None,
+    scale: Scale::new(1.0, 1.0, Unit::Meters),
+    display_unit: Unit::Meters,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  };
+
+  // Hole vertices follow the outer ring
+  let vertices = tool.get_measurement_vertices(&measurement);
+  assert_eq!(vertices.len(), 6);
+  assert_eq!(vertices[4], Point::new(60.0, 20.0));
+
+  let selection = tool
+    .find_selection(
+      Point::new(60.0, 21.0),
+      std::slice::from_ref(&measurement),
+      Transform::identity(),
+    )
+    .unwrap();
+  assert_eq!(selection.vertex_index, Some(4));
+
+  assert!(tool.update_vertex(&mut measurement, 4, Point::new(70.0, 20.0)));
+  assert_eq!(
+    tool.get_vertex(&measurement, 4),
+    Some(Point::new(70.0, 20.0))
+  );
+  assert!(!tool.update_vertex(&mut measurement, 6, Point::new(0.0, 0.0)));
+}
+
+fn wall(id: &str, start: Point, end: Point) -> Measurement {
+  Measurement::Linear {
+    id: id.to_string(),
+    line: Some(Line::new(start, end)),
+    polyline: None,
+    scale: Scale::new(1.0, 1.0, Unit::Meters),
+    display_unit: Unit::Meters,
+    width: None,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  }
+}
+
+fn room(id: &str, start: Point, end: Point) -> Measurement {
+  Measurement::Area {
+    id: id.to_string(),
+    rectangle: Some(Rectangle::new(start, end)),
+    polygon: None,
+    circle: None,
+    ellipse: None,
+    scale: Scale::new(1.0, 1.0, Unit::Meters),
+    display_unit: Unit::Meters,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  }
+}
+
+#[test]
+fn test_find_selection_edge_and_interior() {
+  let tool = SelectionTool::new(10.0);
+  let transform = Transform::identity();
+  let measurements = vec![
+    wall("wall", Point::new(0.0, 300.0), Point::new(200.0, 300.0)),
+    room("room", Point::new(0.0, 0.0), Point::new(100.0, 100.0)),
+  ];
+
+  let selection = tool
+    .find_selection(Point::new(100.0, 304.0), &measurements, transform)
+    .unwrap();
+  assert_eq!(selection.measurement_id, "wall");
+  assert_eq!(selection.hit_kind, HitKind::Edge);
+  assert_eq!(selection.edge_index, Some(0));
+  assert_eq!(selection.vertex_index, None);
+
+  // Right side of the rectangle
+  let selection = tool
+    .find_selection(Point::new(97.0, 50.0), &measurements, transform)
+    .unwrap();
+  assert_eq!(selection.hit_kind, HitKind::Edge);
+  assert_eq!(selection.edge_index, Some(1));
+
+  let selection = tool
+    .find_selection(Point::new(50.0, 50.0), &measurements, transform)
+    .unwrap();
+  assert_eq!(selection.measurement_id, "room");
+  assert_eq!(selection.hit_kind, HitKind::Interior);
+
+  assert!(tool
+    .find_selection(Point::new(150.0, 150.0), &measurements, transform)
+    .is_none());
+
+  // Zoomed out, the threshold covers more of the page
+  let zoomed_out = Transform::new(0.25, 0.0, 0.0);
+  let selection = tool
+    .find_selection(Point::new(100.0, 330.0), &measurements, zoomed_out)
+    .unwrap();
+  assert_eq!(selection.hit_kind, HitKind::Edge);
+}
+
+#[test]
+fn test_find_selection_priority_and_z_order() {
+  let tool = SelectionTool::new(10.0);
+  let transform = Transform::identity();
+  let measurements = vec![
+    room("bottom", Point::new(0.0, 0.0), Point::new(100.0, 100.0)),
+    room("top", Point::new(50.0, 50.0), Point::new(150.0, 150.0)),
+    wall("wall", Point::new(60.0, 60.0), Point::new(60.0, 90.0)),
+  ];
+
+  // An edge beats the interiors it sits on
+  let candidates = tool.find_candidates(Point::new(62.0, 75.0), &measurements, transform);
+  let ids: Vec<&str> = candidates
+    .iter()
+    .map(|c| c.measurement_id.as_str())
+    .collect();
+  assert_eq!(ids, vec!["wall", "top", "bottom"]);
+
+  // The rectangle drawn last is on top
+  let selection = tool
+    .find_selection(Point::new(75.0, 75.0), &measurements, transform)
+    .unwrap();
+  assert_eq!(selection.measurement_id, "top");
+}
+
+#[test]
+fn test_select_at_cycles_overlapping() {
+  let mut tool = SelectionTool::new(10.0);
+  let transform = Transform::identity();
+  let measurements = vec![
+    room("bottom", Point::new(0.0, 0.0), Point::new(100.0, 100.0)),
+    room("top", Point::new(50.0, 50.0), Point::new(150.0, 150.0)),
+  ];
+
+  let click = Point::new(75.0, 75.0);
+  let first = tool.select_at(click, &measurements, transform).unwrap();
+  assert_eq!(first.measurement_id, "top");
+  assert_eq!(tool.get_selected_measurement_id().unwrap(), "top");
+
+  let second = tool
+    .select_at(Point::new(76.0, 75.0), &measurements, transform)
+    .unwrap();
+  assert_eq!(second.measurement_id, "bottom");
+
+  let third = tool.select_at(click, &measurements, transform).unwrap();
+  assert_eq!(third.measurement_id, "top");
+
+  // Clicking somewhere else starts again from the top
+  tool.select_at(click, &measurements, transform);
+  let elsewhere = tool
+    .select_at(Point::new(120.0, 120.0), &measurements, transform)
+    .unwrap();
+  assert_eq!(elsewhere.measurement_id, "top");
+
+  assert!(tool
+    .select_at(Point::new(500.0, 500.0), &measurements, transform)
+    .is_none());
+  assert!(tool.get_selected_measurement_id().is_none());
+}
+
+#[test]
+fn test_indexed_selection_matches_linear() {
+  let mut tool = SelectionTool::new(10.0);
+  let transform = Transform::identity();
+  let mut measurements = vec![
+    room("bottom", Point::new(0.0, 0.0), Point::new(100.0, 100.0)),
+    wall("wall", Point::new(0.0, 50.0), Point::new(200.0, 50.0)),
+    room("top", Point::new(50.0, 50.0), Point::new(150.0, 150.0)),
+  ];
+  let mut index = SpatialIndex::from_measurements(&measurements);
+
+  for click in [
+    Point::new(75.0, 75.0),
+    Point::new(120.0, 52.0),
+    Point::new(151.0, 149.0),
+    Point::new(500.0, 500.0),
+  ] {
+    assert_eq!(
+      tool.find_candidates_indexed(click, &measurements, &index, transform),
+      tool.find_candidates(click, &measurements, transform)
+    );
+  }
+
+  // Edits are picked up once the index is updated
+  measurements[0] = room("bottom", Point::new(300.0, 300.0), Point::new(400.0, 400.0));
+  index.insert(&measurements[0]);
+  let click = Point::new(350.0, 350.0);
+  let selection = tool
+    .select_at_indexed(click, &measurements, &index, transform)
+    .unwrap();
+  assert_eq!(selection.measurement_id, "bottom");
+  assert_eq!(selection.hit_kind, HitKind::Interior);
+  assert_eq!(tool.get_selected_measurement_id().unwrap(), "bottom");
+}
+
+fn marker(id: &str, x: f64, y: f64) -> Measurement {
+  Measurement::Count {
+    id: id.to_string(),
+    point: Point::new(x, y),
+    metadata: None,
+  }
+}
+
+#[test]
+fn test_marquee_window_and_crossing() {
+  let mut tool = SelectionTool::new(10.0);
+  let measurements = vec![
+    room("inside", Point::new(10.0, 10.0), Point::new(40.0, 40.0)),
+    wall(
+      "straddling",
+      Point::new(50.0, 50.0),
+      Point::new(150.0, 50.0),
+    ),
+    marker("count", 80.0, 20.0),
+    room(
+      "around",
+      Point::new(-100.0, -100.0),
+      Point::new(300.0, 300.0),
+    ),
+  ];
+
+  assert_eq!(
+    MarqueeMode::from_drag(Point::new(0.0, 0.0), Point::new(100.0, 100.0)),
+    MarqueeMode::Window
+  );
+  assert_eq!(
+    MarqueeMode::from_drag(Point::new(100.0, 100.0), Point::new(0.0, 0.0)),
+    MarqueeMode::Crossing
+  );
+
+  let window = tool.select_in_rect(
+    Point::new(0.0, 0.0),
+    Point::new(100.0, 100.0),
+    &measurements,
+    MarqueeMode::Window,
+    SelectionMode::Replace,
+  );
+  assert_eq!(window, ["inside", "count"]);
+
+  let crossing = tool.select_in_rect(
+    Point::new(100.0, 100.0),
+    Point::new(0.0, 0.0),
+    &measurements,
+    MarqueeMode::Crossing,
+    SelectionMode::Replace,
+  );
+  assert_eq!(crossing, ["inside", "straddling", "count", "around"]);
+  assert_eq!(tool.get_selected_measurement_id().unwrap(), "around");
+}
+
+#[test]
+fn test_lasso_and_modifiers() {
+  let mut tool = SelectionTool::new(10.0);
+  let transform = Transform::identity();
+  let measurements = vec![
+    marker("a", 10.0, 5.0),
+    marker("b", 90.0, 5.0),
+    marker("c", 50.0, 80.0),
+  ];
+  // A triangle around the bottom two markers only
+  let lasso = [
+    Point::new(0.0, 0.0),
+    Point::new(100.0, 0.0),
+    Point::new(50.0, 40.0),
+  ];
+  let selected = tool.select_in_lasso(
+    &lasso,
+    &measurements,
+    MarqueeMode::Window,
+    SelectionMode::Replace,
+  );
+  assert_eq!(selected, ["a", "b"]);
+
+  tool.apply_selection(["c".to_string()], SelectionMode::Add);
+  assert_eq!(tool.selected_ids(), ["a", "b", "c"]);
+  tool.apply_selection(["a".to_string(), "d".to_string()], SelectionMode::Remove);
+  assert_eq!(tool.selected_ids(), ["b", "c"]);
+  tool.apply_selection(["b".to_string(), "a".to_string()], SelectionMode::Toggle);
+  assert_eq!(tool.selected_ids(), ["c", "a"]);
+  assert!(tool.is_selected("a"));
+
+  // Removed measurements drop out of the set and the primary selection
+  tool.retain_selected(|id| id != "a");
+  assert_eq!(tool.selected_ids(), ["c"]);
+  assert_eq!(
+    tool.get_selected_measurement_id().map(String::as_str),
+    Some("c")
+  );
+
+  // A plain click replaces the set
+  tool.select_at(Point::new(90.0, 5.0), &measurements, transform);
+  assert_eq!(tool.selected_ids(), ["b"]);
+
+  // Lassos need at least three points
+  assert!(tool
+    .select_in_lasso(
+      &lasso[..2],
+      &measurements,
+      MarqueeMode::Crossing,
+      SelectionMode::Replace
+    )
+    .is_empty());
+  assert!(tool.get_selected_measurement_id().is_none());
+}
+
+#[test]
+fn test_insert_and_remove_vertex() {
+  let tool = SelectionTool::new(10.0);
+  let mut measurement = room("room", Point::new(0.0, 0.0), Point::new(100.0, 100.0));
+
+  // Too far from any edge
+  assert_eq!(
+    tool.insert_vertex_at(
+      &mut measurement,
+      Point::new(50.0, 50.0),
+      Transform::identity()
+    ),
+    None
+  );
+  let index = tool
+    .insert_vertex_at(
+      &mut measurement,
+      Point::new(50.0, 4.0),
+      Transform::identity(),
+    )
+    .unwrap();
+  assert_eq!(index, 1);
+  assert_eq!(
+    tool.get_vertex(&measurement, 1),
+    Some(Point::new(50.0, 0.0))
+  );
+  assert_eq!(measurement.vertices().len(), 5);
+  assert_eq!(measurement.real_area().unwrap(), 10000.0);
+
+  assert!(tool.update_vertex(&mut measurement, 1, Point::new(50.0, 50.0)));
+  assert_eq!(measurement.real_area().unwrap(), 7500.0);
+  assert!(tool.remove_vertex(&mut measurement, 1));
+  assert_eq!(measurement.real_area().unwrap(), 10000.0);
+  assert!(tool.remove_vertex(&mut measurement, 0));
+  assert!(!tool.remove_vertex(&mut measurement, 0));
+}
+
+#[test]
+fn test_rotated_rectangle_handles() {
+  let tool = SelectionTool::new(10.0);
+  let transform = Transform::identity();
+  let mut measurement = Measurement::Area {
+    id: "wing".to_string(),
+    rectangle: Some(Rectangle::from_baseline(
+      Point::new(0.0, 0.0),
+      Point::new(80.0, 60.0),
+      Point::new(-30.0, 40.0),
+    )),
+    polygon: None,
+    circle: None,
+    ellipse: None,
+    scale: Scale::new(1.0, 1.0, Unit::Meters),
+    display_unit: Unit::Meters,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  };
+
+  // Every corner is a handle
+  let corners = tool.get_measurement_vertices(&measurement);
+  assert_eq!(corners.len(), 4);
+  let selection = tool
+    .find_selection(
+      Point::new(-28.0, 41.0),
+      std::slice::from_ref(&measurement),
+      transform,
+    )
+    .unwrap();
+  assert_eq!(selection.hit_kind, HitKind::Vertex);
+  assert_eq!(selection.vertex_index, Some(3));
+
+  // Dragging a corner keeps the opposite one and the angle
+  assert!(tool.update_vertex(&mut measurement, 1, Point::new(40.0, 30.0)));
+  let moved = tool.get_measurement_vertices(&measurement);
+  assert!(moved[3].distance_to(&corners[3]) < 1e-9);
+  assert!(moved[1].distance_to(&Point::new(40.0, 30.0)) < 1e-9);
+  assert!((measurement.pixel_area().unwrap() - 2500.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_circle_radius_handles() {
+  let tool = SelectionTool::new(10.0);
+  let transform = Transform::identity();
+  let mut measurement = Measurement::Area {
+    id: "column".to_string(),
+    rectangle: None,
+    polygon: None,
+    circle: Some(Circle::new(Point::new(100.0, 100.0), 20.0)),
+    ellipse: None,
+    scale: Scale::new(1.0, 1.0, Unit::Meters),
+    display_unit: Unit::Meters,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  };
+
+  // The center and the four quadrant points are handles
+  assert_eq!(tool.get_measurement_vertices(&measurement).len(), 5);
+  let selection = tool
+    .find_selection(
+      Point::new(101.0, 121.0),
+      std::slice::from_ref(&measurement),
+      transform,
+    )
+    .unwrap();
+  assert_eq!(selection.hit_kind, HitKind::Vertex);
+  assert_eq!(selection.vertex_index, Some(2));
+
+  // Dragging a quadrant point sets the radius, the center stays put
+  assert!(tool.update_vertex(&mut measurement, 2, Point::new(100.0, 130.0)));
+  let Measurement::Area {
+    circle: Some(circle),
+    ..
+  } = &measurement
+  else {
+    panic!("expected a circle");
+  };
+  assert_eq!(circle.center, Point::new(100.0, 100.0));
+  assert!((circle.radius - 30.0).abs() < 1e-9);
+}
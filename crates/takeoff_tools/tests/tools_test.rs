@@ -1,4 +1,7 @@
-use takeoff_core::{Point, Unit};
+use takeoff_core::{
+  validate_area, Measurement, Point, Polygon, Rectangle, Scale, ScaleRegion, SnapSettings,
+  SnapType, Snapper, Unit, ValidationIssueKind,
+};
 use takeoff_tools::*;
 
 #[test]
@@ -52,6 +55,108 @@ fn test_rectangle_tool() {
   assert!(!tool.is_drawing());
 }
 
+#[test]
+fn test_rectangle_tool_rotated() {
+  let area_of = |action: ToolAction| match action {
+    ToolAction::CreateMeasurement(m) => m.pixel_area().unwrap(),
+    action => panic!("expected a measurement, got {action:?}"),
+  };
+
+  // Dragged corner to corner on a grid turned a quarter turn
+  let mut tool = RectangleTool::new(None, Unit::Meters);
+  tool.set_rotation(Some(90.0));
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  assert!((area_of(tool.on_mouse_up(Point::new(-5.0, 10.0))) - 50.0).abs() < 1e-9);
+
+  // Three clicks: a 3-4-5 baseline, then a point 2 away from it
+  tool.set_rotation(None);
+  tool.set_three_point(true);
+  assert_eq!(tool.on_mouse_down(Point::new(0.0, 0.0)), ToolAction::None);
+  assert_eq!(tool.on_mouse_up(Point::new(0.0, 0.0)), ToolAction::None);
+  assert_eq!(tool.on_mouse_down(Point::new(4.0, 3.0)), ToolAction::None);
+  match tool.on_mouse_move(Point::new(2.8, 4.6)) {
+    ToolAction::UpdatePreview(Measurement::Area {
+      rectangle: Some(rect),
+      ..
+    }) => {
+      assert!((rect.width() - 5.0).abs() < 1e-9);
+      assert!((rect.height() - 2.0).abs() < 1e-9);
+    }
+    action => panic!("expected a preview, got {action:?}"),
+  }
+  assert!(tool.is_drawing());
+  assert!((area_of(tool.on_mouse_down(Point::new(2.8, 4.6))) - 10.0).abs() < 1e-9);
+  assert!(!tool.is_drawing());
+}
+
+#[test]
+fn test_circle_tool() {
+  let created = |action: ToolAction| match action {
+    ToolAction::CreateMeasurement(m) => m,
+    action => panic!("expected a measurement, got {action:?}"),
+  };
+
+  let mut tool = CircleTool::new(None, Unit::Meters);
+  assert_eq!(tool.name(), "circle");
+  assert_eq!(tool.mode(), CircleMode::CenterRadius);
+
+  // Center, then a point on the circle
+  assert_eq!(tool.on_mouse_down(Point::new(10.0, 10.0)), ToolAction::None);
+  assert!(tool.is_drawing());
+  assert!(matches!(
+    tool.on_mouse_move(Point::new(13.0, 14.0)),
+    ToolAction::UpdatePreview(_)
+  ));
+  assert_eq!(tool.on_mouse_up(Point::new(13.0, 14.0)), ToolAction::None);
+  // Clicking the center again would make an empty circle
+  assert_eq!(tool.on_mouse_down(Point::new(10.0, 10.0)), ToolAction::None);
+  let m = created(tool.on_mouse_down(Point::new(13.0, 14.0)));
+  assert!(!tool.is_drawing());
+  match &m {
+    Measurement::Area {
+      id,
+      circle: Some(circle),
+      ..
+    } => {
+      assert!(id.starts_with("circle_"));
+      assert!((circle.radius - 5.0).abs() < 1e-9);
+    }
+    m => panic!("expected a circle, got {m:?}"),
+  }
+  let area = m.pixel_area().unwrap();
+  assert!((area - 25.0 * std::f64::consts::PI).abs() < 1e-9);
+
+  // Three points on the circle
+  tool.set_mode(CircleMode::ThreePoint);
+  tool.on_mouse_down(Point::new(5.0, 0.0));
+  tool.on_mouse_down(Point::new(0.0, 5.0));
+  // A third point in line with the others makes no circle
+  assert_eq!(tool.on_mouse_down(Point::new(-5.0, 10.0)), ToolAction::None);
+  let m = created(tool.on_mouse_down(Point::new(-5.0, 0.0)));
+  assert!((m.pixel_area().unwrap() - area).abs() < 1e-9);
+
+  // Center, end of the first axis, then the second radius
+  tool.set_mode(CircleMode::Ellipse);
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  tool.on_mouse_down(Point::new(0.0, 4.0));
+  match created(tool.on_mouse_down(Point::new(2.0, 1.0))) {
+    Measurement::Area {
+      id,
+      ellipse: Some(ellipse),
+      ..
+    } => {
+      assert!(id.starts_with("ellipse_"));
+      assert!((ellipse.radius_x - 4.0).abs() < 1e-9);
+      assert!((ellipse.radius_y - 2.0).abs() < 1e-9);
+    }
+    m => panic!("expected an ellipse, got {m:?}"),
+  }
+
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  assert_eq!(tool.cancel(), ToolAction::Cancel);
+  assert!(!tool.is_drawing());
+}
+
 #[test]
 fn test_count_tool() {
   let mut tool = CountTool::new();
@@ -117,6 +222,146 @@ fn test_polygon_tool_cancel() {
   assert!(!tool.is_drawing());
 }
 
+#[test]
+fn test_polygon_tool_cutout_mode() {
+  let mut tool = PolygonTool::new(None, Unit::Meters);
+  let target = Measurement::Area {
+    id: "slab".to_string(),
+    rectangle: None,
+    polygon: Some(Polygon::new(vec![
+      Point::new(0.0, 0.0),
+      Point::new(100.0, 0.0),
+      Point::new(100.0, 100.0),
+      Point::new(0.0, 100.0),
+    ])),
+    circle: None,
+    ellipse: None,
+    scale: Scale::new(1.0, 1.0, Unit::Meters),
+    display_unit: Unit::Meters,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  };
+  assert!(tool.set_cutout_target(Some(&target)));
+  assert!(tool.is_cutout_mode());
+
+  tool.on_mouse_down(Point::new(20.0, 20.0));
+  tool.on_mouse_down(Point::new(40.0, 20.0));
+  tool.on_mouse_down(Point::new(40.0, 40.0));
+  tool.on_mouse_down(Point::new(20.0, 40.0));
+  let action = tool.on_mouse_down(Point::new(20.5, 20.5));
+
+  match action {
+    ToolAction::AddCutouts(id, rings) => {
+      assert_eq!(id, "slab");
+      assert_eq!(rings.len(), 1);
+      // The hole is cut out of the target as it is now, not when cutout mode started
+      let mut target = target;
+      assert!(target.add_cutout(rings[0].clone()));
+      assert_eq!(target.pixel_area().unwrap(), 10000.0 - 400.0);
+    }
+    _ => panic!("Closing a ring in cutout mode should cut it out of the target"),
+  }
+  assert!(!tool.is_drawing());
+  assert!(tool.is_cutout_mode());
+
+  assert!(tool.set_cutout_target(None));
+  assert!(!tool.is_cutout_mode());
+}
+
+#[test]
+fn test_polygon_tool_invalid_policy() {
+  let draw_bow_tie = |tool: &mut PolygonTool| {
+    tool.on_mouse_down(Point::new(0.0, 0.0));
+    tool.on_mouse_down(Point::new(100.0, 100.0));
+    tool.on_mouse_down(Point::new(100.0, 0.0));
+    tool.on_mouse_down(Point::new(0.0, 100.0));
+    tool.on_mouse_down(Point::new(1.0, 1.0))
+  };
+
+  // Allowed by default, with the crossing reported
+  let mut tool = PolygonTool::new(None, Unit::Meters);
+  assert!(matches!(
+    draw_bow_tie(&mut tool),
+    ToolAction::CreateMeasurement(_)
+  ));
+  assert_eq!(tool.validation_issues().len(), 1);
+  assert_eq!(
+    tool.validation_issues()[0].kind,
+    ValidationIssueKind::SelfIntersection
+  );
+
+  // Rejected shapes stay open
+  tool.set_invalid_policy(InvalidPolygonPolicy::Reject);
+  assert_eq!(draw_bow_tie(&mut tool), ToolAction::None);
+  assert!(tool.is_drawing());
+  tool.cancel();
+
+  // Repaired shapes become one measurement per valid part
+  tool.set_invalid_policy(InvalidPolygonPolicy::Repair);
+  match draw_bow_tie(&mut tool) {
+    ToolAction::CreateMeasurements(parts) => {
+      assert_eq!(parts.len(), 2);
+      for part in &parts {
+        assert!(validate_area(part).unwrap().is_empty());
+        assert!((part.pixel_area().unwrap() - 2500.0).abs() < 1e-6);
+      }
+    }
+    action => panic!("expected two measurements, got {action:?}"),
+  }
+  assert!(!tool.is_drawing());
+
+  // Valid shapes are created as drawn whatever the policy
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  tool.on_mouse_down(Point::new(100.0, 0.0));
+  tool.on_mouse_down(Point::new(100.0, 100.0));
+  let action = tool.on_mouse_down(Point::new(1.0, 1.0));
+  assert!(matches!(action, ToolAction::CreateMeasurement(_)));
+  assert!(tool.validation_issues().is_empty());
+}
+
+#[test]
+fn test_polyline_tool_arc_mode() {
+  let mut tool = PolylineTool::new(None, Unit::Meters);
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  tool.on_mouse_down(Point::new(10.0, 0.0));
+
+  // Half circle from (10,0) through (15,-5) to (20,0)
+  tool.set_arc_mode(true);
+  assert!(tool.is_arc_mode());
+  tool.on_mouse_down(Point::new(15.0, -5.0));
+  tool.on_mouse_down(Point::new(20.0, 0.0));
+
+  match tool.cancel() {
+    ToolAction::CreateMeasurement(measurement) => {
+      let expected = 10.0 + 5.0 * std::f64::consts::PI;
+      assert!((measurement.pixel_length().unwrap() - expected).abs() < 1e-9);
+    }
+    _ => panic!("Finishing a polyline should create a measurement"),
+  }
+}
+
+#[test]
+fn test_polygon_tool_arc_mode() {
+  let mut tool = PolygonTool::new(None, Unit::Meters);
+  tool.set_arc_mode(true);
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  // Arc from (0,0) through (10,-10) to (20,0), then straight edges
+  tool.on_mouse_down(Point::new(10.0, -10.0));
+  tool.on_mouse_down(Point::new(20.0, 0.0));
+  tool.set_arc_mode(false);
+  tool.on_mouse_down(Point::new(20.0, 20.0));
+  tool.on_mouse_down(Point::new(0.0, 20.0));
+
+  match tool.on_mouse_down(Point::new(0.5, 0.5)) {
+    ToolAction::CreateMeasurement(measurement) => {
+      let expected = 400.0 + 50.0 * std::f64::consts::PI;
+      assert!((measurement.pixel_area().unwrap() - expected).abs() < 1e-9);
+    }
+    _ => panic!("Closing a polygon should create a measurement"),
+  }
+}
+
 #[test]
 fn test_tool_cancel() {
   let mut scale_tool = ScaleTool::new();
@@ -127,3 +372,221 @@ fn test_tool_cancel() {
   assert!(matches!(action, ToolAction::Cancel));
   assert!(!scale_tool.is_drawing());
 }
+
+#[test]
+fn test_tools_use_scale_regions() {
+  let page = Scale::new(12.0, 1.0, Unit::Feet);
+  let detail = Scale::new(48.0, 1.0, Unit::Feet);
+  let regions = vec![ScaleRegion::from_rectangle(
+    "detail",
+    Rectangle::new(Point::new(100.0, 100.0), Point::new(200.0, 200.0)),
+    detail,
+  )];
+
+  let mut line_tool = LineTool::new(Some(page), Unit::Feet);
+  line_tool.set_scale_regions(regions.clone());
+  line_tool.on_mouse_down(Point::new(110.0, 110.0));
+  match line_tool.on_mouse_up(Point::new(150.0, 110.0)) {
+    ToolAction::CreateMeasurement(Measurement::Linear { scale, .. }) => assert_eq!(scale, detail),
+    _ => panic!("Line tool should create a linear measurement"),
+  }
+  assert!(line_tool.scale_warning().is_none());
+
+  // A line leaving the detail keeps the detail scale but warns
+  line_tool.on_mouse_down(Point::new(110.0, 110.0));
+  line_tool.on_mouse_up(Point::new(300.0, 110.0));
+  let warning = line_tool.scale_warning().unwrap();
+  assert_eq!(warning.region_ids, vec!["detail".to_string()]);
+
+  let mut polygon_tool = PolygonTool::new(Some(page), Unit::Feet);
+  polygon_tool.set_scale_regions(regions);
+  polygon_tool.on_mouse_down(Point::new(0.0, 0.0));
+  polygon_tool.on_mouse_down(Point::new(50.0, 0.0));
+  polygon_tool.on_mouse_down(Point::new(50.0, 50.0));
+  match polygon_tool.on_mouse_down(Point::new(0.0, 0.0)) {
+    ToolAction::CreateMeasurement(Measurement::Area { scale, .. }) => assert_eq!(scale, page),
+    _ => panic!("Polygon tool should create an area measurement"),
+  }
+  assert!(polygon_tool.scale_warning().is_none());
+}
+
+#[test]
+fn test_tools_snap_to_measurements() {
+  let wall = Measurement::Linear {
+    id: "wall".to_string(),
+    line: Some(takeoff_core::Line::new(
+      Point::new(0.0, 0.0),
+      Point::new(100.0, 0.0),
+    )),
+    polyline: None,
+    scale: Scale::new(1.0, 1.0, Unit::Feet),
+    display_unit: Unit::Feet,
+    width: None,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  };
+  let mut snapper = Snapper::new(SnapSettings {
+    ortho: true,
+    ..SnapSettings::default()
+  });
+  snapper.set_measurements(&[wall]);
+
+  let mut line_tool = LineTool::new(None, Unit::Feet);
+  line_tool.set_snapper(Some(snapper.clone()));
+  line_tool.on_mouse_down(Point::new(97.0, 2.0));
+  assert_eq!(
+    line_tool.last_snap().unwrap().snap_type,
+    Some(SnapType::Vertex)
+  );
+  match line_tool.on_mouse_up(Point::new(106.0, 60.0)) {
+    ToolAction::CreateMeasurement(Measurement::Linear {
+      line: Some(line), ..
+    }) => {
+      assert_eq!(line.start, Point::new(100.0, 0.0));
+      // Ortho pulls the off-axis end onto the vertical through the start
+      assert_eq!(line.end, Point::new(100.0, 60.0));
+    }
+    _ => panic!("Line tool should create a linear measurement"),
+  }
+  assert_eq!(
+    line_tool.last_snap().unwrap().snap_type,
+    Some(SnapType::Ortho)
+  );
+
+  // Zooming in shrinks the snap distance in world units
+  line_tool.snapper_mut().unwrap().set_view_scale(10.0);
+  line_tool.on_mouse_down(Point::new(97.0, 2.0));
+  assert!(line_tool.last_snap().unwrap().snap_type.is_none());
+  line_tool.cancel();
+
+  let mut polygon_tool = PolygonTool::new(None, Unit::Feet);
+  polygon_tool.set_snapper(Some(snapper.clone()));
+  polygon_tool.on_mouse_down(Point::new(49.0, 3.0));
+  polygon_tool.on_mouse_move(Point::new(52.0, 41.0));
+  assert_eq!(
+    polygon_tool.last_snap().unwrap().snap_type,
+    Some(SnapType::Ortho)
+  );
+  polygon_tool.on_mouse_down(Point::new(52.0, 41.0));
+  polygon_tool.on_mouse_down(Point::new(10.0, 44.0));
+  match polygon_tool.on_mouse_down(Point::new(50.0, 1.0)) {
+    ToolAction::CreateMeasurement(Measurement::Area {
+      polygon: Some(polygon),
+      ..
+    }) => assert_eq!(
+      polygon.points,
+      vec![
+        Point::new(50.0, 0.0),
+        Point::new(50.0, 41.0),
+        Point::new(10.0, 41.0)
+      ]
+    ),
+    _ => panic!("Polygon tool should create an area measurement"),
+  }
+
+  let mut count_tool = CountTool::new();
+  count_tool.set_snapper(Some(snapper));
+  count_tool.on_mouse_down(Point::new(33.0, 4.0));
+  match count_tool.on_mouse_up(Point::new(33.0, 4.0)) {
+    ToolAction::CreateMeasurement(Measurement::Count { point, .. }) => {
+      assert_eq!(point, Point::new(33.0, 0.0))
+    }
+    _ => panic!("Count tool should create a count"),
+  }
+  assert_eq!(
+    count_tool.last_snap().unwrap().snap_type,
+    Some(SnapType::Edge)
+  );
+}
+
+#[test]
+fn test_transform_tool() {
+  let room = Measurement::Area {
+    id: "room".to_string(),
+    rectangle: Some(Rectangle::new(Point::new(0.0, 0.0), Point::new(20.0, 10.0))),
+    polygon: None,
+    circle: None,
+    ellipse: None,
+    scale: Scale::new(1.0, 1.0, Unit::Feet),
+    display_unit: Unit::Feet,
+    depth: None,
+    pitch: None,
+    metadata: None,
+  };
+  let marker = Measurement::Count {
+    id: "marker".to_string(),
+    point: Point::new(30.0, 10.0),
+    metadata: None,
+  };
+
+  let mut live = vec![room, marker];
+  // Apply a committed transformation to the live measurements, as a host would
+  let commit = |live: &mut Vec<Measurement>, action| match action {
+    ToolAction::TransformMeasurements(ids, transformation) => {
+      assert_eq!(ids, ["room", "marker"]);
+      for measurement in live.iter_mut() {
+        assert!(transformation.apply(measurement));
+      }
+    }
+    _ => panic!("Dragging should transform both measurements"),
+  };
+
+  let mut tool = TransformTool::new(TransformMode::Move);
+  assert_eq!(tool.name(), "transform");
+  // Nothing to transform yet
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  assert!(!tool.is_drawing());
+
+  tool.set_targets(&live);
+  assert_eq!(tool.target_ids(), ["room", "marker"]);
+  assert_eq!(tool.pivot(), Some(Point::new(15.0, 5.0)));
+  // Edits made after picking the targets are kept by the commit
+  live[1].translate(0.0, 5.0);
+  tool.on_mouse_down(Point::new(5.0, 5.0));
+  assert!(tool.is_drawing());
+  let action = tool.on_mouse_move(Point::new(6.0, 5.0));
+  assert!(matches!(action, ToolAction::UpdatePreviews(ref m) if m.len() == 2));
+  let action = tool.on_mouse_up(Point::new(15.0, 10.0));
+  assert_eq!(
+    action,
+    ToolAction::TransformMeasurements(
+      vec!["room".to_string(), "marker".to_string()],
+      Transformation::Translate { dx: 10.0, dy: 5.0 }
+    )
+  );
+  commit(&mut live, action);
+  assert_eq!(live[0].vertices()[0], Point::new(10.0, 5.0));
+  assert_eq!(live[1].vertices(), vec![Point::new(40.0, 20.0)]);
+
+  // Rotate a quarter turn about an explicit pivot
+  tool.set_mode(TransformMode::Rotate);
+  tool.set_pivot(Some(Point::new(10.0, 5.0)));
+  tool.on_mouse_down(Point::new(20.0, 5.0));
+  commit(&mut live, tool.on_mouse_up(Point::new(10.0, 15.0)));
+  let marker = live[1].vertices()[0];
+  assert!((marker.x - -5.0).abs() < 1e-9);
+  assert!((marker.y - 35.0).abs() < 1e-9);
+  assert!(matches!(
+    &live[0],
+    Measurement::Area {
+      rectangle: Some(_),
+      ..
+    }
+  ));
+
+  // Dragging twice as far from the pivot doubles the size
+  tool.set_mode(TransformMode::Scale);
+  let area = live[0].pixel_area().unwrap();
+  tool.on_mouse_down(Point::new(15.0, 5.0));
+  commit(&mut live, tool.on_mouse_up(Point::new(20.0, 5.0)));
+  assert!((live[0].pixel_area().unwrap() - area * 4.0).abs() < 1e-9);
+
+  // A zero-length mirror axis does nothing
+  tool.set_mode(TransformMode::Mirror);
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  assert_eq!(tool.on_mouse_up(Point::new(0.0, 0.0)), ToolAction::None);
+  tool.on_mouse_down(Point::new(0.0, 0.0));
+  assert_eq!(tool.cancel(), ToolAction::Cancel);
+  assert!(!tool.is_drawing());
+}
@@ -5,39 +5,179 @@ use napi::{
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
 use std::{
+  collections::HashMap,
   sync::{Arc, Mutex},
   thread,
 };
-use takeoff_core::{Point, Scale, TakeoffState, Transform, Unit};
+use takeoff_core::{
+  boolean_op, offset_area, offset_linear, repair_area, validate_area, Assembly, BooleanOp,
+  Condition, ConditionTotals, CostSummary, DerivedItem, Dimension, Formula, GeometryConversion,
+  History, Measurement, MeasurementMetadata, OffsetOptions, PageKey, Pitch, Point, Scale,
+  ScaleRegion, ScaleRegionWarning, SnapResult, SnapSettings, Snapper, SpatialIndex, TakeoffProject,
+  TakeoffState, Transform, Unit, ValidationIssue,
+};
+use takeoff_tools::{HitKind, MarqueeMode, SelectionMode, SelectionResult, SelectionTool};
+
+/// Part of a measurement hit by a selection click
+#[napi(string_enum)]
+pub enum SelectionHitKind {
+  Vertex,
+  Edge,
+  Interior,
+}
+
+/// Measurement found under a selection click
+#[napi(object)]
+pub struct SelectionHit {
+  pub measurement_id: String,
+  pub vertex_index: Option<u32>,
+  pub edge_index: Option<u32>,
+  pub hit_kind: SelectionHitKind,
+}
+
+impl From<SelectionResult> for SelectionHit {
+  fn from(result: SelectionResult) -> Self {
+    Self {
+      measurement_id: result.measurement_id,
+      vertex_index: result.vertex_index.map(|index| index as u32),
+      edge_index: result.edge_index.map(|index| index as u32),
+      hit_kind: match result.hit_kind {
+        HitKind::Vertex => SelectionHitKind::Vertex,
+        HitKind::Edge => SelectionHitKind::Edge,
+        HitKind::Interior => SelectionHitKind::Interior,
+      },
+    }
+  }
+}
+
+/// How a selection combines with the current selection set
+#[napi(string_enum)]
+pub enum SelectionModifier {
+  /// Replace the set
+  Replace,
+  /// Add to the set (shift)
+  Add,
+  /// Remove from the set
+  Remove,
+  /// Flip each picked measurement in or out of the set (ctrl)
+  Toggle,
+}
+
+impl From<SelectionModifier> for SelectionMode {
+  fn from(modifier: SelectionModifier) -> Self {
+    match modifier {
+      SelectionModifier::Replace => SelectionMode::Replace,
+      SelectionModifier::Add => SelectionMode::Add,
+      SelectionModifier::Remove => SelectionMode::Remove,
+      SelectionModifier::Toggle => SelectionMode::Toggle,
+    }
+  }
+}
+
+/// Which measurements a marquee or lasso picks up
+#[napi(string_enum)]
+pub enum SelectionMarquee {
+  /// Only measurements entirely inside
+  Window,
+  /// Measurements inside or touching the boundary
+  Crossing,
+}
+
+impl From<SelectionMarquee> for MarqueeMode {
+  fn from(marquee: SelectionMarquee) -> Self {
+    match marquee {
+      SelectionMarquee::Window => MarqueeMode::Window,
+      SelectionMarquee::Crossing => MarqueeMode::Crossing,
+    }
+  }
+}
 
 #[napi]
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(from = "SavedEngine")]
 pub struct TakeoffEngine {
   state: TakeoffState,
   transform: Transform,
   scale: Option<Scale>,
   display_unit: Unit,
+  history: History,
+  project: TakeoffProject,
+  /// Undo history of each page other than the active one
+  page_histories: Vec<(PageKey, History)>,
+  snap_settings: SnapSettings,
 
+  #[serde(skip)]
+  selection_tool: SelectionTool,
+  /// Kept in sync with `state.measurements`
+  #[serde(skip)]
+  spatial_index: SpatialIndex,
+  /// Assembly items of each measurement that uses one, kept in sync with `state.measurements`
+  #[serde(skip)]
+  derived_items: HashMap<String, Vec<DerivedItem>>,
+  /// Parsed item formulas of each assembly, kept in sync with `state.assemblies`
+  #[serde(skip)]
+  formulas: HashMap<String, Vec<Option<Formula>>>,
   #[serde(skip)]
   transform_callbacks: Arc<Mutex<Vec<ThreadsafeFunction<Transform>>>>,
 }
 
+/// The parts of a `TakeoffEngine` that are saved; the caches are rebuilt on load
+#[derive(Deserialize)]
+struct SavedEngine {
+  state: TakeoffState,
+  transform: Transform,
+  scale: Option<Scale>,
+  display_unit: Unit,
+  history: History,
+  project: TakeoffProject,
+  #[serde(default)]
+  page_histories: Vec<(PageKey, History)>,
+  #[serde(default)]
+  snap_settings: SnapSettings,
+}
+
+impl From<SavedEngine> for TakeoffEngine {
+  fn from(saved: SavedEngine) -> Self {
+    let mut engine = Self {
+      state: saved.state,
+      history: saved.history,
+      project: saved.project,
+      page_histories: saved.page_histories,
+      snap_settings: saved.snap_settings,
+      ..Self::new(saved.transform, saved.scale, Some(saved.display_unit))
+    };
+    engine.parse_assemblies();
+    engine.rebuild_index();
+    engine
+  }
+}
+
 #[napi]
 impl TakeoffEngine {
   #[napi(constructor)]
   pub fn new(transform: Transform, scale: Option<Scale>, display_unit: Option<Unit>) -> Self {
+    let mut state = TakeoffState::new();
+    state.scale = scale;
     Self {
-      state: TakeoffState::new(),
+      state,
       transform,
       scale,
       display_unit: display_unit.unwrap_or(Unit::Feet),
+      history: History::new(),
+      project: TakeoffProject::new(),
+      page_histories: Vec::new(),
+      snap_settings: SnapSettings::default(),
+      selection_tool: SelectionTool::default(),
+      spatial_index: SpatialIndex::new(),
+      derived_items: HashMap::new(),
+      formulas: HashMap::new(),
       transform_callbacks: Arc::new(Mutex::new(Vec::new())),
     }
   }
 
   #[napi(getter)]
   pub fn display_unit(&self) -> Unit {
-    self.display_unit.clone()
+    self.display_unit
   }
   #[napi(setter)]
   pub fn set_display_unit(&mut self, display_unit: Unit) {
@@ -51,11 +191,14 @@ impl TakeoffEngine {
   #[napi(setter)]
   pub fn set_state(&mut self, state: TakeoffState) {
     self.state = state;
+    self.history.clear();
+    self.parse_assemblies();
+    self.rebuild_index();
   }
 
   #[napi(getter)]
   pub fn transform(&self) -> Transform {
-    self.transform.clone()
+    self.transform
   }
 
   fn call_transform_callbacks(&self, transform: Transform) {
@@ -75,7 +218,7 @@ impl TakeoffEngine {
 
   #[napi(getter)]
   pub fn scale(&self) -> Option<Scale> {
-    self.scale.clone()
+    self.scale
   }
   #[napi(setter)]
   pub fn set_scale(&mut self, scale: Either<Scale, Null>) {
@@ -83,6 +226,7 @@ impl TakeoffEngine {
       Either::A(scale) => Some(scale),
       Either::B(_) => None,
     };
+    self.history.set_scale(&mut self.state, self.scale);
   }
 
   #[napi]
@@ -91,6 +235,439 @@ impl TakeoffEngine {
   }
 }
 
+/// Selection methods
+#[napi]
+impl TakeoffEngine {
+  /// Select the measurement at a world point: vertices first, then edges, then interiors
+  ///
+  /// Clicking again at the same spot cycles through overlapping measurements.
+  /// With a modifier other than `Replace`, the top hit is added to, removed from
+  /// or toggled in the selection set instead.
+  #[napi]
+  pub fn select_at(
+    &mut self,
+    point: Point,
+    modifier: Option<SelectionModifier>,
+  ) -> Option<SelectionHit> {
+    let mode = modifier.map_or(SelectionMode::Replace, SelectionMode::from);
+    if mode == SelectionMode::Replace {
+      return self
+        .selection_tool
+        .select_at_indexed(
+          point,
+          &self.state.measurements,
+          &self.spatial_index,
+          self.transform,
+        )
+        .map(SelectionHit::from);
+    }
+    let hit = self.selection_tool.find_selection_indexed(
+      point,
+      &self.state.measurements,
+      &self.spatial_index,
+      self.transform,
+    )?;
+    self
+      .selection_tool
+      .apply_selection([hit.measurement_id.clone()], mode);
+    Some(hit.into())
+  }
+
+  /// Select with a rectangle dragged from `start` to `end`, returning the selection set
+  ///
+  /// Without a marquee mode, dragging left to right selects only measurements
+  /// entirely inside (window) and right to left also those it touches (crossing).
+  #[napi]
+  pub fn select_in_rect(
+    &mut self,
+    start: Point,
+    end: Point,
+    marquee: Option<SelectionMarquee>,
+    modifier: Option<SelectionModifier>,
+  ) -> Vec<String> {
+    let marquee = marquee.map_or(MarqueeMode::from_drag(start, end), MarqueeMode::from);
+    let ids = self.spatial_index.query_rect(
+      Point::new(start.x.min(end.x), start.y.min(end.y)),
+      Point::new(start.x.max(end.x), start.y.max(end.y)),
+    );
+    let nearby = SpatialIndex::resolve(&self.state.measurements, &ids);
+    self
+      .selection_tool
+      .select_in_rect(
+        start,
+        end,
+        nearby,
+        marquee,
+        modifier.map_or(SelectionMode::Replace, SelectionMode::from),
+      )
+      .to_vec()
+  }
+
+  /// Select with a freeform lasso, returning the selection set
+  ///
+  /// Lassos select only measurements entirely inside unless `marquee` is `Crossing`.
+  #[napi]
+  pub fn select_in_lasso(
+    &mut self,
+    points: Vec<Point>,
+    marquee: Option<SelectionMarquee>,
+    modifier: Option<SelectionModifier>,
+  ) -> Vec<String> {
+    let Some((min, max)) = Point::bounds(&points) else {
+      return self.selected_ids();
+    };
+    let ids = self.spatial_index.query_rect(min, max);
+    let nearby = SpatialIndex::resolve(&self.state.measurements, &ids);
+    self
+      .selection_tool
+      .select_in_lasso(
+        &points,
+        nearby,
+        marquee.map_or(MarqueeMode::Window, MarqueeMode::from),
+        modifier.map_or(SelectionMode::Replace, SelectionMode::from),
+      )
+      .to_vec()
+  }
+
+  /// IDs of every selected measurement, in the order they were selected
+  #[napi]
+  pub fn selected_ids(&self) -> Vec<String> {
+    self.selection_tool.selected_ids().to_vec()
+  }
+
+  /// Clear the current selection
+  #[napi]
+  pub fn clear_selection(&mut self) {
+    self.selection_tool.clear_selection();
+  }
+
+  /// Get the measurements whose bounds overlap a world rectangle, bottom to top
+  ///
+  /// Use with the visible part of the page to draw only what is on screen.
+  #[napi]
+  pub fn measurements_in_rect(&self, min: Point, max: Point) -> Vec<Measurement> {
+    let ids = self.spatial_index.query_rect(min, max);
+    SpatialIndex::resolve(&self.state.measurements, &ids)
+      .into_iter()
+      .cloned()
+      .collect()
+  }
+}
+
+/// Snapping methods
+#[napi]
+impl TakeoffEngine {
+  #[napi(getter)]
+  pub fn snap_settings(&self) -> SnapSettings {
+    self.snap_settings.clone()
+  }
+  #[napi(setter)]
+  pub fn set_snap_settings(&mut self, snap_settings: SnapSettings) {
+    self.snap_settings = snap_settings;
+  }
+
+  /// Snap a world point to the current measurements
+  ///
+  /// `anchor` is the previous point of the shape being drawn, for ortho and angle locks.
+  /// The tolerance is in screen pixels at the current zoom.
+  #[napi]
+  pub fn snap_point(&self, point: Point, anchor: Option<Point>) -> SnapResult {
+    let mut snapper = Snapper::new(self.snap_settings.clone());
+    snapper.set_view_scale(self.transform.scale);
+    let ids = self
+      .spatial_index
+      .query_point(point, snapper.search_radius());
+    snapper.set_measurements(SpatialIndex::resolve(&self.state.measurements, &ids));
+    snapper.snap(point, anchor)
+  }
+}
+
+/// Scale region methods
+#[napi]
+impl TakeoffEngine {
+  /// Add a region of the page with its own scale, replacing one with the same ID
+  #[napi]
+  pub fn add_scale_region(&mut self, region: ScaleRegion) {
+    self.history.add_scale_region(&mut self.state, region);
+  }
+
+  #[napi]
+  pub fn remove_scale_region(&mut self, id: String) -> bool {
+    self.history.remove_scale_region(&mut self.state, &id)
+  }
+
+  /// Get the scale that applies at a point
+  #[napi]
+  pub fn scale_at(&self, point: Point) -> Option<Scale> {
+    self.state.scale_at(&point)
+  }
+
+  /// Check whether a measurement crosses a scale region boundary
+  #[napi]
+  pub fn check_scale_regions(&self, measurement: Measurement) -> Option<ScaleRegionWarning> {
+    self.state.scale_region_warning(&measurement)
+  }
+}
+
+/// Condition methods
+#[napi]
+impl TakeoffEngine {
+  /// Add a condition, replacing one with the same ID
+  #[napi]
+  pub fn add_condition(&mut self, condition: Condition) {
+    self.history.add_condition(&mut self.state, condition);
+  }
+
+  /// Remove a condition; measurements assigned to it keep the ID but are left out of the totals
+  #[napi]
+  pub fn remove_condition(&mut self, id: String) -> bool {
+    self.history.remove_condition(&mut self.state, &id)
+  }
+
+  #[napi]
+  pub fn conditions(&self) -> Vec<Condition> {
+    self.state.conditions().to_vec()
+  }
+
+  /// Assign a measurement to a condition, or unassign it with `None`
+  ///
+  /// Fails if the condition does not exist or is taken off with another kind of measurement.
+  #[napi]
+  pub fn assign_condition(&mut self, measurement_id: String, condition_id: Option<String>) -> bool {
+    let Some(assign) = self.condition_assignment(condition_id) else {
+      return false;
+    };
+    let assigned = self
+      .history
+      .modify_measurement(&mut self.state, &measurement_id, assign);
+    self.reindex_measurement(&measurement_id);
+    assigned
+  }
+
+  /// Assign every selected measurement of the right kind to a condition, returning how many changed
+  #[napi]
+  pub fn assign_selected_condition(&mut self, condition_id: Option<String>) -> u32 {
+    match self.condition_assignment(condition_id) {
+      Some(assign) => self.modify_selected(assign),
+      None => 0,
+    }
+  }
+
+  /// Get the measurements assigned to a condition, bottom to top
+  #[napi]
+  pub fn measurements_for_condition(&self, condition_id: String) -> Vec<Measurement> {
+    self
+      .state
+      .measurements_for_condition(&condition_id)
+      .into_iter()
+      .cloned()
+      .collect()
+  }
+
+  /// Total length, area, volume and count of each condition, in condition order
+  #[napi]
+  pub fn condition_totals(&self) -> Vec<ConditionTotals> {
+    self.state.condition_totals()
+  }
+
+  /// Price the measurements with rates, line by line and by condition and trade
+  #[napi]
+  pub fn cost_summary(&self) -> Result<CostSummary> {
+    self.state.cost_summary().ok_or_else(|| {
+      Error::from_reason("A cost rate is not a decimal number or an amount is too large")
+    })
+  }
+}
+
+/// Assembly methods
+///
+/// A measurement that uses an assembly drives one quantity per assembly item,
+/// re-evaluated whenever the measurement or the assembly changes.
+#[napi]
+impl TakeoffEngine {
+  /// Add an assembly, replacing one with the same ID
+  ///
+  /// Fails, naming the items, if any item's formula does not parse.
+  #[napi]
+  pub fn add_assembly(&mut self, assembly: Assembly) -> Result<()> {
+    let formulas = assembly.formulas();
+    let invalid = assembly.invalid_items(&formulas);
+    if !invalid.is_empty() {
+      return Err(Error::from_reason(format!(
+        "Invalid formula in assembly items: {}",
+        invalid.join(", ")
+      )));
+    }
+    self.formulas.insert(assembly.id.clone(), formulas);
+    self.history.add_assembly(&mut self.state, assembly);
+    self.refresh_derived_items();
+    Ok(())
+  }
+
+  /// Remove an assembly; measurements using it keep the ID but drive no items
+  #[napi]
+  pub fn remove_assembly(&mut self, id: String) -> bool {
+    let removed = self.history.remove_assembly(&mut self.state, &id);
+    if removed {
+      self.formulas.remove(&id);
+      self.refresh_derived_items();
+    }
+    removed
+  }
+
+  #[napi]
+  pub fn assemblies(&self) -> Vec<Assembly> {
+    self.state.assemblies().to_vec()
+  }
+
+  /// Have a measurement use an assembly, or none with `None`
+  ///
+  /// Fails if the assembly does not exist.
+  #[napi]
+  pub fn assign_assembly(&mut self, measurement_id: String, assembly_id: Option<String>) -> bool {
+    if assembly_id
+      .as_deref()
+      .is_some_and(|id| self.state.assembly(id).is_none())
+    {
+      return false;
+    }
+    let modified =
+      self
+        .history
+        .modify_measurement(&mut self.state, &measurement_id, |measurement| {
+          let metadata = measurement.metadata_mut();
+          if metadata.assembly_id == assembly_id {
+            return false;
+          }
+          metadata.assembly_id = assembly_id;
+          true
+        });
+    if modified {
+      self.reindex_measurement(&measurement_id);
+    }
+    modified
+  }
+
+  /// Get the items a measurement drives through its assembly
+  ///
+  /// Empty if it uses no assembly. An item whose formula gives no finite number
+  /// for it has no quantity.
+  #[napi]
+  pub fn derived_items(&self, measurement_id: String) -> Vec<DerivedItem> {
+    self
+      .derived_items
+      .get(&measurement_id)
+      .cloned()
+      .unwrap_or_default()
+  }
+
+  /// Get the items every measurement drives, bottom to top
+  #[napi]
+  pub fn all_derived_items(&self) -> Vec<DerivedItem> {
+    self
+      .state
+      .measurements
+      .iter()
+      .filter_map(|m| self.derived_items.get(m.id()))
+      .flatten()
+      .cloned()
+      .collect()
+  }
+}
+
+/// Multi-page project methods
+///
+/// The engine works on one page at a time; switching pages stores the current
+/// state, transform and scale in the project and loads the other page's. Each
+/// page keeps its own undo history.
+#[napi]
+impl TakeoffEngine {
+  #[napi(getter)]
+  pub fn project(&self) -> TakeoffProject {
+    let mut project = self.project.clone();
+    project.insert_page(self.page_snapshot());
+    project
+  }
+  #[napi(setter)]
+  pub fn set_project(&mut self, project: TakeoffProject) {
+    let active = project.active_page().cloned();
+    self.project = project;
+    self.page_histories.clear();
+    if let Some(page) = active {
+      self.load_page(page);
+    }
+  }
+
+  /// Switch to a page, creating it if needed, and return its state
+  ///
+  /// The current state is only kept if it has a file ID and page number.
+  #[napi]
+  pub fn set_active_page(&mut self, file_id: String, page_number: u32) -> TakeoffState {
+    let snapshot = self.page_snapshot();
+    if let Some(key) = snapshot.page_key() {
+      let history = History::with_limit(self.history.limit());
+      let history = std::mem::replace(&mut self.history, history);
+      self.page_histories.retain(|(page, _)| *page != key);
+      self.page_histories.push((key, history));
+    }
+    self.project.insert_page(snapshot);
+    let page = self
+      .project
+      .set_active_page(PageKey::new(file_id, page_number))
+      .clone();
+    self.load_page(page);
+    self.state.clone()
+  }
+
+  /// Copy the active page's scale to other pages, returning how many were updated
+  #[napi]
+  pub fn copy_scale_to_pages(&mut self, pages: Vec<PageKey>) -> u32 {
+    let snapshot = self.page_snapshot();
+    let Some(source) = snapshot.page_key() else {
+      return 0;
+    };
+    self.project.insert_page(snapshot);
+    self.project.copy_scale_to_pages(&source, &pages) as u32
+  }
+
+  /// List pages that have no scale yet
+  #[napi]
+  pub fn pages_without_scale(&self) -> Vec<PageKey> {
+    self.project().pages_without_scale()
+  }
+
+  /// List every page in the project
+  #[napi]
+  pub fn page_keys(&self) -> Vec<PageKey> {
+    self.project().page_keys()
+  }
+
+  fn page_snapshot(&self) -> TakeoffState {
+    let mut state = self.state.clone();
+    state.transform = self.transform;
+    state
+  }
+
+  /// Make a page the current state, picking up its undo history if it had one
+  fn load_page(&mut self, state: TakeoffState) {
+    self.scale = state.scale;
+    self.set_transform(state.transform);
+    let limit = self.history.limit();
+    let stashed = state.page_key().and_then(|key| {
+      self
+        .page_histories
+        .iter()
+        .position(|(page, _)| *page == key)
+    });
+    self.history = stashed
+      .map(|index| self.page_histories.swap_remove(index).1)
+      .unwrap_or_else(|| History::with_limit(limit));
+    self.state = state;
+    self.parse_assemblies();
+    self.rebuild_index();
+  }
+}
+
 /// Coordinate transformation methods
 #[napi]
 impl TakeoffEngine {
@@ -162,3 +739,596 @@ impl TakeoffEngine {
     self.transform
   }
 }
+
+/// Measurement editing methods, recorded in the undo history
+#[napi]
+impl TakeoffEngine {
+  /// Add a measurement to the state
+  #[napi]
+  pub fn add_measurement(&mut self, measurement: Measurement) {
+    let id = measurement.id().to_string();
+    self.history.add_measurement(&mut self.state, measurement);
+    self.reindex_measurement(&id);
+  }
+
+  /// Remove a measurement by ID
+  #[napi]
+  pub fn remove_measurement(&mut self, id: String) -> bool {
+    let removed = self.history.remove_measurement(&mut self.state, &id);
+    self.reindex_measurement(&id);
+    removed
+  }
+
+  /// Remove all measurements
+  #[napi]
+  pub fn clear_measurements(&mut self) {
+    self.history.clear_measurements(&mut self.state);
+    self.rebuild_index();
+  }
+
+  /// Replace the measurement with the same ID
+  #[napi]
+  pub fn update_measurement(&mut self, measurement: Measurement) -> bool {
+    let id = measurement.id().to_string();
+    let updated = self
+      .history
+      .update_measurement(&mut self.state, measurement);
+    self.reindex_measurement(&id);
+    updated
+  }
+
+  /// Move a vertex of a measurement
+  ///
+  /// Wrap a drag in `beginTransaction`/`commitTransaction` so it undoes as one step.
+  #[napi]
+  pub fn update_vertex(&mut self, id: String, vertex_index: u32, point: Point) -> bool {
+    let selection_tool = &self.selection_tool;
+    let modified = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        selection_tool.update_vertex(measurement, vertex_index as usize, point)
+      });
+    self.reindex_measurement(&id);
+    modified
+  }
+
+  /// Add a vertex where a world point hits an edge of a measurement
+  ///
+  /// Returns the index of the new vertex, or `None` if the point is not on an edge.
+  #[napi]
+  pub fn insert_vertex(&mut self, id: String, point: Point) -> Option<u32> {
+    let (selection_tool, transform) = (&self.selection_tool, self.transform);
+    let mut inserted = None;
+    self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        inserted = selection_tool.insert_vertex_at(measurement, point, transform);
+        inserted.is_some()
+      });
+    self.reindex_measurement(&id);
+    inserted.map(|index| index as u32)
+  }
+
+  /// Remove a vertex, keeping at least 2 for polylines and 3 for polygon rings
+  #[napi]
+  pub fn remove_vertex(&mut self, id: String, vertex_index: u32) -> bool {
+    let selection_tool = &self.selection_tool;
+    let removed = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        selection_tool.remove_vertex(measurement, vertex_index as usize)
+      });
+    self.reindex_measurement(&id);
+    removed
+  }
+
+  /// Cut a hole out of an area measurement
+  #[napi]
+  pub fn add_cutout(&mut self, id: String, points: Vec<Point>) -> bool {
+    let modified = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        measurement.add_cutout(points)
+      });
+    self.reindex_measurement(&id);
+    modified
+  }
+
+  /// Cut several holes out of an area measurement as one undo step, such as the
+  /// rings of `ToolAction::AddCutouts`
+  ///
+  /// Rings outside the area or overlapping a hole are skipped.
+  #[napi]
+  pub fn add_cutouts(&mut self, id: String, rings: Vec<Vec<Point>>) -> bool {
+    let modified = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        let mut cut = false;
+        for ring in rings {
+          cut |= measurement.add_cutout(ring);
+        }
+        cut
+      });
+    self.reindex_measurement(&id);
+    modified
+  }
+
+  /// Set the depth of an area or linear measurement so it reports a volume
+  #[napi]
+  pub fn set_measurement_depth(&mut self, id: String, depth: Option<Dimension>) -> bool {
+    let modified = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        measurement.set_depth(depth)
+      });
+    self.reindex_measurement(&id);
+    modified
+  }
+
+  /// Set the width of a linear measurement so it reports a volume
+  #[napi]
+  pub fn set_measurement_width(&mut self, id: String, width: Option<Dimension>) -> bool {
+    let modified = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        measurement.set_width(width)
+      });
+    self.reindex_measurement(&id);
+    modified
+  }
+
+  /// Set the roof or ramp pitch of an area or linear measurement
+  #[napi]
+  pub fn set_measurement_pitch(&mut self, id: String, pitch: Option<Pitch>) -> bool {
+    let modified = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        measurement.set_pitch(pitch)
+      });
+    self.reindex_measurement(&id);
+    modified
+  }
+
+  /// Set the name, color, notes, tags and layer of a measurement, or clear them with `None`
+  #[napi]
+  pub fn set_measurement_metadata(
+    &mut self,
+    id: String,
+    metadata: Option<MeasurementMetadata>,
+  ) -> bool {
+    let modified = self
+      .history
+      .modify_measurement(&mut self.state, &id, |measurement| {
+        measurement.set_metadata(metadata);
+        true
+      });
+    self.reindex_measurement(&id);
+    modified
+  }
+
+  /// Get the measurements with a tag, ignoring case, bottom to top
+  #[napi]
+  pub fn measurements_with_tag(&self, tag: String) -> Vec<Measurement> {
+    self
+      .state
+      .measurements_with_tag(&tag)
+      .into_iter()
+      .cloned()
+      .collect()
+  }
+
+  /// Get the measurements on a layer, bottom to top
+  #[napi]
+  pub fn measurements_on_layer(&self, layer_id: String) -> Vec<Measurement> {
+    self
+      .state
+      .measurements_on_layer(&layer_id)
+      .into_iter()
+      .cloned()
+      .collect()
+  }
+
+  /// Delete every selected measurement as one undo step, returning how many were removed
+  #[napi]
+  pub fn delete_selected(&mut self) -> u32 {
+    let ids = self.selection_tool.selected_ids().to_vec();
+    self.history.begin_transaction();
+    let removed = ids
+      .iter()
+      .filter(|id| self.history.remove_measurement(&mut self.state, id))
+      .count();
+    self.history.commit_transaction();
+    self.rebuild_index();
+    self.selection_tool.clear_selection();
+    removed as u32
+  }
+
+  /// Set the display unit of every selected measurement, returning how many changed
+  ///
+  /// Counts have no unit and are skipped.
+  #[napi]
+  pub fn set_selected_display_unit(&mut self, display_unit: Unit) -> u32 {
+    self.modify_selected(|measurement| measurement.set_display_unit(display_unit))
+  }
+
+  /// Set the scale of every selected measurement, returning how many changed
+  ///
+  /// Counts have no scale and are skipped.
+  #[napi]
+  pub fn set_selected_scale(&mut self, scale: Scale) -> u32 {
+    self.modify_selected(|measurement| measurement.set_scale(scale))
+  }
+
+  /// Move every selected measurement to a layer, or off any layer with `None`, returning how many changed
+  #[napi]
+  pub fn set_selected_layer(&mut self, layer_id: Option<String>) -> u32 {
+    self.modify_selected(|measurement| {
+      measurement.metadata_mut().layer_id = layer_id.clone();
+      true
+    })
+  }
+
+  /// Move every selected measurement by an offset in world units, returning how many moved
+  #[napi]
+  pub fn move_selected(&mut self, delta_x: f64, delta_y: f64) -> u32 {
+    self.modify_selected(|measurement| {
+      measurement.translate(delta_x, delta_y);
+      true
+    })
+  }
+
+  /// Rotate every selected measurement counter-clockwise by `degrees`, returning how many turned
+  ///
+  /// Turns about `pivot`, or the center of the selection without one. Rectangles
+  /// become polygons unless the angle is a multiple of 90.
+  #[napi]
+  pub fn rotate_selected(&mut self, degrees: f64, pivot: Option<Point>) -> u32 {
+    let Some(pivot) = pivot.or_else(|| self.selection_center()) else {
+      return 0;
+    };
+    self.modify_selected(|measurement| {
+      measurement.rotate(pivot, degrees);
+      true
+    })
+  }
+
+  /// Mirror every selected measurement across the line through `a` and `b`, returning how many flipped
+  #[napi]
+  pub fn mirror_selected(&mut self, a: Point, b: Point) -> u32 {
+    self.modify_selected(|measurement| measurement.mirror(a, b))
+  }
+
+  /// Scale every selected measurement by `factor`, returning how many changed
+  ///
+  /// Scales about `origin`, or the center of the selection without one.
+  #[napi]
+  pub fn scale_selected(&mut self, factor: f64, origin: Option<Point>) -> u32 {
+    let Some(origin) = origin.or_else(|| self.selection_center()) else {
+      return 0;
+    };
+    self.modify_selected(|measurement| measurement.scale_about(origin, factor))
+  }
+
+  /// Combine two area measurements into new ones as one undo step
+  ///
+  /// The results are added on top and returned; the source areas are kept unless
+  /// `keep_sources` is false. Returns an empty list if either is not an area.
+  #[napi]
+  pub fn combine_areas(
+    &mut self,
+    a: String,
+    b: String,
+    op: BooleanOp,
+    keep_sources: Option<bool>,
+  ) -> Vec<Measurement> {
+    let (Some(first), Some(second)) = (self.state.measurement(&a), self.state.measurement(&b))
+    else {
+      return Vec::new();
+    };
+    let Some(results) = boolean_op(first, second, op) else {
+      return Vec::new();
+    };
+    self.history.begin_transaction();
+    if !keep_sources.unwrap_or(true) {
+      self.history.remove_measurement(&mut self.state, &a);
+      self.history.remove_measurement(&mut self.state, &b);
+    }
+    for measurement in &results {
+      self
+        .history
+        .add_measurement(&mut self.state, measurement.clone());
+    }
+    self.history.commit_transaction();
+    self.rebuild_index();
+    results
+  }
+
+  /// Offset a measurement into new area measurements as one undo step
+  ///
+  /// Lines and polylines become the area they cover at a total width of
+  /// `distance`, such as a wall footprint. Areas grow outward by a positive
+  /// distance or shrink inward by a negative one. The results are added on top
+  /// and returned; the source is kept unless `keep_source` is false.
+  #[napi]
+  pub fn offset_measurement(
+    &mut self,
+    id: String,
+    distance: Dimension,
+    options: Option<OffsetOptions>,
+    keep_source: Option<bool>,
+  ) -> Vec<Measurement> {
+    let Some(source) = self.state.measurement(&id) else {
+      return Vec::new();
+    };
+    let options = options.unwrap_or_default();
+    let results = match source {
+      Measurement::Linear { .. } => offset_linear(source, distance, &options),
+      _ => offset_area(source, distance, &options),
+    };
+    let Some(results) = results else {
+      return Vec::new();
+    };
+    self.history.begin_transaction();
+    if !keep_source.unwrap_or(true) {
+      self.history.remove_measurement(&mut self.state, &id);
+    }
+    for measurement in &results {
+      self
+        .history
+        .add_measurement(&mut self.state, measurement.clone());
+    }
+    self.history.commit_transaction();
+    self.rebuild_index();
+    results
+  }
+
+  /// Convert a measurement to another geometry kind in place
+  ///
+  /// The ID, scale and display unit are kept. Returns the converted measurement,
+  /// or `None` if it is not of the kind the conversion starts from.
+  #[napi]
+  pub fn convert_measurement(
+    &mut self,
+    id: String,
+    conversion: GeometryConversion,
+  ) -> Option<Measurement> {
+    let converted = self.state.measurement(&id)?.convert(conversion)?;
+    self
+      .history
+      .update_measurement(&mut self.state, converted.clone());
+    self.reindex_measurement(&id);
+    Some(converted)
+  }
+
+  /// Add linear measurements around the edges of an area as one undo step
+  ///
+  /// There is one closed polyline for the outer ring and one for each hole. The
+  /// area is kept. Returns the new measurements, or an empty list for other kinds.
+  #[napi]
+  pub fn add_perimeter_measurements(&mut self, id: String) -> Vec<Measurement> {
+    let Some(results) = self
+      .state
+      .measurement(&id)
+      .and_then(Measurement::perimeter_measurements)
+    else {
+      return Vec::new();
+    };
+    self.history.begin_transaction();
+    for measurement in &results {
+      self
+        .history
+        .add_measurement(&mut self.state, measurement.clone());
+      self.reindex_measurement(measurement.id());
+    }
+    self.history.commit_transaction();
+    results
+  }
+
+  /// Find self-intersections, repeated points, spikes and degenerate rings in an area
+  ///
+  /// Returns an empty list for valid areas and for measurements that are not areas.
+  #[napi]
+  pub fn validate_measurement(&self, id: String) -> Vec<ValidationIssue> {
+    self
+      .state
+      .measurement(&id)
+      .and_then(validate_area)
+      .unwrap_or_default()
+  }
+
+  /// Replace an invalid area with its valid parts as one undo step
+  ///
+  /// The parts are added on top and returned. A valid area is left as it is and
+  /// an empty list is returned, as it is for measurements that are not areas.
+  #[napi]
+  pub fn repair_measurement(&mut self, id: String) -> Vec<Measurement> {
+    let Some(source) = self.state.measurement(&id) else {
+      return Vec::new();
+    };
+    if validate_area(source).is_none_or(|issues| issues.is_empty()) {
+      return Vec::new();
+    }
+    let Some(results) = repair_area(source) else {
+      return Vec::new();
+    };
+    self.history.begin_transaction();
+    self.history.remove_measurement(&mut self.state, &id);
+    for measurement in &results {
+      self
+        .history
+        .add_measurement(&mut self.state, measurement.clone());
+    }
+    self.history.commit_transaction();
+    self.rebuild_index();
+    results
+  }
+
+  /// Group the following edits into a single undo step
+  #[napi]
+  pub fn begin_transaction(&mut self) {
+    self.history.begin_transaction();
+  }
+
+  /// Close the group started by `beginTransaction`
+  #[napi]
+  pub fn commit_transaction(&mut self) {
+    self.history.commit_transaction();
+  }
+
+  /// Revert and discard the edits made since the matching `beginTransaction`
+  #[napi]
+  pub fn rollback_transaction(&mut self) {
+    self.history.rollback_transaction(&mut self.state);
+    self.scale = self.state.scale;
+    self.parse_assemblies();
+    self.rebuild_index();
+  }
+
+  #[napi]
+  pub fn undo(&mut self) -> bool {
+    let undone = self.history.undo(&mut self.state);
+    self.scale = self.state.scale;
+    self.parse_assemblies();
+    self.rebuild_index();
+    undone
+  }
+
+  #[napi]
+  pub fn redo(&mut self) -> bool {
+    let redone = self.history.redo(&mut self.state);
+    self.scale = self.state.scale;
+    self.parse_assemblies();
+    self.rebuild_index();
+    redone
+  }
+
+  #[napi]
+  pub fn can_undo(&self) -> bool {
+    self.history.can_undo()
+  }
+
+  #[napi]
+  pub fn can_redo(&self) -> bool {
+    self.history.can_redo()
+  }
+
+  /// Set the maximum number of undo steps kept for each page
+  #[napi]
+  pub fn set_history_limit(&mut self, limit: u32) {
+    self.history.set_limit(limit as usize);
+    for (_, history) in &mut self.page_histories {
+      history.set_limit(limit as usize);
+    }
+  }
+}
+
+impl TakeoffEngine {
+  /// Apply an edit to every selected measurement as one undo step
+  fn modify_selected<F>(&mut self, edit: F) -> u32
+  where
+    F: Fn(&mut Measurement) -> bool,
+  {
+    let ids = self.selection_tool.selected_ids().to_vec();
+    self.history.begin_transaction();
+    let mut modified = 0;
+    for id in &ids {
+      if self
+        .history
+        .modify_measurement(&mut self.state, id, |measurement| edit(measurement))
+      {
+        self.reindex_measurement(id);
+        modified += 1;
+      }
+    }
+    self.history.commit_transaction();
+    modified
+  }
+
+  /// Edit that assigns a measurement to a condition, or `None` if the condition does not exist
+  fn condition_assignment(
+    &self,
+    condition_id: Option<String>,
+  ) -> Option<impl Fn(&mut Measurement) -> bool> {
+    let condition = match &condition_id {
+      Some(id) => Some(self.state.condition(id)?.clone()),
+      None => None,
+    };
+    Some(move |measurement: &mut Measurement| {
+      if condition.as_ref().is_some_and(|c| !c.accepts(measurement)) {
+        return false;
+      }
+      measurement.metadata_mut().condition_id = condition_id.clone();
+      true
+    })
+  }
+
+  /// Center of the combined bounds of the selected measurements
+  fn selection_center(&self) -> Option<Point> {
+    Measurement::center_of(
+      self
+        .selection_tool
+        .selected_ids()
+        .iter()
+        .filter_map(|id| self.state.measurement(id)),
+    )
+  }
+
+  /// Bring one measurement's entry in the spatial index up to date with the state,
+  /// and re-evaluate its assembly items
+  fn reindex_measurement(&mut self, id: &str) {
+    let measurement = self.state.measurements.iter().find(|m| m.id() == id);
+    match measurement.and_then(|m| self.evaluate_assembly(m)) {
+      Some(items) => self.derived_items.insert(id.to_string(), items),
+      None => self.derived_items.remove(id),
+    };
+    match measurement {
+      Some(measurement) => self.spatial_index.insert(measurement),
+      None => {
+        self.spatial_index.remove(id);
+        self
+          .selection_tool
+          .retain_selected(|selected| selected != id);
+      }
+    }
+  }
+
+  /// Rebuild the spatial index and assembly items after the measurement list
+  /// was replaced or reordered, deselecting measurements that are gone
+  fn rebuild_index(&mut self) {
+    self.spatial_index.rebuild(&self.state.measurements);
+    let state = &self.state;
+    self
+      .selection_tool
+      .retain_selected(|id| state.measurement(id).is_some());
+    self.refresh_derived_items();
+  }
+
+  /// Re-evaluate the assembly items of every measurement
+  fn refresh_derived_items(&mut self) {
+    self.derived_items = self
+      .state
+      .measurements
+      .iter()
+      .filter_map(|m| Some((m.id().to_string(), self.evaluate_assembly(m)?)))
+      .collect();
+  }
+
+  /// Parse the formulas of every assembly after the state was replaced, or
+  /// assemblies were undone or redone
+  fn parse_assemblies(&mut self) {
+    self.formulas = self
+      .state
+      .assemblies()
+      .iter()
+      .map(|assembly| (assembly.id.clone(), assembly.formulas()))
+      .collect();
+  }
+
+  /// Items a measurement drives through its assembly, using the parsed formulas
+  fn evaluate_assembly(&self, measurement: &Measurement) -> Option<Vec<DerivedItem>> {
+    let assembly_id = measurement.metadata()?.assembly_id.as_deref()?;
+    let assembly = self.state.assembly(assembly_id)?;
+    let formulas = self.formulas.get(assembly_id)?;
+    Some(assembly.evaluate_parsed(measurement, formulas))
+  }
+}
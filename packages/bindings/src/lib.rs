@@ -1,5 +1,7 @@
 pub mod engine;
 pub mod measurement;
+pub mod scale;
+pub mod units;
 // Re-export the crates for JavaScript bindings
 pub use engine::TakeoffEngine;
 use napi_derive::napi;
@@ -1,7 +1,11 @@
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 use serde::{Deserialize, Serialize};
-use takeoff_core::{Measurement, Scale, Unit};
+use takeoff_core::{
+  boolean_op, offset_area, offset_linear, repair_area, validate_area, BooleanOp, Dimension,
+  DisplayFormat, GeometryConversion, Measurement, MeasurementMetadata, OffsetOptions, Scale, Unit,
+  ValidationIssue, VolumeUnit,
+};
 
 #[napi]
 #[derive(Serialize, Deserialize, Clone)]
@@ -25,11 +29,21 @@ impl TakeoffMeasurement {
     self.measurement = measurement;
   }
 
+  /// Name, color, notes, tags and layer of the measurement
+  #[napi(getter)]
+  pub fn metadata(&self) -> Option<MeasurementMetadata> {
+    self.measurement.metadata().cloned()
+  }
+  #[napi(setter)]
+  pub fn set_metadata(&mut self, metadata: Option<MeasurementMetadata>) {
+    self.measurement.set_metadata(metadata);
+  }
+
   #[napi]
   pub fn calculate_area(&self, scale: Scale, target_unit: Unit) -> Result<f64> {
     let measurement = self.measurement.clone();
     let real_area = measurement
-      .real_area_with_target(&scale.clone().into(), &target_unit.into())
+      .real_area_with_target(&scale, &target_unit)
       .ok_or_else(|| Error::from_reason("Failed to calculate area"))?;
 
     Ok(real_area)
@@ -50,12 +64,96 @@ impl TakeoffMeasurement {
   #[napi]
   pub fn calculate_distance(&self, scale: Scale, target_unit: Unit) -> Result<f64> {
     let measurement = self.measurement.clone();
-    let pixel_distance = measurement
-      .pixel_length()
+    let real_distance = measurement
+      .real_length_with_target(&scale, &target_unit)
       .ok_or_else(|| Error::from_reason("Measurement is not a linear measurement"))?;
 
-    let real_distance = scale.pixel_to_real_unit(pixel_distance, target_unit);
-
     Ok(real_distance)
   }
+
+  /// Area projected onto the plan, ignoring any pitch
+  #[napi]
+  pub fn calculate_plan_area(&self, scale: Scale, target_unit: Unit) -> Result<f64> {
+    self
+      .measurement
+      .plan_area_with_target(&scale, &target_unit)
+      .ok_or_else(|| Error::from_reason("Measurement is not an area measurement"))
+  }
+
+  #[napi]
+  pub fn calculate_volume(&self, target_unit: VolumeUnit) -> Result<f64> {
+    self
+      .measurement
+      .real_volume_with_target(target_unit)
+      .ok_or_else(|| Error::from_reason("Measurement has no depth or width"))
+  }
+
+  /// Formatted value of the measurement, e.g. `12'-6 1/2"` or `120 SF`
+  #[napi]
+  pub fn display_value(&self, format: Option<DisplayFormat>) -> String {
+    self
+      .measurement
+      .display_value_with(&format.unwrap_or_default())
+  }
+
+  /// Combine this area with another into new area measurements, one per piece
+  #[napi]
+  pub fn boolean_op(&self, other: Measurement, op: BooleanOp) -> Result<Vec<Measurement>> {
+    boolean_op(&self.measurement, &other, op)
+      .ok_or_else(|| Error::from_reason("Both measurements must be area measurements"))
+  }
+
+  /// Turn a line or polyline into the area it covers at a total width centered on it
+  #[napi]
+  pub fn offset_linear(
+    &self,
+    width: Dimension,
+    options: Option<OffsetOptions>,
+  ) -> Result<Vec<Measurement>> {
+    offset_linear(&self.measurement, width, &options.unwrap_or_default()).ok_or_else(|| {
+      Error::from_reason("Measurement is not a linear measurement or the width is not positive")
+    })
+  }
+
+  /// Grow an area outward by a positive distance, or shrink it inward by a negative one
+  #[napi]
+  pub fn offset_area(
+    &self,
+    distance: Dimension,
+    options: Option<OffsetOptions>,
+  ) -> Result<Vec<Measurement>> {
+    offset_area(&self.measurement, distance, &options.unwrap_or_default())
+      .ok_or_else(|| Error::from_reason("Measurement is not an area measurement"))
+  }
+
+  /// Find self-intersections, repeated points, spikes and degenerate rings in this area
+  #[napi]
+  pub fn validate(&self) -> Result<Vec<ValidationIssue>> {
+    validate_area(&self.measurement)
+      .ok_or_else(|| Error::from_reason("Measurement is not an area measurement"))
+  }
+
+  /// Split this area into valid area measurements, one per part
+  #[napi]
+  pub fn repair(&self) -> Result<Vec<Measurement>> {
+    repair_area(&self.measurement)
+      .ok_or_else(|| Error::from_reason("Measurement is not an area measurement"))
+  }
+
+  /// Convert to another geometry kind, keeping the ID, scale and display unit
+  #[napi]
+  pub fn convert(&self, conversion: GeometryConversion) -> Result<Measurement> {
+    self.measurement.convert(conversion).ok_or_else(|| {
+      Error::from_reason("Measurement is not of the kind the conversion starts from")
+    })
+  }
+
+  /// New linear measurements around the edges of this area, one per ring
+  #[napi]
+  pub fn perimeter_measurements(&self) -> Result<Vec<Measurement>> {
+    self
+      .measurement
+      .perimeter_measurements()
+      .ok_or_else(|| Error::from_reason("Measurement is not an area measurement"))
+  }
 }
@@ -0,0 +1,20 @@
+use napi_derive::napi;
+use takeoff_core::{Scale, ScalePreset};
+
+/// Parse a printed scale such as `1/4" = 1'-0"`, `1" = 20'` or `1:100` for a page rendered at `dpi`
+#[napi]
+pub fn parse_scale(notation: String, dpi: f64) -> Option<Scale> {
+  Scale::parse(&notation, dpi)
+}
+
+/// Format a scale in conventional notation for a page rendered at `dpi`
+#[napi]
+pub fn format_scale(scale: Scale, dpi: f64) -> String {
+  scale.to_notation(dpi)
+}
+
+/// Standard architectural, engineering and metric scales
+#[napi]
+pub fn scale_presets() -> Vec<ScalePreset> {
+  ScalePreset::catalog()
+}
@@ -0,0 +1,32 @@
+use napi_derive::napi;
+use takeoff_core::{DisplayFormat, Pitch, Unit, UnitUtils, VolumeUnit};
+
+/// Format a length in `unit` for display, e.g. `12'-6 1/2"`
+#[napi]
+pub fn format_length(value: f64, unit: Unit, format: Option<DisplayFormat>) -> String {
+  format.unwrap_or_default().format_length(value, unit)
+}
+
+/// Format an area in square `unit` for display, e.g. `120 SF`
+#[napi]
+pub fn format_area(value: f64, unit: Unit, format: Option<DisplayFormat>) -> String {
+  format.unwrap_or_default().format_area(value, unit)
+}
+
+/// Format a volume for display, e.g. `12.50 CY`
+#[napi]
+pub fn format_volume(value: f64, unit: VolumeUnit, format: Option<DisplayFormat>) -> String {
+  format.unwrap_or_default().format_volume(value, unit)
+}
+
+/// Convert a volume between units
+#[napi]
+pub fn convert_volume(value: f64, from: VolumeUnit, to: VolumeUnit) -> f64 {
+  UnitUtils::convert_volume(value, from, to)
+}
+
+/// Parse a pitch such as `6/12` or `30°`
+#[napi]
+pub fn parse_pitch(text: String) -> Option<Pitch> {
+  Pitch::parse(&text)
+}
@@ -32,8 +32,13 @@ fn test_polygon_area() {
       Point::new(10.0, 5.0),
       Point::new(0.0, 5.0),
     ])),
+    circle: None,
+    ellipse: None,
     scale: Scale::new(1.0, 1.0, Unit::Meters),
     display_unit: Unit::Feet,
+    depth: None,
+    pitch: None,
+    metadata: None,
   };
   let area = polygon.pixel_area().unwrap();
   assert_eq!(area, 50.0);
@@ -52,8 +57,13 @@ fn test_calculate_irregular_polygon_area() {
       Point::new(5.0, 15.0),
       Point::new(0.0, 10.0),
     ])),
+    circle: None,
+    ellipse: None,
     scale: Scale::new(1.0, 1.0, Unit::Meters),
     display_unit: Unit::Feet,
+    depth: None,
+    pitch: None,
+    metadata: None,
   };
   let area = polygon.pixel_area().unwrap();
   assert_eq!(area, 100.0);
@@ -70,8 +80,13 @@ fn test_polygon_perimeter() {
       Point::new(10.0, 5.0),
       Point::new(0.0, 5.0),
     ])),
+    circle: None,
+    ellipse: None,
     scale: Scale::new(1.0, 1.0, Unit::Meters),
     display_unit: Unit::Feet,
+    depth: None,
+    pitch: None,
+    metadata: None,
   };
   let perimeter = polygon.pixel_perimeter().unwrap();
   assert_eq!(perimeter, 30.0);
@@ -109,6 +124,7 @@ fn test_takeoff_state() {
   let measurement = Measurement::Count {
     id: "test1".to_string(),
     point: Point::new(10.0, 20.0),
+    metadata: None,
   };
 
   state.add_measurement(measurement);